@@ -22,8 +22,17 @@ def sbi_dependencies():
             "flagset": crate.spec(
                 version = "0.4.3",
             ),
+            "proc-macro2": crate.spec(
+                version = "1.0",
+            ),
+            "quote": crate.spec(
+                version = "1.0",
+            ),
             "static_assertions": crate.spec(
                 version = "1.1",
             ),
+            "syn": crate.spec(
+                version = "2.0",
+            ),
         },
     )
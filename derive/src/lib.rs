@@ -0,0 +1,472 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! `#[derive(SbiFunction)]` for the function enums of SBI extensions.
+//!
+//! Each variant of the enum is one function of the extension and must be tagged with its function
+//! ID using `#[sbi(fid = ...)]`. Each field of a variant must be tagged with the register it's
+//! passed in using `#[sbi(reg = aN)]`, or with the registers it's split across using
+//! `#[sbi(regs(aN, aM, ...))]`. Single-register fields must implement `SbiReg` and multi-register
+//! fields `SbiRegs<N>`.
+//!
+//! The function ID is passed in a6 unless the enum is tagged with `#[sbi(fid_reg = a7)]`, as the
+//! legacy extensions are. A variant may be tagged with `#[sbi(check = path)]` to validate the
+//! parsed function with `fn path(&Self) -> Result<()>`.
+//!
+//! The derive generates:
+//! - an `SbiFunction` implementation returning each field in its register(s),
+//! - `from_regs()`, parsing the function back from `a0-a7`,
+//! - `fid()` and the `FUNCTIONS` table of function IDs and names,
+//! - a `VendorExtension` implementation with the given `EID` if the enum is tagged with
+//!   `#[sbi(vendor_eid = ...)]`.
+//!
+//! Duplicate function IDs and fields sharing a register are compile errors.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Error, Expr, Fields, Ident,
+    Member, Path, Result, Type,
+};
+
+/// Derives `SbiFunction` and `from_regs()` for an SBI function enum. See the crate documentation.
+#[proc_macro_derive(SbiFunction, attributes(sbi))]
+pub fn derive_sbi_function(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+// The number of argument registers, a0-a7.
+const NUM_REGS: usize = 8;
+
+struct FieldInfo {
+    member: Member,
+    ty: Type,
+    regs: Vec<usize>,
+}
+
+struct VariantInfo {
+    ident: Ident,
+    fid: Expr,
+    check: Option<Path>,
+    fields: Vec<FieldInfo>,
+}
+
+// Parses a register name, a0-a7, into its index.
+fn parse_reg(ident: &Ident) -> Result<usize> {
+    let name = ident.to_string();
+    name.strip_prefix('a')
+        .and_then(|n| n.parse::<usize>().ok())
+        .filter(|&n| n < NUM_REGS)
+        .ok_or_else(|| Error::new(ident.span(), "expected a register name, a0-a7"))
+}
+
+// Parses the enum attributes, returning the index of the function ID register and the vendor
+// extension ID, if any.
+fn parse_container_attrs(attrs: &[Attribute]) -> Result<(usize, Option<Expr>)> {
+    let mut fid_reg = 6;
+    let mut vendor_eid = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("sbi")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("fid_reg") {
+                let reg: Ident = meta.value()?.parse()?;
+                fid_reg = parse_reg(&reg)?;
+                if fid_reg < 6 {
+                    return Err(Error::new(
+                        reg.span(),
+                        "the function ID must be in a6 or a7",
+                    ));
+                }
+                Ok(())
+            } else if meta.path.is_ident("vendor_eid") {
+                vendor_eid = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown sbi enum attribute"))
+            }
+        })?;
+    }
+    Ok((fid_reg, vendor_eid))
+}
+
+fn parse_variant_attrs(attrs: &[Attribute], span: Span) -> Result<(Expr, Option<Path>)> {
+    let mut fid = None;
+    let mut check = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("sbi")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("fid") {
+                fid = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("check") {
+                check = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown sbi variant attribute"))
+            }
+        })?;
+    }
+    let fid = fid.ok_or_else(|| Error::new(span, "missing #[sbi(fid = ...)]"))?;
+    Ok((fid, check))
+}
+
+fn parse_field_attrs(attrs: &[Attribute], span: Span, fid_reg: usize) -> Result<Vec<usize>> {
+    let mut regs = Vec::new();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("sbi")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("reg") {
+                let reg: Ident = meta.value()?.parse()?;
+                regs.push((parse_reg(&reg)?, reg.span()));
+                Ok(())
+            } else if meta.path.is_ident("regs") {
+                meta.parse_nested_meta(|inner| {
+                    let reg = inner.path.require_ident()?;
+                    regs.push((parse_reg(reg)?, reg.span()));
+                    Ok(())
+                })
+            } else {
+                Err(meta.error("unknown sbi field attribute"))
+            }
+        })?;
+    }
+    if regs.is_empty() {
+        return Err(Error::new(
+            span,
+            "missing #[sbi(reg = ...)] or #[sbi(regs(...))]",
+        ));
+    }
+    for &(reg, span) in &regs {
+        if reg == fid_reg || reg == 7 {
+            return Err(Error::new(
+                span,
+                "register is reserved for the extension and function IDs",
+            ));
+        }
+    }
+    Ok(regs.into_iter().map(|(reg, _)| reg).collect())
+}
+
+fn parse_variants(input: &DeriveInput, fid_reg: usize) -> Result<Vec<VariantInfo>> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new(
+            input.span(),
+            "SbiFunction can only be derived for enums",
+        ));
+    };
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        let (fid, check) = parse_variant_attrs(&variant.attrs, variant.span())?;
+        let mut fields = Vec::new();
+        let mut used = [false; NUM_REGS];
+        let members: Vec<Member> = match &variant.fields {
+            Fields::Named(named) => named
+                .named
+                .iter()
+                .map(|f| Member::Named(f.ident.clone().unwrap()))
+                .collect(),
+            Fields::Unnamed(unnamed) => (0..unnamed.unnamed.len()).map(Member::from).collect(),
+            Fields::Unit => Vec::new(),
+        };
+        for (field, member) in variant.fields.iter().zip(members) {
+            let regs = parse_field_attrs(&field.attrs, field.span(), fid_reg)?;
+            for &reg in &regs {
+                if used[reg] {
+                    return Err(Error::new(
+                        field.span(),
+                        format!("register a{reg} is used by more than one field"),
+                    ));
+                }
+                used[reg] = true;
+            }
+            fields.push(FieldInfo {
+                member,
+                ty: field.ty.clone(),
+                regs,
+            });
+        }
+        variants.push(VariantInfo {
+            ident: variant.ident.clone(),
+            fid,
+            check,
+            fields,
+        });
+    }
+    Ok(variants)
+}
+
+// Returns an expression parsing `field` from `args`.
+fn field_from_regs(field: &FieldInfo) -> TokenStream {
+    let ty = &field.ty;
+    if let [reg] = field.regs[..] {
+        quote!(<#ty as ::sbi_rs::SbiReg>::from_reg(args[#reg])?)
+    } else {
+        let n = field.regs.len();
+        let regs = &field.regs;
+        quote!(<#ty as ::sbi_rs::SbiRegs<#n>>::from_regs([#(args[#regs]),*])?)
+    }
+}
+
+// Returns the method returning the value of register `reg` for each variant, or `None` if no
+// field is passed in `reg`.
+fn reg_method(name: &Ident, variants: &[VariantInfo], reg: usize) -> Option<TokenStream> {
+    let mut used = false;
+    let arms = variants.iter().map(|v| {
+        let ident = &v.ident;
+        let field = v.fields.iter().find_map(|f| {
+            let pos = f.regs.iter().position(|&r| r == reg)?;
+            Some((f, pos))
+        });
+        match field {
+            Some((f, pos)) => {
+                used = true;
+                let member = &f.member;
+                let ty = &f.ty;
+                let value = if f.regs.len() == 1 {
+                    quote!(<#ty as ::sbi_rs::SbiReg>::to_reg(f))
+                } else {
+                    let n = f.regs.len();
+                    quote!(<#ty as ::sbi_rs::SbiRegs<#n>>::to_regs(f)[#pos])
+                };
+                quote!(#name::#ident { #member: f, .. } => #value,)
+            }
+            None => quote!(#name::#ident { .. } => 0,),
+        }
+    });
+    let arms: Vec<_> = arms.collect();
+    let method = format_ident!("a{}", reg);
+    used.then(|| {
+        quote! {
+            fn #method(&self) -> u64 {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let (fid_reg, vendor_eid) = parse_container_attrs(&input.attrs)?;
+    let variants = parse_variants(input, fid_reg)?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fids: Vec<_> = variants.iter().map(|v| &v.fid).collect();
+    let idents: Vec<_> = variants.iter().map(|v| &v.ident).collect();
+    let names: Vec<_> = idents.iter().map(|i| i.to_string()).collect();
+    let num_fids = variants.len();
+    let duplicate_msg = format!("duplicate SBI function ID in {name}");
+
+    let parsers = variants.iter().map(|v| {
+        let ident = &v.ident;
+        let fid = &v.fid;
+        let members = v.fields.iter().map(|f| &f.member);
+        let values = v.fields.iter().map(field_from_regs);
+        let check = v.check.as_ref().map(|check| quote!(#check(&func)?;));
+        quote! {
+            if fid == (#fid) {
+                let func = #name::#ident { #(#members: #values),* };
+                #check
+                return Ok(func);
+            }
+        }
+    });
+
+    // The body of `from_regs`. Vendor extensions repeat it in their `VendorExtension::from_regs`
+    // rather than calling the inherent `from_regs`, which shares the trait method's name.
+    let decode = quote! {
+        let fid = args[#fid_reg];
+        #(#parsers)*
+        Err(::sbi_rs::Error::NotSupported)
+    };
+    let vendor_impl = vendor_eid.map(|eid| {
+        quote! {
+            #[automatically_derived]
+            impl #impl_generics ::sbi_rs::VendorExtension for #name #ty_generics #where_clause {
+                const EID: u64 = #eid;
+
+                fn from_regs(args: &[u64]) -> ::sbi_rs::Result<Self> {
+                    #decode
+                }
+            }
+        }
+    });
+
+    let fid_method = (fid_reg == 6).then(|| {
+        quote!(
+            fn a6(&self) -> u64 {
+                self.fid()
+            }
+        )
+    });
+    let reg_methods = (0..6).filter_map(|reg| reg_method(name, &variants, reg));
+
+    Ok(quote! {
+        const _: () = {
+            let fids: [u64; #num_fids] = [#(#fids),*];
+            let mut i = 0;
+            while i < fids.len() {
+                let mut j = i + 1;
+                while j < fids.len() {
+                    if fids[i] == fids[j] {
+                        ::core::panic!(#duplicate_msg);
+                    }
+                    j += 1;
+                }
+                i += 1;
+            }
+        };
+
+        #[automatically_derived]
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// The function IDs and names of the functions of this extension.
+            pub const FUNCTIONS: &'static [(u64, &'static str)] = &[#((#fids, #names)),*];
+
+            /// Attempts to parse `Self` from the passed in `a0-a7`.
+            #[allow(dead_code)]
+            pub(crate) fn from_regs(args: &[u64]) -> ::sbi_rs::Result<Self> {
+                #decode
+            }
+
+            /// Returns the function ID of this function.
+            pub fn fid(&self) -> u64 {
+                match self {
+                    #(#name::#idents { .. } => #fids,)*
+                }
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics ::sbi_rs::SbiFunction for #name #ty_generics #where_clause {
+            #fid_method
+            #(#reg_methods)*
+        }
+
+        #vendor_impl
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn expansion() {
+        let input: DeriveInput = parse_quote! {
+            enum Function {
+                #[sbi(fid = 0)]
+                Get,
+                #[sbi(fid = 1, check = check_set)]
+                Set {
+                    #[sbi(reg = a0)]
+                    value: u64,
+                },
+            }
+        };
+        let expected = quote! {
+            const _: () = {
+                let fids: [u64; 2usize] = [0, 1];
+                let mut i = 0;
+                while i < fids.len() {
+                    let mut j = i + 1;
+                    while j < fids.len() {
+                        if fids[i] == fids[j] {
+                            ::core::panic!("duplicate SBI function ID in Function");
+                        }
+                        j += 1;
+                    }
+                    i += 1;
+                }
+            };
+
+            #[automatically_derived]
+            impl Function {
+                /// The function IDs and names of the functions of this extension.
+                pub const FUNCTIONS: &'static [(u64, &'static str)] = &[(0, "Get"), (1, "Set")];
+
+                /// Attempts to parse `Self` from the passed in `a0-a7`.
+                #[allow(dead_code)]
+                pub(crate) fn from_regs(args: &[u64]) -> ::sbi_rs::Result<Self> {
+                    let fid = args[6usize];
+                    if fid == (0) {
+                        let func = Function::Get {};
+                        return Ok(func);
+                    }
+                    if fid == (1) {
+                        let func = Function::Set {
+                            value: <u64 as ::sbi_rs::SbiReg>::from_reg(args[0usize])?
+                        };
+                        check_set(&func)?;
+                        return Ok(func);
+                    }
+                    Err(::sbi_rs::Error::NotSupported)
+                }
+
+                /// Returns the function ID of this function.
+                pub fn fid(&self) -> u64 {
+                    match self {
+                        Function::Get { .. } => 0,
+                        Function::Set { .. } => 1,
+                    }
+                }
+            }
+
+            #[automatically_derived]
+            impl ::sbi_rs::SbiFunction for Function {
+                fn a6(&self) -> u64 {
+                    self.fid()
+                }
+                fn a0(&self) -> u64 {
+                    match self {
+                        Function::Get { .. } => 0,
+                        Function::Set { value: f, .. } => <u64 as ::sbi_rs::SbiReg>::to_reg(f),
+                    }
+                }
+            }
+        };
+        assert_eq!(expand(&input).unwrap().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn vendor_extension() {
+        let input: DeriveInput = parse_quote! {
+            #[sbi(vendor_eid = 0x0900_0000)]
+            enum Function {
+                #[sbi(fid = 0)]
+                Get,
+            }
+        };
+        let expected = quote! {
+            #[automatically_derived]
+            impl ::sbi_rs::VendorExtension for Function {
+                const EID: u64 = 0x0900_0000;
+
+                fn from_regs(args: &[u64]) -> ::sbi_rs::Result<Self> {
+                    let fid = args[6usize];
+                    if fid == (0) {
+                        let func = Function::Get {};
+                        return Ok(func);
+                    }
+                    Err(::sbi_rs::Error::NotSupported)
+                }
+            }
+        };
+        // The vendor extension decodes the registers itself rather than calling `from_regs`.
+        let output = expand(&input).unwrap().to_string();
+        assert!(output.ends_with(&expected.to_string()), "{output}");
+    }
+
+    #[test]
+    fn rejects_reserved_registers() {
+        let input: DeriveInput = parse_quote! {
+            enum Function {
+                #[sbi(fid = 0)]
+                Call(#[sbi(reg = a6)] u64),
+            }
+        };
+        assert!(expand(&input).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+use sbi_rs::SbiFunction;
+
+#[derive(SbiFunction)]
+pub enum Function {
+    #[sbi(fid = 0, check = no_such_check)]
+    Call(#[sbi(reg = a0)] u64),
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use sbi_rs::SbiFunction;
+
+#[derive(SbiFunction)]
+pub enum Function {
+    #[sbi(fid = 0)]
+    First,
+    #[sbi(fid = 0)]
+    Second,
+}
+
+fn main() {}
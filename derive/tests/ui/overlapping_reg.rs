@@ -0,0 +1,14 @@
+use sbi_rs::SbiFunction;
+
+#[derive(SbiFunction)]
+pub enum Function {
+    #[sbi(fid = 0)]
+    Call {
+        #[sbi(reg = a0)]
+        first: u64,
+        #[sbi(reg = a0)]
+        second: u64,
+    },
+}
+
+fn main() {}
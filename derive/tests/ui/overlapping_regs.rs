@@ -0,0 +1,14 @@
+use sbi_rs::SbiFunction;
+
+#[derive(SbiFunction)]
+pub enum Function {
+    #[sbi(fid = 0)]
+    Call {
+        #[sbi(regs(a0, a1))]
+        range: u64,
+        #[sbi(reg = a1)]
+        len: u64,
+    },
+}
+
+fn main() {}
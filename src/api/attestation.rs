@@ -14,16 +14,16 @@ pub const MAX_CERT_SIZE: usize = 4096;
 
 /// Get the attestation capabilities.
 pub fn get_capabilities() -> Result<AttestationCapabilities> {
-    let caps = AttestationCapabilities::default();
+    let mut caps = [0u8; AttestationCapabilities::SIZE];
     let msg = SbiMessage::Attestation(AttestationFunction::GetCapabilities {
-        caps_addr_out: (&caps as *const AttestationCapabilities) as u64,
-        caps_size: core::mem::size_of::<AttestationCapabilities>() as u64,
+        caps_addr_out: caps.as_mut_ptr() as u64,
+        caps_size: AttestationCapabilities::SIZE as u64,
     });
 
     // Safety: &caps is the single reference to a variable defined in this scope.
     unsafe { ecall_send::<()>(&msg) }?;
 
-    Ok(caps)
+    AttestationCapabilities::from_bytes(&caps)
 }
 
 /// Get an attestation evidence.
@@ -53,7 +53,7 @@ pub fn get_evidence(
         cert_request_size: cert_request.len() as u64,
         request_data_addr: request_data.as_ptr() as u64,
         evidence_format: evidence_format as u64,
-        cert_addr_out: (cert_bytes.as_ptr()) as u64,
+        cert_addr_out: cert_bytes.as_mut_ptr() as u64,
         cert_size: MAX_CERT_SIZE as u64,
     });
 
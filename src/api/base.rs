@@ -60,3 +60,13 @@ pub fn get_machine_implementation_id() -> Result<u64> {
     // Safety: This ecall doesn't touch memory
     unsafe { ecall_send(&msg) }
 }
+
+/// Creates a message for a firmware-specific extension defined by the SBI implementation with ID
+/// `impl_id`. Returns `NotSupported` if the running SBI implementation has a different ID, since
+/// firmware-specific extension IDs mean different things to different implementations.
+pub fn firmware_specific_message(impl_id: u64, regs: [u64; 8]) -> Result<SbiMessage> {
+    if get_implementation_id()? != impl_id {
+        return Err(SbiError::NotSupported);
+    }
+    SbiMessage::firmware_specific(impl_id, regs)
+}
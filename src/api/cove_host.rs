@@ -103,20 +103,19 @@ pub fn tvm_initiate_fence(vmid: u64) -> Result<()> {
 
 /// Returns information about the TSM that booted this host context.
 pub fn get_info() -> Result<TsmInfo> {
-    let mut tsm_info = TsmInfo::default();
-    let tsm_info_size = core::mem::size_of::<TsmInfo>() as u64;
+    let mut tsm_info = [0u8; TsmInfo::SIZE];
     let msg = SbiMessage::CoveHost(TsmGetInfo {
-        dest_addr: &mut tsm_info as *mut _ as u64,
-        len: tsm_info_size,
+        dest_addr: tsm_info.as_mut_ptr() as u64,
+        len: TsmInfo::SIZE as u64,
     });
-    // Safety: The passed info pointer is uniquely owned so it's safe to modify in SBI.
+    // Safety: The passed info buffer is uniquely owned so it's safe to modify in SBI.
     let tsm_info_len: u64 = unsafe { ecall_send(&msg)? };
 
-    if tsm_info_len != tsm_info_size {
+    if tsm_info_len != TsmInfo::SIZE as u64 {
         return Err(Error::Failed);
     }
 
-    Ok(tsm_info)
+    TsmInfo::from_bytes(&tsm_info)
 }
 
 /// Converts the given page range to confidential memory for use in creating or filling pages of a
@@ -0,0 +1,44 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::CppcFunction::*;
+use crate::{ecall_send, CppcRegister, Error, Result, SbiMessage};
+
+/// Returns the width in bits of the given CPPC register.
+/// A return value of 0 (which denotes an unimplemented register per the documentation)
+/// is automatically converted into NotSupported error.
+pub fn probe(reg_id: CppcRegister) -> Result<u64> {
+    let msg = SbiMessage::Cppc(Probe { reg_id });
+    // Safety: This ecall doesn't touch memory
+    let width = unsafe { ecall_send(&msg) }?;
+    if width == 0 {
+        Err(Error::NotSupported)
+    } else {
+        Ok(width)
+    }
+}
+
+/// Reads the full value of the given CPPC register. On RV32 the upper 32 bits are fetched with a
+/// separate `ReadHi` call.
+pub fn read(reg_id: CppcRegister) -> Result<u64> {
+    let msg = SbiMessage::Cppc(Read { reg_id });
+    // Safety: This ecall doesn't touch memory
+    let lo: u64 = unsafe { ecall_send(&msg) }?;
+    if cfg!(target_pointer_width = "32") {
+        let msg = SbiMessage::Cppc(ReadHi { reg_id });
+        // Safety: This ecall doesn't touch memory
+        let hi: u64 = unsafe { ecall_send(&msg) }?;
+        Ok((lo & 0xffff_ffff) | (hi << 32))
+    } else {
+        Ok(lo)
+    }
+}
+
+/// Writes `value` to the given CPPC register.
+pub fn write(reg_id: CppcRegister, value: u64) -> Result<()> {
+    let msg = SbiMessage::Cppc(Write { reg_id, value });
+    // Safety: This ecall doesn't touch memory
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
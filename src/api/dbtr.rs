@@ -0,0 +1,160 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::split_addr;
+use crate::DbtrFunction::*;
+use crate::{
+    ecall_send, DbtrShmemEntry, DbtrTriggerData, DbtrTriggerId, Error, Result, SbiMessage,
+};
+
+/// Returns the number of debug triggers on this CPU that support the configuration in `tdata1`,
+/// or the total number of triggers if `tdata1` is 0.
+pub fn num_triggers(tdata1: u64) -> Result<u64> {
+    let msg = SbiMessage::Dbtr(NumTriggers {
+        trig_tdata1: tdata1,
+    });
+    // Safety: NumTriggers doesn't touch memory.
+    unsafe { ecall_send(&msg) }
+}
+
+/// Uninstalls the triggers selected by `idx_base` and `idx_mask`, freeing their indices.
+pub fn uninstall(idx_base: u64, idx_mask: u64) -> Result<()> {
+    let msg = SbiMessage::Dbtr(UninstallTriggers {
+        trig_idx_base: idx_base,
+        trig_idx_mask: idx_mask,
+    });
+    // Safety: UninstallTriggers doesn't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Enables the triggers selected by `idx_base` and `idx_mask`.
+pub fn enable(idx_base: u64, idx_mask: u64) -> Result<()> {
+    let msg = SbiMessage::Dbtr(EnableTriggers {
+        trig_idx_base: idx_base,
+        trig_idx_mask: idx_mask,
+    });
+    // Safety: EnableTriggers doesn't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Disables the triggers selected by `idx_base` and `idx_mask`.
+pub fn disable(idx_base: u64, idx_mask: u64) -> Result<()> {
+    let msg = SbiMessage::Dbtr(DisableTriggers {
+        trig_idx_base: idx_base,
+        trig_idx_mask: idx_mask,
+    });
+    // Safety: DisableTriggers doesn't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// The debug trigger shared memory registered for the calling CPU. Used to read, install and update
+/// triggers. The shared memory is unregistered when this is dropped.
+pub struct DebugTriggers<'a> {
+    shmem: &'a mut [DbtrShmemEntry],
+}
+
+impl<'a> DebugTriggers<'a> {
+    /// Registers `shmem` as the debug trigger shared memory for the calling CPU. At most
+    /// `shmem.len()` triggers can be read, installed or updated in a single call.
+    ///
+    /// # Safety
+    ///
+    /// The address of `shmem` is passed to the SBI implementation as a physical address, so the
+    /// caller must guarantee that `shmem` is identity mapped: its virtual address must equal its
+    /// physical address for as long as it's registered.
+    pub unsafe fn new(shmem: &'a mut [DbtrShmemEntry]) -> Result<Self> {
+        let (shmem_lo, shmem_hi) = split_addr(shmem.as_mut_ptr() as u64);
+        let msg = SbiMessage::Dbtr(SetShmem {
+            shmem_lo,
+            shmem_hi,
+            flags: 0,
+        });
+        // Safety: `shmem` is uniquely borrowed for as long as it's registered, the caller guaranteed
+        // it's identity mapped, and the SBI implementation only accesses it while handling the DBTR
+        // calls made through `self`.
+        unsafe { ecall_send::<()>(&msg) }?;
+        Ok(Self { shmem })
+    }
+
+    fn check_count(&self, count: usize) -> Result<u64> {
+        if count > self.shmem.len() {
+            return Err(Error::InvalidParam);
+        }
+        Ok(count as u64)
+    }
+
+    /// Reads the state and configuration of `out.len()` triggers starting at `idx_base` into
+    /// `out`.
+    pub fn read(&mut self, idx_base: u64, out: &mut [DbtrTriggerData]) -> Result<()> {
+        let msg = SbiMessage::Dbtr(ReadTriggers {
+            trig_idx_base: idx_base,
+            trig_count: self.check_count(out.len())?,
+        });
+        // Safety: ReadTriggers only writes the first `out.len()` entries of the shared memory,
+        // which is uniquely borrowed by `self`.
+        unsafe { ecall_send::<()>(&msg) }?;
+        for (data, entry) in out.iter_mut().zip(self.shmem.iter()) {
+            // Safety: Every bit pattern is a valid `DbtrTriggerData`.
+            *data = unsafe { entry.data };
+        }
+        Ok(())
+    }
+
+    /// Installs the given triggers and writes the index assigned to each into `indices`, which
+    /// must be the same length as `triggers`.
+    pub fn install(&mut self, triggers: &[DbtrTriggerData], indices: &mut [usize]) -> Result<()> {
+        if triggers.len() != indices.len() {
+            return Err(Error::InvalidParam);
+        }
+        let msg = SbiMessage::Dbtr(InstallTriggers {
+            trig_count: self.check_count(triggers.len())?,
+        });
+        for (entry, data) in self.shmem.iter_mut().zip(triggers) {
+            entry.data = *data;
+        }
+        // Safety: InstallTriggers only reads and writes the first `triggers.len()` entries of the
+        // shared memory, which is uniquely borrowed by `self`.
+        unsafe { ecall_send::<()>(&msg) }?;
+        for (idx, entry) in indices.iter_mut().zip(self.shmem.iter()) {
+            // Safety: Every bit pattern is a valid `DbtrTriggerId`.
+            *idx = unsafe { entry.id.idx };
+        }
+        Ok(())
+    }
+
+    /// Installs a single trigger and returns its index.
+    pub fn install_one(&mut self, trigger: DbtrTriggerData) -> Result<usize> {
+        let mut idx = [0];
+        self.install(&[trigger], &mut idx)?;
+        Ok(idx[0])
+    }
+
+    /// Replaces the configuration of the installed trigger at `idx` with `trigger`.
+    pub fn update(&mut self, idx: usize, trigger: DbtrTriggerData) -> Result<()> {
+        let msg = SbiMessage::Dbtr(UpdateTriggers {
+            trig_count: self.check_count(1)?,
+        });
+        self.shmem[0].data = trigger;
+        self.shmem[0].id = DbtrTriggerId { idx };
+        // Safety: UpdateTriggers only reads the first entry of the shared memory, which is
+        // uniquely borrowed by `self`.
+        unsafe { ecall_send::<()>(&msg) }?;
+        Ok(())
+    }
+}
+
+impl Drop for DebugTriggers<'_> {
+    fn drop(&mut self) {
+        let msg = SbiMessage::Dbtr(SetShmem {
+            shmem_lo: u64::MAX,
+            shmem_hi: u64::MAX,
+            flags: 0,
+        });
+        // Safety: Doesn't access our memory. There's nothing to be done if unregistering fails.
+        let _ = unsafe { ecall_send::<()>(&msg) };
+    }
+}
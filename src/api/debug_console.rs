@@ -2,18 +2,207 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{ecall_send, DebugConsoleFunction, Result, SbiMessage};
+use core::fmt;
 
-/// Prints the given string in a platfrom-dependent way.
-pub fn console_puts(chars: &[u8]) -> Result<()> {
+use super::split_addr;
+use crate::{ecall_send, DebugConsoleFunction, Error, Result, SbiMessage};
+
+/// Writes as many bytes of `chars` as the console accepts in a single call and returns the number
+/// of bytes written.
+///
+/// # Safety
+///
+/// The address of `chars` is passed to the SBI implementation as a physical address, so the caller
+/// must guarantee that `chars` is identity mapped: its virtual address must equal its physical
+/// address.
+pub unsafe fn console_write(chars: &[u8]) -> Result<usize> {
+    let (addr, addr_hi) = split_addr(chars.as_ptr() as u64);
     let msg = SbiMessage::DebugConsole(DebugConsoleFunction::Write {
         len: chars.len() as u64,
-        addr: chars.as_ptr() as u64,
-        addr_hi: 0,
+        addr,
+        addr_hi,
     });
 
-    // Safety: The sbi implementation is trusted not to write memory when printing to the console.
+    // Safety: The caller guaranteed `chars` is identity mapped, and the sbi implementation is
+    // trusted not to write memory when printing to the console.
+    let written: usize = unsafe { ecall_send(&msg) }?;
+    if written > chars.len() {
+        return Err(Error::Failed);
+    }
+
+    Ok(written)
+}
+
+/// Prints the given string in a platfrom-dependent way.
+///
+/// # Safety
+///
+/// `chars` must be identity mapped, as for `console_write`.
+pub unsafe fn console_puts(mut chars: &[u8]) -> Result<()> {
+    while !chars.is_empty() {
+        // Safety: The caller guaranteed `chars` is identity mapped.
+        let written = unsafe { console_write(chars) }?;
+        if written == 0 {
+            // Bail out rather than spin forever on a console that makes no progress.
+            return Err(Error::Failed);
+        }
+        chars = &chars[written..];
+    }
+
+    Ok(())
+}
+
+/// Reads up to `buf.len()` bytes from the console into `buf` without blocking. Returns the number
+/// of bytes read, which may be 0.
+///
+/// # Safety
+///
+/// The address of `buf` is passed to the SBI implementation as a physical address, so the caller
+/// must guarantee that `buf` is identity mapped: its virtual address must equal its physical
+/// address.
+pub unsafe fn console_read(buf: &mut [u8]) -> Result<usize> {
+    let (addr, addr_hi) = split_addr(buf.as_mut_ptr() as u64);
+    let msg = SbiMessage::DebugConsole(DebugConsoleFunction::Read {
+        len: buf.len() as u64,
+        addr,
+        addr_hi,
+    });
+
+    // Safety: Read only writes to the pages pointed to by `buf`, which is the single mutable
+    // reference to that memory and was guaranteed by the caller to be identity mapped.
+    let read: usize = unsafe { ecall_send(&msg) }?;
+    if read > buf.len() {
+        return Err(Error::Failed);
+    }
+
+    Ok(read)
+}
+
+/// Writes a single byte to the console.
+pub fn console_write_byte(byte: u8) -> Result<()> {
+    let msg = SbiMessage::DebugConsole(DebugConsoleFunction::WriteByte { byte: byte as u64 });
+
+    // Safety: WriteByte doesn't touch memory.
     unsafe { ecall_send::<()>(&msg) }?;
 
     Ok(())
 }
+
+/// The SBI debug console, for use with `core::fmt::Write`.
+pub struct DebugConsole {
+    _private: (),
+}
+
+impl DebugConsole {
+    /// Creates a handle to the SBI debug console.
+    ///
+    /// # Safety
+    ///
+    /// Strings are passed to the SBI implementation by physical address, so the caller must
+    /// guarantee that every string written through the returned console is identity mapped. This
+    /// includes the stack, where formatted output is assembled.
+    pub unsafe fn new() -> Self {
+        Self { _private: () }
+    }
+}
+
+impl fmt::Write for DebugConsole {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // Safety: The creator of `self` guaranteed that strings written through it are identity
+        // mapped.
+        unsafe { console_puts(s.as_bytes()) }.map_err(|_| fmt::Error)
+    }
+}
+
+#[doc(hidden)]
+pub unsafe fn _print(args: fmt::Arguments) {
+    use fmt::Write;
+    // Safety: The caller of `sbi_print!` guaranteed that the stack and `args` are identity mapped.
+    let mut console = unsafe { DebugConsole::new() };
+    // There's nowhere to report a failure to print.
+    let _ = console.write_fmt(args);
+}
+
+/// Prints to the SBI debug console.
+///
+/// # Safety
+///
+/// Must be invoked in an `unsafe` block. The formatted output is passed to the SBI implementation
+/// by physical address, so the caller must guarantee that the stack and the formatted arguments
+/// are identity mapped, as for `DebugConsole::new`.
+#[macro_export]
+macro_rules! sbi_print {
+    ($($arg:tt)*) => {
+        $crate::api::debug_console::_print(format_args!($($arg)*))
+    };
+}
+
+/// Prints to the SBI debug console, with a newline.
+///
+/// # Safety
+///
+/// Must be invoked in an `unsafe` block, under the same identity-mapping requirement as
+/// `sbi_print!`.
+#[macro_export]
+macro_rules! sbi_println {
+    () => {
+        $crate::sbi_print!("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::api::debug_console::_print(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockReply, MockSbi};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    #[test]
+    fn puts_partial_writes() {
+        let mock = MockSbi::install();
+        mock.push_reply(MockReply::success(3));
+        mock.push_reply(MockReply::success(2));
+        // Safety: The mock reads the strings from their virtual addresses.
+        assert_eq!(unsafe { console_puts(b"hello") }, Ok(()));
+        let lens: Vec<u64> = mock
+            .calls()
+            .iter()
+            .map(|msg| match msg {
+                SbiMessage::DebugConsole(DebugConsoleFunction::Write { len, .. }) => *len,
+                _ => panic!("unexpected call {msg:?}"),
+            })
+            .collect();
+        assert_eq!(lens, [5, 2]);
+
+        // A console that makes no progress, or claims to write more than it was given.
+        mock.push_reply(MockReply::success(0));
+        assert_eq!(unsafe { console_puts(b"hello") }, Err(Error::Failed));
+        mock.push_reply(MockReply::success(6));
+        assert_eq!(unsafe { console_write(b"hello") }, Err(Error::Failed));
+        mock.push_reply(MockReply::success(2));
+        assert_eq!(unsafe { console_read(&mut [0; 1]) }, Err(Error::Failed));
+    }
+
+    #[test]
+    fn println() {
+        let mock = MockSbi::install();
+        let printed = Rc::new(RefCell::new(Vec::new()));
+        let output = printed.clone();
+        mock.set_handler(move |msg| match *msg {
+            SbiMessage::DebugConsole(DebugConsoleFunction::Write { len, addr, .. }) => {
+                // Safety: `console_write` passes a valid string of `len` bytes at `addr`.
+                let chars = unsafe { core::slice::from_raw_parts(addr as *const u8, len as usize) };
+                output.borrow_mut().extend_from_slice(chars);
+                MockReply::success(len as i64)
+            }
+            _ => panic!("unexpected call {msg:?}"),
+        });
+        // Safety: The mock reads the string from its virtual address.
+        unsafe { crate::sbi_println!("{} + {} = {}", 1, 2, 3) };
+        assert_eq!(printed.borrow().as_slice(), b"1 + 2 = 3\n");
+    }
+}
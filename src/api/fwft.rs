@@ -0,0 +1,28 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use flagset::FlagSet;
+
+use crate::FwftFunction::*;
+use crate::{ecall_send, FwftFeature, FwftFlag, Result, SbiMessage};
+
+/// Sets the value of the given firmware feature. Passing `FwftFlag::Lock` in `flags` prevents
+/// further changes until the next hart reset.
+pub fn set(feature: FwftFeature, value: u64, flags: impl Into<FlagSet<FwftFlag>>) -> Result<()> {
+    let msg = SbiMessage::Fwft(Set {
+        feature,
+        value,
+        flags: flags.into(),
+    });
+    // Safety: Set doesn't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Returns the current value of the given firmware feature.
+pub fn get(feature: FwftFeature) -> Result<u64> {
+    let msg = SbiMessage::Fwft(Get { feature });
+    // Safety: Get doesn't touch memory.
+    unsafe { ecall_send(&msg) }
+}
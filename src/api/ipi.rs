@@ -0,0 +1,19 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{ecall_send, HartMask, IpiFunction, Result, SbiMessage};
+
+/// Sends a supervisor software interrupt to the harts selected by `hart_mask`.
+pub fn send_ipi(hart_mask: HartMask) -> Result<()> {
+    let msg = SbiMessage::Ipi(IpiFunction::send_ipi(hart_mask));
+    // Safety: SendIpi doesn't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Sends a supervisor software interrupt to each hart in `hart_ids`, issuing as many `send_ipi`
+/// calls as needed to cover all of them. Stops at the first error.
+pub fn send_ipi_to_harts<I: IntoIterator<Item = u64>>(hart_ids: I) -> Result<()> {
+    HartMask::from_hart_ids(hart_ids).try_for_each(send_ipi)
+}
@@ -0,0 +1,22 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::LegacyFunction::*;
+use crate::{ecall_send, Result, SbiMessage};
+
+/// Writes the byte `ch` to the debug console with the legacy console_putchar extension.
+pub fn console_putchar(ch: u8) -> Result<()> {
+    let msg = SbiMessage::PutChar(ch as u64);
+    // Safety: PutChar doesn't touch memory.
+    unsafe { ecall_send::<()>(&msg) }
+}
+
+/// Reads a byte from the debug console with the legacy console_getchar extension. Returns `None`
+/// if there is no byte to read.
+pub fn console_getchar() -> Result<Option<u8>> {
+    let msg = SbiMessage::Legacy(GetChar);
+    // Safety: GetChar doesn't touch memory.
+    let c: i64 = unsafe { ecall_send(&msg) }?;
+    Ok(u8::try_from(c).ok())
+}
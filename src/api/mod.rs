@@ -2,15 +2,28 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-/// Debug Console for printing strings through SBI.
+/// Debug Console for printing strings through SBI. Buffers are passed to the SBI implementation by
+/// physical address, so they must be identity mapped.
 pub mod debug_console;
 
 /// Host interfaces for reset extension.
 pub mod reset;
 
+/// Host interfaces for system suspend.
+pub mod susp;
+
 /// Host interfaces for hart state management.
 pub mod state;
 
+/// Supervisor timer interfaces.
+pub mod time;
+
+/// Inter-processor interrupt interfaces.
+pub mod ipi;
+
+/// Remote fence interfaces.
+pub mod rfence;
+
 /// Host interfaces for nested virtualization acceleration.
 pub mod nacl;
 
@@ -26,11 +39,44 @@ pub mod cove_guest;
 /// Host interfaces for PMU.
 pub mod pmu;
 
+/// Interfaces for collaborative processor performance control.
+pub mod cppc;
+
+/// Guest interfaces for steal-time accounting.
+pub mod sta;
+
+/// Interfaces for getting/setting firmware features.
+pub mod fwft;
+
+/// Interfaces for hardware breakpoints and watchpoints.
+pub mod dbtr;
+
+/// Interfaces for handling supervisor software events.
+pub mod sse;
+
+/// Interfaces for sending messages to platform services through the SBI message proxy.
+pub mod mpxy;
+
 /// Base SBI inferfaces.
 pub mod base;
 
+/// Interfaces to the legacy (v0.1) console extensions.
+pub mod legacy;
+
 /// Host interfaces for attestation.
 pub mod attestation;
 
 /// Salus vendor extensions.
 pub mod salus;
+
+// Splits `addr` into the lower and upper register values used to pass a physical address to SBI.
+#[cfg(target_pointer_width = "32")]
+fn split_addr(addr: u64) -> (u64, u64) {
+    (addr & 0xffff_ffff, addr >> 32)
+}
+
+// Splits `addr` into the lower and upper register values used to pass a physical address to SBI.
+#[cfg(not(target_pointer_width = "32"))]
+fn split_addr(addr: u64) -> (u64, u64) {
+    (addr, 0)
+}
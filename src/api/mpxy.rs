@@ -0,0 +1,381 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use core::mem::{size_of, MaybeUninit};
+use core::slice;
+
+use super::split_addr;
+use crate::MpxyFunction::*;
+use crate::{
+    ecall_send, Error, MpxyAttrId, MpxyChannelIdsHeader, MpxyNotificationHeader, Result,
+    SbiMessage, MPXY_SHMEM_ALIGN,
+};
+
+/// Types that can be sent and received as MPXY message data by `Channel::request` and
+/// `Channel::send`.
+///
+/// # Safety
+///
+/// Implementors must have no padding bytes and every bit pattern of `size_of::<Self>()` bytes must
+/// be a valid value of the type.
+pub unsafe trait MessageData: Copy {}
+
+// Safety: Integers and arrays of `MessageData` have no padding and no invalid bit patterns.
+unsafe impl MessageData for u8 {}
+unsafe impl MessageData for u16 {}
+unsafe impl MessageData for u32 {}
+unsafe impl MessageData for u64 {}
+unsafe impl MessageData for i8 {}
+unsafe impl MessageData for i16 {}
+unsafe impl MessageData for i32 {}
+unsafe impl MessageData for i64 {}
+unsafe impl<T: MessageData, const N: usize> MessageData for [T; N] {}
+
+fn as_bytes<T: MessageData>(data: &T) -> &[u8] {
+    // Safety: `MessageData` types have no padding, so all `size_of::<T>()` bytes are initialized.
+    unsafe { slice::from_raw_parts(data as *const T as *const u8, size_of::<T>()) }
+}
+
+// Reads the little-endian u32 at index `idx` of the u32 array in `buf`.
+fn read_word(buf: &[u8], idx: usize) -> Result<u32> {
+    let bytes = buf.get(idx * 4..idx * 4 + 4).ok_or(Error::Failed)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Returns the size in bytes of the shared memory that must be passed to `MpxyShmem::new`.
+pub fn shmem_size() -> Result<usize> {
+    let msg = SbiMessage::Mpxy(GetShmemSize);
+    // Safety: GetShmemSize doesn't touch memory.
+    unsafe { ecall_send(&msg) }
+}
+
+/// The MPXY shared memory registered for the calling CPU, through which all message data and
+/// attributes are exchanged. The shared memory is unregistered when this is dropped.
+pub struct MpxyShmem<'a> {
+    buf: &'a mut [u8],
+}
+
+impl<'a> MpxyShmem<'a> {
+    /// Registers `buf` as the MPXY shared memory for the calling CPU. `buf` must be 4KiB aligned
+    /// and at least `shmem_size()` bytes long.
+    ///
+    /// # Safety
+    ///
+    /// The address of `buf` is passed to the SBI implementation as a physical address, so the
+    /// caller must guarantee that `buf` is identity mapped: its virtual address must equal its
+    /// physical address for as long as it's registered.
+    pub unsafe fn new(buf: &'a mut [u8]) -> Result<Self> {
+        let size = shmem_size()?;
+        if buf.as_ptr() as u64 % MPXY_SHMEM_ALIGN != 0 || buf.len() < size {
+            return Err(Error::InvalidParam);
+        }
+        let buf = &mut buf[..size];
+        let (shmem_phys_lo, shmem_phys_hi) = split_addr(buf.as_mut_ptr() as u64);
+        let msg = SbiMessage::Mpxy(SetShmem {
+            shmem_phys_lo,
+            shmem_phys_hi,
+            flags: 0,
+        });
+        // Safety: `buf` is uniquely borrowed for as long as it's registered, the caller guaranteed
+        // it's identity mapped, and the SBI implementation only accesses it while handling the MPXY
+        // calls made through `self`.
+        unsafe { ecall_send::<()>(&msg) }?;
+        Ok(Self { buf })
+    }
+
+    /// Fills `ids` with the IDs of the available channels and returns the number of IDs written.
+    /// Stops early if `ids` is too small to hold all of them.
+    pub fn channel_ids(&mut self, ids: &mut [u32]) -> Result<usize> {
+        const HEADER_WORDS: usize = size_of::<MpxyChannelIdsHeader>() / 4;
+        let mut count = 0;
+        while count < ids.len() {
+            let msg = SbiMessage::Mpxy(GetChannelIds {
+                start_index: count as u64,
+            });
+            // Safety: GetChannelIds only writes the shared memory, which is uniquely borrowed by
+            // `self`.
+            unsafe { ecall_send::<()>(&msg) }?;
+            let header = MpxyChannelIdsHeader {
+                remaining: read_word(self.buf, 0)?,
+                returned: read_word(self.buf, 1)?,
+            };
+            let returned = (header.returned as usize).min(ids.len() - count);
+            for i in 0..returned {
+                ids[count + i] = read_word(self.buf, HEADER_WORDS + i)?;
+            }
+            count += returned;
+            if header.remaining == 0 || header.returned == 0 {
+                break;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Returns a handle for sending messages over the channel with the given ID.
+    pub fn channel(&mut self, channel_id: u32) -> Channel<'_> {
+        Channel {
+            buf: self.buf,
+            channel_id,
+        }
+    }
+}
+
+impl Drop for MpxyShmem<'_> {
+    fn drop(&mut self) {
+        let msg = SbiMessage::Mpxy(SetShmem {
+            shmem_phys_lo: u64::MAX,
+            shmem_phys_hi: u64::MAX,
+            flags: 0,
+        });
+        // Safety: Doesn't access our memory. There's nothing to be done if unregistering fails.
+        let _ = unsafe { ecall_send::<()>(&msg) };
+    }
+}
+
+/// A handle to an MPXY channel, borrowing the registered shared memory for the duration of each
+/// call.
+pub struct Channel<'a> {
+    buf: &'a mut [u8],
+    channel_id: u32,
+}
+
+impl Channel<'_> {
+    /// Returns the ID of this channel.
+    pub fn id(&self) -> u32 {
+        self.channel_id
+    }
+
+    /// Reads `values.len()` attributes of the channel, starting at `base_attr_id`, into `values`.
+    pub fn read_attributes(&mut self, base_attr_id: MpxyAttrId, values: &mut [u32]) -> Result<()> {
+        if values.len() * 4 > self.buf.len() {
+            return Err(Error::InvalidParam);
+        }
+        let msg = SbiMessage::Mpxy(ReadAttributes {
+            channel_id: self.channel_id as u64,
+            base_attr_id,
+            attr_count: values.len() as u64,
+        });
+        // Safety: ReadAttributes only writes the shared memory, which is uniquely borrowed by
+        // `self`.
+        unsafe { ecall_send::<()>(&msg) }?;
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = read_word(self.buf, i)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the value of a single attribute of the channel.
+    pub fn read_attribute(&mut self, attr_id: MpxyAttrId) -> Result<u32> {
+        let mut value = [0];
+        self.read_attributes(attr_id, &mut value)?;
+        Ok(value[0])
+    }
+
+    /// Writes `values.len()` attributes of the channel, starting at `base_attr_id`, from `values`.
+    pub fn write_attributes(&mut self, base_attr_id: MpxyAttrId, values: &[u32]) -> Result<()> {
+        if values.len() * 4 > self.buf.len() {
+            return Err(Error::InvalidParam);
+        }
+        for (chunk, value) in self.buf.chunks_exact_mut(4).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        let msg = SbiMessage::Mpxy(WriteAttributes {
+            channel_id: self.channel_id as u64,
+            base_attr_id,
+            attr_count: values.len() as u64,
+        });
+        // Safety: WriteAttributes only reads the shared memory, which is uniquely borrowed by
+        // `self`.
+        unsafe { ecall_send::<()>(&msg) }?;
+        Ok(())
+    }
+
+    /// Sets the value of a single attribute of the channel.
+    pub fn write_attribute(&mut self, attr_id: MpxyAttrId, value: u32) -> Result<()> {
+        self.write_attributes(attr_id, &[value])
+    }
+
+    // Copies `data` to the start of the shared memory.
+    fn fill(&mut self, data: &[u8]) -> Result<u64> {
+        self.buf
+            .get_mut(..data.len())
+            .ok_or(Error::InvalidParam)?
+            .copy_from_slice(data);
+        Ok(data.len() as u64)
+    }
+
+    /// Sends `request` as message `msg_id` and waits for the response, which is copied into
+    /// `response`. Returns the full length of the response, which is larger than `response.len()`
+    /// if the response was truncated.
+    pub fn send_with_response(
+        &mut self,
+        msg_id: u32,
+        request: &[u8],
+        response: &mut [u8],
+    ) -> Result<usize> {
+        let msg = SbiMessage::Mpxy(SendMessageWithResponse {
+            channel_id: self.channel_id as u64,
+            msg_id: msg_id as u64,
+            msg_data_len: self.fill(request)?,
+        });
+        // Safety: SendMessageWithResponse only reads and writes the shared memory, which is
+        // uniquely borrowed by `self`.
+        let len: usize = unsafe { ecall_send(&msg) }?;
+        let data = self.buf.get(..len).ok_or(Error::Failed)?;
+        let copied = len.min(response.len());
+        response[..copied].copy_from_slice(&data[..copied]);
+        Ok(len)
+    }
+
+    /// Sends `request` as message `msg_id` without waiting for a response.
+    pub fn send_without_response(&mut self, msg_id: u32, request: &[u8]) -> Result<()> {
+        let msg = SbiMessage::Mpxy(SendMessageWithoutResponse {
+            channel_id: self.channel_id as u64,
+            msg_id: msg_id as u64,
+            msg_data_len: self.fill(request)?,
+        });
+        // Safety: SendMessageWithoutResponse only reads the shared memory, which is uniquely
+        // borrowed by `self`.
+        unsafe { ecall_send::<()>(&msg) }?;
+        Ok(())
+    }
+
+    /// Sends `request` as message `msg_id` and returns the response. Fails if the response is
+    /// shorter than `Resp`; any trailing response data is ignored.
+    pub fn request<Req: MessageData, Resp: MessageData>(
+        &mut self,
+        msg_id: u32,
+        request: &Req,
+    ) -> Result<Resp> {
+        let mut response = MaybeUninit::<Resp>::zeroed();
+        // Safety: `response` is zero-initialized and `MessageData` types are valid for any bit
+        // pattern, so it's safe to view and write it as bytes.
+        let bytes = unsafe {
+            slice::from_raw_parts_mut(response.as_mut_ptr() as *mut u8, size_of::<Resp>())
+        };
+        let len = self.send_with_response(msg_id, as_bytes(request), bytes)?;
+        if len < size_of::<Resp>() {
+            return Err(Error::Failed);
+        }
+        // Safety: See above.
+        Ok(unsafe { response.assume_init() })
+    }
+
+    /// Sends `request` as message `msg_id` without waiting for a response.
+    pub fn send<Req: MessageData>(&mut self, msg_id: u32, request: &Req) -> Result<()> {
+        self.send_without_response(msg_id, as_bytes(request))
+    }
+
+    /// Copies the channel's pending notification event data into `events` and returns the header
+    /// describing it. At most `events.len()` bytes of the `returned` bytes are copied.
+    pub fn notification_events(&mut self, events: &mut [u8]) -> Result<MpxyNotificationHeader> {
+        const HEADER_LEN: usize = size_of::<MpxyNotificationHeader>();
+        let msg = SbiMessage::Mpxy(GetNotificationEvents {
+            channel_id: self.channel_id as u64,
+        });
+        // Safety: GetNotificationEvents only writes the shared memory, which is uniquely borrowed
+        // by `self`.
+        unsafe { ecall_send::<()>(&msg) }?;
+        let header = MpxyNotificationHeader {
+            remaining: read_word(self.buf, 0)?,
+            returned: read_word(self.buf, 1)?,
+            lost: read_word(self.buf, 2)?,
+            reserved: read_word(self.buf, 3)?,
+        };
+        let data = self
+            .buf
+            .get(HEADER_LEN..HEADER_LEN + header.returned as usize)
+            .ok_or(Error::Failed)?;
+        let copied = data.len().min(events.len());
+        events[..copied].copy_from_slice(&data[..copied]);
+        Ok(header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockReply, MockSbi};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[repr(C, align(4096))]
+    struct Pages([u8; 8192]);
+
+    // Writes the u32s `words` to the shared memory at `addr`.
+    fn write_words(addr: u64, words: &[u32]) {
+        for (i, word) in words.iter().enumerate() {
+            // Safety: The tests register shared memory large enough for all the words written.
+            unsafe { (addr as *mut u32).add(i).write(word.to_le()) };
+        }
+    }
+
+    // Installs a mock that answers MPXY calls like a firmware with channels 10, 11 and 12 and two
+    // bytes of notification data on channel 10.
+    fn install() -> MockSbi {
+        let mock = MockSbi::install();
+        let shmem = Rc::new(Cell::new(0));
+        mock.set_handler(move |msg| {
+            let SbiMessage::Mpxy(function) = *msg else {
+                panic!("unexpected call {msg:?}");
+            };
+            match function {
+                GetShmemSize => return MockReply::success(4096),
+                SetShmem { shmem_phys_lo, .. } => shmem.set(shmem_phys_lo),
+                // Two IDs per call.
+                GetChannelIds { start_index: 0 } => write_words(shmem.get(), &[1, 2, 10, 11]),
+                GetChannelIds { start_index: 2 } => write_words(shmem.get(), &[0, 1, 12]),
+                GetNotificationEvents { channel_id: 10 } => {
+                    write_words(shmem.get(), &[4, 2, 1, 0, 0xbbaa])
+                }
+                _ => panic!("unexpected call {msg:?}"),
+            }
+            MockReply::success(0)
+        });
+        mock
+    }
+
+    #[test]
+    fn channel_ids() {
+        let _mock = install();
+        let mut pages = Pages([0; 8192]);
+        // Safety: The mock treats the address of the pages as identity mapped.
+        let mut shmem = unsafe { MpxyShmem::new(&mut pages.0) }.unwrap();
+        let mut ids = [0; 4];
+        assert_eq!(shmem.channel_ids(&mut ids), Ok(3));
+        assert_eq!(ids, [10, 11, 12, 0]);
+        // Stops once `ids` is full.
+        let mut ids = [0; 1];
+        assert_eq!(shmem.channel_ids(&mut ids), Ok(1));
+        assert_eq!(ids, [10]);
+    }
+
+    #[test]
+    fn notification_events() {
+        let _mock = install();
+        let mut pages = Pages([0; 8192]);
+        // Safety: The mock treats the address of the pages as identity mapped.
+        let mut shmem = unsafe { MpxyShmem::new(&mut pages.0) }.unwrap();
+        let mut events = [0; 1];
+        assert_eq!(
+            shmem.channel(10).notification_events(&mut events),
+            Ok(MpxyNotificationHeader {
+                remaining: 4,
+                returned: 2,
+                lost: 1,
+                reserved: 0,
+            })
+        );
+        assert_eq!(events, [0xaa]);
+    }
+
+    #[test]
+    fn misaligned_shmem() {
+        let _mock = install();
+        let mut pages = Pages([0; 8192]);
+        // Safety: Registration fails before the address is passed on.
+        let shmem = unsafe { MpxyShmem::new(&mut pages.0[8..]) };
+        assert!(matches!(shmem, Err(Error::InvalidParam)));
+    }
+}
@@ -2,9 +2,118 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use core::{marker::PhantomData, ptr};
+
 use crate::NaclFunction::*;
-use crate::NaclShmem;
 use crate::{ecall_send, Result, SbiMessage};
+use crate::{NaclFeature, NaclHfenceEntry, NaclShmem, NACL_HFENCE_ENTRIES, NACL_SCRATCH_BYTES};
+
+/// Provides volatile accessors to a registered `NaclShmem` area for batching CSR updates and
+/// HFENCEs. Each access touches only the field it reads or writes, so an SBI implementation can
+/// also use this on its mapping of the caller's area.
+pub struct NaclShmemRef<'a> {
+    ptr: *mut NaclShmem,
+    _lifetime: PhantomData<&'a NaclShmem>,
+}
+
+impl NaclShmemRef<'_> {
+    /// Creates a new `NaclShmemRef` from a raw pointer to a `NaclShmem`.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that `ptr` is suitably aligned and points to a `NaclShmem`
+    /// structure that is valid for the lifetime `'a`.
+    pub unsafe fn new(ptr: *mut NaclShmem) -> Self {
+        Self {
+            ptr,
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Reads the HS or VS CSR at `csr_num`.
+    pub fn csr(&self, csr_num: u16) -> u64 {
+        let index = NaclShmem::csr_index(csr_num);
+        // Safety: `index` is guaranteed to be a valid index into `csrs` and the caller guaranteed
+        // at construction that `ptr` points to a valid `NaclShmem`.
+        unsafe { ptr::addr_of!((*self.ptr).csrs[index]).read_volatile() }
+    }
+
+    /// Writes the HS or VS CSR at `csr_num` and marks it dirty so that it's picked up by the next
+    /// `sync_csr()` or `sync_sret()`.
+    pub fn set_csr(&self, csr_num: u16, val: u64) {
+        let index = NaclShmem::csr_index(csr_num);
+        let (word, bit) = NaclShmem::csr_dirty_bit(csr_num);
+        // Safety: `index` and `word` are guaranteed to be valid indices into `csrs` and
+        // `dirty_bitmap` and the caller guaranteed at construction that `ptr` points to a valid
+        // `NaclShmem`.
+        unsafe {
+            ptr::addr_of_mut!((*self.ptr).csrs[index]).write_volatile(val);
+            let dirty = ptr::addr_of_mut!((*self.ptr).dirty_bitmap[word]);
+            dirty.write_volatile(dirty.read_volatile() | bit);
+        }
+    }
+
+    /// Returns true if the HS or VS CSR at `csr_num` has been written but not yet synchronized.
+    pub fn is_csr_dirty(&self, csr_num: u16) -> bool {
+        let (word, bit) = NaclShmem::csr_dirty_bit(csr_num);
+        // Safety: `word` is guaranteed to be a valid index into `dirty_bitmap` and the caller
+        // guaranteed at construction that `ptr` points to a valid `NaclShmem`.
+        unsafe { ptr::addr_of!((*self.ptr).dirty_bitmap[word]).read_volatile() & bit != 0 }
+    }
+
+    /// Reads the HFENCE queue entry at `index`, which must be less than `NACL_HFENCE_ENTRIES`.
+    pub fn hfence_entry(&self, index: usize) -> NaclHfenceEntry {
+        assert!(index < NACL_HFENCE_ENTRIES);
+        // Safety: `index` is guaranteed to be a valid index into `hfence_queue` and the caller
+        // guaranteed at construction that `ptr` points to a valid `NaclShmem`.
+        unsafe { ptr::addr_of!((*self.ptr).hfence_queue[index]).read_volatile() }
+    }
+
+    /// Writes the HFENCE queue entry at `index`, which must be less than `NACL_HFENCE_ENTRIES`.
+    pub fn set_hfence_entry(&self, index: usize, entry: NaclHfenceEntry) {
+        assert!(index < NACL_HFENCE_ENTRIES);
+        // Safety: `index` is guaranteed to be a valid index into `hfence_queue` and the caller
+        // guaranteed at construction that `ptr` points to a valid `NaclShmem`.
+        unsafe { ptr::addr_of_mut!((*self.ptr).hfence_queue[index]).write_volatile(entry) };
+    }
+
+    /// Reads the `index`th u64 of the scratch area, which must be less than
+    /// `NACL_SCRATCH_BYTES / 8`.
+    pub fn scratch(&self, index: usize) -> u64 {
+        assert!(index < NACL_SCRATCH_BYTES / 8);
+        // Safety: `index` is guaranteed to be a valid index into `scratch` and the caller
+        // guaranteed at construction that `ptr` points to a valid `NaclShmem`.
+        unsafe { ptr::addr_of!((*self.ptr).scratch[index]).read_volatile() }
+    }
+
+    /// Writes the `index`th u64 of the scratch area, which must be less than
+    /// `NACL_SCRATCH_BYTES / 8`.
+    pub fn set_scratch(&self, index: usize, val: u64) {
+        assert!(index < NACL_SCRATCH_BYTES / 8);
+        // Safety: `index` is guaranteed to be a valid index into `scratch` and the caller
+        // guaranteed at construction that `ptr` points to a valid `NaclShmem`.
+        unsafe { ptr::addr_of_mut!((*self.ptr).scratch[index]).write_volatile(val) };
+    }
+
+    /// Places `entry` in the first free slot of the HFENCE queue so that it's processed by the
+    /// next `sync_hfence()` or `sync_sret()`. Returns the index of the slot, or `None` if the queue
+    /// is full.
+    pub fn queue_hfence(&self, entry: NaclHfenceEntry) -> Option<usize> {
+        let index = (0..NACL_HFENCE_ENTRIES).find(|&i| !self.hfence_entry(i).is_pending())?;
+        self.set_hfence_entry(index, entry);
+        Some(index)
+    }
+}
+
+/// Returns true if the given NACL feature is supported.
+pub fn probe_feature(feature: NaclFeature) -> Result<bool> {
+    let msg = SbiMessage::Nacl(ProbeFeature {
+        feature_id: feature as u64,
+    });
+    // Safety: ProbeFeature doesn't touch memory.
+    let available: u64 = unsafe { ecall_send(&msg) }?;
+    Ok(available != 0)
+}
 
 /// Registers the nested hypervisor <-> host hypervisor shared memory area for the calling CPU.
 /// `shmem_ptr` must be page-aligned and refer to a sufficient number of accessible, contiguous
@@ -34,3 +143,41 @@ pub fn unregister_shmem() -> Result<()> {
     unsafe { ecall_send::<()>(&msg) }?;
     Ok(())
 }
+
+/// Synchronizes the HS or VS CSR at `csr_num` from the registered shared memory, or all dirty CSRs
+/// if `csr_num` is `None`.
+pub fn sync_csr(csr_num: Option<u16>) -> Result<()> {
+    let msg = SbiMessage::Nacl(SyncCsr {
+        csr_num: csr_num.map_or(u64::MAX, u64::from),
+    });
+    // Safety: SyncCsr only accesses the registered shared memory, which the caller of
+    // `register_shmem()` agreed to treat as volatile.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Processes the HFENCE queue entry at `entry_index` in the registered shared memory, or all
+/// pending entries if `entry_index` is `None`.
+pub fn sync_hfence(entry_index: Option<usize>) -> Result<()> {
+    let msg = SbiMessage::Nacl(SyncHfence {
+        entry_index: entry_index.map_or(u64::MAX, |i| i as u64),
+    });
+    // Safety: SyncHfence only accesses the registered shared memory, which the caller of
+    // `register_shmem()` agreed to treat as volatile.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Synchronizes all dirty CSRs and pending HFENCEs and then emulates an SRET into the guest. Only
+/// returns on failure.
+///
+/// # Safety
+///
+/// The caller must have set up the guest state in the registered shared memory such that it's
+/// safe to enter the guest, and must be prepared to handle the resulting trap back into the
+/// nested hypervisor.
+pub unsafe fn sync_sret() -> Result<()> {
+    let msg = SbiMessage::Nacl(SyncSret);
+    ecall_send::<()>(&msg)?;
+    Ok(())
+}
@@ -2,11 +2,12 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use super::split_addr;
 use crate::ecall_send;
 use crate::{
     PmuCounterConfigFlags, PmuCounterStartFlags, PmuCounterStopFlags, PmuEventType, PmuFunction,
 };
-use crate::{PmuCounterInfo, Result, SbiMessage};
+use crate::{PmuCounterInfo, PmuEventInfo, PmuSnapshotArea, Result, SbiMessage};
 
 /// Returns the number of PMU counters supported by the platform
 pub fn get_num_counters() -> Result<u64> {
@@ -82,3 +83,79 @@ pub fn read_firmware_counter(counter_index: u64) -> Result<u64> {
     // Safety: PmuFunction does not touch memory.
     unsafe { ecall_send(&msg) }
 }
+
+/// Reads the upper 32 bits of the firmware counter specified by counter_index on RV32
+pub fn read_firmware_counter_hi(counter_index: u64) -> Result<u64> {
+    let msg = SbiMessage::Pmu(PmuFunction::ReadFirmwareCounterHi(counter_index));
+    // Safety: PmuFunction does not touch memory.
+    unsafe { ecall_send(&msg) }
+}
+
+/// Reads the full 64-bit value of the firmware counter specified by counter_index
+#[cfg(target_pointer_width = "32")]
+pub fn read_firmware_counter_full(counter_index: u64) -> Result<u64> {
+    let lo = read_firmware_counter(counter_index)?;
+    let hi = read_firmware_counter_hi(counter_index)?;
+    Ok((hi << 32) | (lo & 0xffff_ffff))
+}
+
+/// Reads the full 64-bit value of the firmware counter specified by counter_index
+#[cfg(not(target_pointer_width = "32"))]
+pub fn read_firmware_counter_full(counter_index: u64) -> Result<u64> {
+    read_firmware_counter(counter_index)
+}
+
+/// Registers the counter snapshot area for the calling CPU. `snapshot_ptr` must be the physical
+/// address of a `PmuSnapshotArea`. The memory must remain accessible until the snapshot area is
+/// disabled by calling `disable_snapshot()`.
+///
+/// # Safety
+///
+/// The caller must own the memory referenced by `snapshot_ptr`. Since the snapshot area is written
+/// by the SBI implementation whenever counters are stopped with the take_snapshot flag, the caller
+/// must treat the memory as volatile until it is disabled.
+pub unsafe fn set_snapshot_shmem(snapshot_ptr: *mut PmuSnapshotArea) -> Result<()> {
+    let (shmem_phys_lo, shmem_phys_hi) = split_addr(snapshot_ptr as u64);
+    let msg = SbiMessage::Pmu(PmuFunction::SnapshotSetShmem {
+        shmem_phys_lo,
+        shmem_phys_hi,
+        flags: 0,
+    });
+    ecall_send::<()>(&msg)?;
+    Ok(())
+}
+
+/// Disables the counter snapshot area for the calling CPU.
+pub fn disable_snapshot() -> Result<()> {
+    let msg = SbiMessage::Pmu(PmuFunction::SnapshotSetShmem {
+        shmem_phys_lo: u64::MAX,
+        shmem_phys_hi: u64::MAX,
+        flags: 0,
+    });
+    // Safety: Doesn't access our memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Queries whether each event in `events` is supported in a single call. The result for each
+/// entry can be checked with `PmuEventInfo::is_supported()`.
+pub fn get_event_info(events: &mut [PmuEventInfo]) -> Result<()> {
+    let (shmem_phys_lo, shmem_phys_hi) = split_addr(events.as_mut_ptr() as u64);
+    let msg = SbiMessage::Pmu(PmuFunction::EventGetInfo {
+        shmem_phys_lo,
+        shmem_phys_hi,
+        num_entries: events.len() as u64,
+        flags: 0,
+    });
+    // Safety: EventGetInfo only writes the output field of the entries in `events`, which is the
+    // single mutable reference to that memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Returns whether `event_type` with `event_data` is supported.
+pub fn is_event_supported(event_type: PmuEventType, event_data: u64) -> Result<bool> {
+    let mut event = [PmuEventInfo::new(event_type, event_data)];
+    get_event_info(&mut event)?;
+    Ok(event[0].is_supported())
+}
@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::RfenceFunction::*;
+use crate::{ecall_send, FenceRange, HartMask, Result, SbiMessage};
+
+/// Instructs the harts in `hart_mask` to execute a FENCE.I instruction.
+pub fn remote_fence_i(hart_mask: HartMask) -> Result<()> {
+    let msg = SbiMessage::Rfence(RemoteFenceI { hart_mask });
+    // Safety: Remote fences don't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Instructs the harts in `hart_mask` to execute SFENCE.VMA covering the virtual addresses in
+/// `range`.
+pub fn remote_sfence_vma(hart_mask: HartMask, range: FenceRange) -> Result<()> {
+    let msg = SbiMessage::Rfence(RemoteSfenceVma { hart_mask, range });
+    // Safety: Remote fences don't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Instructs the harts in `hart_mask` to execute SFENCE.VMA covering the virtual addresses in
+/// `range` for address space `asid`.
+pub fn remote_sfence_vma_asid(hart_mask: HartMask, range: FenceRange, asid: u64) -> Result<()> {
+    let msg = SbiMessage::Rfence(RemoteSfenceVmaAsid {
+        hart_mask,
+        range,
+        asid,
+    });
+    // Safety: Remote fences don't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Instructs the harts in `hart_mask` to execute HFENCE.GVMA covering the guest physical addresses
+/// in `range` for guest `vmid`.
+pub fn remote_hfence_gvma_vmid(hart_mask: HartMask, range: FenceRange, vmid: u64) -> Result<()> {
+    let msg = SbiMessage::Rfence(RemoteHfenceGvmaVmid {
+        hart_mask,
+        range,
+        vmid,
+    });
+    // Safety: Remote fences don't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Instructs the harts in `hart_mask` to execute HFENCE.GVMA covering the guest physical addresses
+/// in `range` for all guests.
+pub fn remote_hfence_gvma(hart_mask: HartMask, range: FenceRange) -> Result<()> {
+    let msg = SbiMessage::Rfence(RemoteHfenceGvma { hart_mask, range });
+    // Safety: Remote fences don't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Instructs the harts in `hart_mask` to execute HFENCE.VVMA covering the guest virtual addresses
+/// in `range` for address space `asid` of the current guest.
+pub fn remote_hfence_vvma_asid(hart_mask: HartMask, range: FenceRange, asid: u64) -> Result<()> {
+    let msg = SbiMessage::Rfence(RemoteHfenceVvmaAsid {
+        hart_mask,
+        range,
+        asid,
+    });
+    // Safety: Remote fences don't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Instructs the harts in `hart_mask` to execute HFENCE.VVMA covering the guest virtual addresses
+/// in `range` for the current guest.
+pub fn remote_hfence_vvma(hart_mask: HartMask, range: FenceRange) -> Result<()> {
+    let msg = SbiMessage::Rfence(RemoteHfenceVvma { hart_mask, range });
+    // Safety: Remote fences don't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::salus::*;
-use crate::{ecall_send, Result};
+use crate::{ecall_send, Result, SbiMessage};
 
 /// Copies `len` bytes from `from` to `to`.
 ///
@@ -19,7 +19,7 @@ pub unsafe fn test_memcpy(to: *mut u8, from: *const u8, len: u64) -> Result<()>
         from: from as u64,
         len,
     });
-    let msg = SalusSbiMessage::SalusTest(function).into();
+    let msg = SbiMessage::from_vendor(&function);
     ecall_send::<()>(&msg)?;
     Ok(())
 }
@@ -0,0 +1,137 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::split_addr;
+use crate::SseFunction::*;
+use crate::{ecall_send, Result, SbiMessage, SseAttrId, SseEventId};
+
+/// Registers a handler for `event_id`. When the event fires, the handler is entered at
+/// `handler_entry_pc` with a6 set to the hart ID and a7 set to `handler_entry_arg`. The event must
+/// then be enabled with `enable` before it's delivered.
+///
+/// # Safety
+///
+/// `handler_entry_pc` must be the address of a handler that follows the SSE entry convention: it
+/// must save and restore any state it clobbers and finish by calling `complete`. The handler may
+/// run at any point after the event is enabled, interrupting the current context.
+pub unsafe fn register(
+    event_id: SseEventId,
+    handler_entry_pc: u64,
+    handler_entry_arg: u64,
+) -> Result<()> {
+    let msg = SbiMessage::Sse(Register {
+        event_id,
+        handler_entry_pc,
+        handler_entry_arg,
+    });
+    // Safety: Register doesn't touch memory. The caller guarantees the handler is safe to enter.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Unregisters the handler for `event_id`. The event must be disabled first.
+pub fn unregister(event_id: SseEventId) -> Result<()> {
+    let msg = SbiMessage::Sse(Unregister { event_id });
+    // Safety: Unregister doesn't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Enables delivery of `event_id` to its registered handler.
+pub fn enable(event_id: SseEventId) -> Result<()> {
+    let msg = SbiMessage::Sse(Enable { event_id });
+    // Safety: Enable doesn't touch memory. The handler's safety was guaranteed at registration.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Disables delivery of `event_id`.
+pub fn disable(event_id: SseEventId) -> Result<()> {
+    let msg = SbiMessage::Sse(Disable { event_id });
+    // Safety: Disable doesn't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Completes handling of the current event and resumes the interrupted context. Only returns if
+/// called outside of an event handler.
+pub fn complete() -> Result<()> {
+    let msg = SbiMessage::Sse(Complete);
+    // Safety: Complete doesn't touch memory and doesn't return on success.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Injects `event_id` on `hart_id`. `hart_id` is ignored for global events, which are routed to
+/// their preferred hart.
+pub fn inject(event_id: SseEventId, hart_id: u64) -> Result<()> {
+    let msg = SbiMessage::Sse(Inject { event_id, hart_id });
+    // Safety: Inject doesn't touch memory. The handler's safety was guaranteed at registration.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Unmasks delivery of software events on the calling hart.
+pub fn hart_unmask() -> Result<()> {
+    let msg = SbiMessage::Sse(HartUnmask);
+    // Safety: HartUnmask doesn't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Masks delivery of software events on the calling hart.
+pub fn hart_mask() -> Result<()> {
+    let msg = SbiMessage::Sse(HartMask);
+    // Safety: HartMask doesn't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Reads `values.len()` attributes of `event_id`, starting at `base_attr_id`, into `values`. Each
+/// attribute is an XLEN-wide `unsigned long`.
+pub fn read_attrs(
+    event_id: SseEventId,
+    base_attr_id: SseAttrId,
+    values: &mut [usize],
+) -> Result<()> {
+    let (output_phys_lo, output_phys_hi) = split_addr(values.as_mut_ptr() as u64);
+    let msg = SbiMessage::Sse(ReadAttrs {
+        event_id,
+        base_attr_id,
+        attr_count: values.len() as u64,
+        output_phys_lo,
+        output_phys_hi,
+    });
+    // Safety: ReadAttrs only writes `values`, which is the single mutable reference to that memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Returns the value of a single attribute of `event_id`.
+pub fn read_attr(event_id: SseEventId, attr_id: SseAttrId) -> Result<usize> {
+    let mut value = [0];
+    read_attrs(event_id, attr_id, &mut value)?;
+    Ok(value[0])
+}
+
+/// Writes `values.len()` attributes of `event_id`, starting at `base_attr_id`, from `values`. Each
+/// attribute is an XLEN-wide `unsigned long`.
+pub fn write_attrs(event_id: SseEventId, base_attr_id: SseAttrId, values: &[usize]) -> Result<()> {
+    let (input_phys_lo, input_phys_hi) = split_addr(values.as_ptr() as u64);
+    let msg = SbiMessage::Sse(WriteAttrs {
+        event_id,
+        base_attr_id,
+        attr_count: values.len() as u64,
+        input_phys_lo,
+        input_phys_hi,
+    });
+    // Safety: WriteAttrs only reads `values`.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Sets the value of a single attribute of `event_id`.
+pub fn write_attr(event_id: SseEventId, attr_id: SseAttrId, value: usize) -> Result<()> {
+    write_attrs(event_id, attr_id, &[value])
+}
@@ -0,0 +1,137 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use core::sync::atomic::{fence, Ordering};
+use core::{hint, marker::PhantomData, ptr};
+
+use super::split_addr;
+use crate::StaFunction::*;
+use crate::{ecall_send, Result, SbiMessage, StaShmem};
+
+/// Provides volatile accessors to a registered `StaShmem` area.
+pub struct StaShmemRef<'a> {
+    ptr: *const StaShmem,
+    _lifetime: PhantomData<&'a StaShmem>,
+}
+
+impl StaShmemRef<'_> {
+    /// Creates a new `StaShmemRef` from a raw pointer to a `StaShmem`.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that `ptr` is suitably aligned and points to a `StaShmem`
+    /// structure that is valid for the lifetime `'a`.
+    pub unsafe fn new(ptr: *const StaShmem) -> Self {
+        Self {
+            ptr,
+            _lifetime: PhantomData,
+        }
+    }
+
+    fn sequence(&self) -> u32 {
+        // Safety: The caller guaranteed at construction that `ptr` points to a valid `StaShmem`.
+        unsafe { ptr::addr_of!((*self.ptr).sequence).read_volatile() }
+    }
+
+    /// Returns the amount of time this virtual hart has been scheduled out while not idle.
+    ///
+    /// Retries until it reads a value that wasn't being updated by the SBI implementation.
+    pub fn steal(&self) -> u64 {
+        loop {
+            let sequence = self.sequence();
+            if sequence & 1 != 0 {
+                hint::spin_loop();
+                continue;
+            }
+            fence(Ordering::Acquire);
+            // Safety: The caller guaranteed at construction that `ptr` points to a valid
+            // `StaShmem`.
+            let steal = unsafe { ptr::addr_of!((*self.ptr).steal).read_volatile() };
+            fence(Ordering::Acquire);
+            if self.sequence() == sequence {
+                return steal;
+            }
+        }
+    }
+
+    /// Returns true if this virtual hart is currently scheduled out while not idle.
+    pub fn preempted(&self) -> bool {
+        // Safety: The caller guaranteed at construction that `ptr` points to a valid `StaShmem`.
+        unsafe { ptr::addr_of!((*self.ptr).preempted).read_volatile() != 0 }
+    }
+}
+
+/// Registers the steal-time shared memory area for the calling CPU. `shmem_ptr` must be the
+/// physical address of a `StaShmem` struct, which is 64-byte aligned. The memory must remain
+/// accessible until the shared-memory area is unregistered by calling `unregister_shmem()`.
+///
+/// # Safety
+///
+/// The caller must own the memory referenced by `shmem_ptr`. Since the shared-memory area may be
+/// written by the SBI implementation at any time, the caller must treat the memory as volatile
+/// until it is unregistered.
+pub unsafe fn register_shmem(shmem_ptr: *mut StaShmem) -> Result<()> {
+    let (shmem_lo, shmem_hi) = split_addr(shmem_ptr as u64);
+    let msg = SbiMessage::Sta(SetShmem {
+        shmem_lo,
+        shmem_hi,
+        flags: 0,
+    });
+    ecall_send::<()>(&msg)?;
+    Ok(())
+}
+
+/// Unregisters the steal-time shared memory area for the calling CPU.
+pub fn unregister_shmem() -> Result<()> {
+    let msg = SbiMessage::Sta(SetShmem {
+        shmem_lo: u64::MAX,
+        shmem_hi: u64::MAX,
+        flags: 0,
+    });
+    // Safety: Doesn't access our memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockSbi;
+
+    #[test]
+    fn shmem_ref() {
+        let mut shmem = StaShmem::default();
+        shmem.sequence = 2;
+        shmem.steal = 1234;
+        shmem.preempted = 1;
+        // Safety: `shmem` outlives the reference.
+        let shmem_ref = unsafe { StaShmemRef::new(&shmem) };
+        assert_eq!(shmem_ref.steal(), 1234);
+        assert!(shmem_ref.preempted());
+    }
+
+    #[test]
+    fn register() {
+        let mock = MockSbi::install();
+        let mut shmem = StaShmem::default();
+        // Safety: The mock doesn't access the shared memory.
+        unsafe { register_shmem(&mut shmem) }.unwrap();
+        unregister_shmem().unwrap();
+        assert_eq!(
+            mock.calls(),
+            [
+                SbiMessage::Sta(SetShmem {
+                    shmem_lo: &shmem as *const StaShmem as u64,
+                    shmem_hi: 0,
+                    flags: 0,
+                }),
+                SbiMessage::Sta(SetShmem {
+                    shmem_lo: u64::MAX,
+                    shmem_hi: u64::MAX,
+                    flags: 0,
+                }),
+            ]
+        );
+    }
+}
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::StateFunction::*;
-use crate::{ecall_send, Result, SbiMessage};
+use crate::{ecall_send, Error, HartState, Result, SbiMessage, SuspendType};
 
 /// Starts the given cpu executing at `start_addr` with `opaque` in register a1.
 ///
@@ -22,3 +22,50 @@ pub unsafe fn hart_start(hart_id: u64, start_addr: u64, opaque: u64) -> Result<(
     ecall_send::<()>(&msg)?;
     Ok(())
 }
+
+/// Stops the calling cpu. Only returns if the stop request failed.
+pub fn hart_stop() -> Result<()> {
+    let msg = SbiMessage::HartState(HartStop);
+    // Safety: HartStop doesn't touch memory and doesn't return on success.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
+
+/// Returns the current state of the given cpu.
+pub fn hart_get_status(hart_id: u64) -> Result<HartState> {
+    let msg = SbiMessage::HartState(HartStatus { hart_id });
+    // Safety: HartStatus doesn't touch memory.
+    let state = unsafe { ecall_send(&msg) }?;
+    HartState::from_reg(state)
+}
+
+/// Suspends the calling cpu with the given `suspend_type`. For retentive suspend types, returns
+/// once the cpu resumes. For non-retentive suspend types, execution resumes at `resume_addr` with
+/// the hart ID in register a0 and `opaque` in register a1, and this function only returns on
+/// failure.
+///
+/// # Safety
+///
+/// For non-retentive suspend types, resume_addr must point to code that can be safely executed
+/// with the MMU off, and opaque, if a pointer, must point to data that is safe to access from the
+/// resumed context.
+pub unsafe fn hart_suspend(suspend_type: SuspendType, resume_addr: u64, opaque: u64) -> Result<()> {
+    let msg = SbiMessage::HartState(HartSuspend {
+        suspend_type,
+        resume_addr,
+        opaque,
+    });
+    ecall_send::<()>(&msg)?;
+    Ok(())
+}
+
+/// Suspends the calling cpu with the given retentive `suspend_type`, returning once the cpu
+/// resumes. Returns `InvalidParam` if `suspend_type` is non-retentive.
+pub fn hart_retentive_suspend(suspend_type: SuspendType) -> Result<()> {
+    if !suspend_type.is_retentive() {
+        return Err(Error::InvalidParam);
+    }
+    // Safety: Retentive suspends return to the caller with its state intact and ignore the resume
+    // address and opaque value.
+    unsafe { hart_suspend(suspend_type, 0, 0) }
+}
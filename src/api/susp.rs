@@ -0,0 +1,28 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::SuspendFunction::*;
+use crate::{ecall_send, Result, SbiMessage, SleepType};
+
+/// Suspends the system into the sleep state given by `sleep_type`. On wakeup the calling cpu
+/// resumes executing at `resume_addr` with the MMU off, its hart ID in register a0 and `opaque` in
+/// register a1. Only returns if the suspend request failed.
+///
+/// All other cpus must be stopped or suspended before calling this function.
+///
+/// # Safety
+///
+/// resume_addr must point to code that can be safely executed with the MMU off.
+/// opaque, if a pointer, must point to data that is safe to access from the resumed context.
+pub unsafe fn system_suspend(sleep_type: SleepType, resume_addr: u64, opaque: u64) -> Result<()> {
+    let msg = SbiMessage::Suspend(SystemSuspend {
+        sleep_type,
+        resume_addr,
+        opaque,
+    });
+    // Safety: Passes one pointer to SBI, the caller guarantees that it points to code that can be
+    // executed on resume.
+    ecall_send::<()>(&msg)?;
+    Ok(())
+}
@@ -0,0 +1,15 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::TimeFunction::*;
+use crate::{ecall_send, Result, SbiMessage};
+
+/// Programs the clock for the next event at absolute time `stime_value` and clears any pending
+/// timer interrupt. Pass `u64::MAX` to clear the timer interrupt without scheduling a new event.
+pub fn set_timer(stime_value: u64) -> Result<()> {
+    let msg = SbiMessage::Time(SetTimer { stime_value });
+    // Safety: SetTimer doesn't touch memory.
+    unsafe { ecall_send::<()>(&msg) }?;
+    Ok(())
+}
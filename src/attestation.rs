@@ -2,10 +2,13 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::bytes::*;
 use crate::error::*;
 use crate::function::*;
 
+use core::mem::{offset_of, size_of};
 use flagset::{flags, FlagSet};
+use static_assertions::const_assert;
 
 /// The data blob passed to the GetEvidence call must be 64 bytes long.
 pub const EVIDENCE_DATA_BLOB_SIZE: usize = 64;
@@ -32,7 +35,7 @@ flags! {
 }
 
 /// A list of supported hash algorithms.
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum HashAlgorithm {
     /// SHA-384
@@ -43,6 +46,15 @@ pub enum HashAlgorithm {
 }
 
 impl HashAlgorithm {
+    /// Creates a hash algorithm from its raw value or returns an error if the value is unknown.
+    pub fn from_raw_value(value: u8) -> Result<Self> {
+        match value {
+            1 => Ok(HashAlgorithm::Sha384),
+            2 => Ok(HashAlgorithm::Sha512),
+            _ => Err(Error::InvalidParam),
+        }
+    }
+
     /// The hash algorithm output size, in bytes.
     pub fn size(&self) -> usize {
         match self {
@@ -61,7 +73,7 @@ pub const MAX_HASH_SIZE: usize = 64;
 /// GetCapabilities caller. It lets the caller know which hash algorithms,
 /// evidence formats, and measurements mappings the SBI implementation supports.
 #[repr(C)]
-#[derive(Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct AttestationCapabilities {
     /// The TCB Secure Version Number.
     pub tcb_svn: u64,
@@ -103,7 +115,7 @@ impl AttestationCapabilities {
         index: usize,
     ) -> Result<&mut AttestationCapabilities> {
         // We do not allow for a sparse measurement register array.
-        if index + 1 > (self.static_measurements + self.runtime_measurements) as usize {
+        if index >= self.measurement_count() {
             return Err(Error::Failed);
         }
 
@@ -114,6 +126,83 @@ impl AttestationCapabilities {
 
         Ok(self)
     }
+
+    // Returns the number of static and runtime measurement registers.
+    fn measurement_count(&self) -> usize {
+        self.static_measurements as usize + self.runtime_measurements as usize
+    }
+
+    /// The size of `AttestationCapabilities` in bytes, including trailing padding.
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Decodes `AttestationCapabilities` as written by the SBI implementation. Returns
+    /// `InvalidParam` if `bytes` isn't exactly `SIZE` bytes long, if the hash algorithm, the
+    /// evidence formats or any measurement register descriptor is invalid, or if there are more
+    /// than `MAX_MEASUREMENT_REGISTERS` measurement registers. The trailing padding is ignored.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        check_size(bytes, Self::SIZE)?;
+        let hash_algorithm =
+            HashAlgorithm::from_raw_value(get_u8(bytes, offset_of!(Self, hash_algorithm)))?;
+        let evidence_formats = FlagSet::new(get_u8(bytes, offset_of!(Self, evidence_formats)))
+            .map_err(|_| Error::InvalidParam)?;
+        let mut measurement_registers =
+            [MeasurementRegisterDescriptor::default(); MAX_MEASUREMENT_REGISTERS];
+        let registers = &bytes[offset_of!(Self, measurement_registers)..];
+        for (register, bytes) in measurement_registers
+            .iter_mut()
+            .zip(registers.chunks_exact(MeasurementRegisterDescriptor::SIZE))
+        {
+            *register = MeasurementRegisterDescriptor::from_bytes(bytes)?;
+        }
+        let caps = Self {
+            tcb_svn: get_u64(bytes, offset_of!(Self, tcb_svn)),
+            hash_algorithm,
+            evidence_formats,
+            static_measurements: get_u8(bytes, offset_of!(Self, static_measurements)),
+            runtime_measurements: get_u8(bytes, offset_of!(Self, runtime_measurements)),
+            measurement_registers,
+        };
+        if caps.measurement_count() > MAX_MEASUREMENT_REGISTERS {
+            return Err(Error::InvalidParam);
+        }
+        Ok(caps)
+    }
+
+    /// Encodes these capabilities in the layout `GetCapabilities` returns them in, with zeroed
+    /// padding.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        put_u64(&mut bytes, offset_of!(Self, tcb_svn), self.tcb_svn);
+        put_u8(
+            &mut bytes,
+            offset_of!(Self, hash_algorithm),
+            self.hash_algorithm as u8,
+        );
+        put_u8(
+            &mut bytes,
+            offset_of!(Self, evidence_formats),
+            self.evidence_formats.bits(),
+        );
+        put_u8(
+            &mut bytes,
+            offset_of!(Self, static_measurements),
+            self.static_measurements,
+        );
+        put_u8(
+            &mut bytes,
+            offset_of!(Self, runtime_measurements),
+            self.runtime_measurements,
+        );
+        let registers = &mut bytes[offset_of!(Self, measurement_registers)..];
+        for (register, bytes) in self
+            .measurement_registers
+            .iter()
+            .zip(registers.chunks_exact_mut(MeasurementRegisterDescriptor::SIZE))
+        {
+            bytes.copy_from_slice(&register.to_bytes());
+        }
+        bytes
+    }
 }
 
 /// Measurement register descriptor.
@@ -122,7 +211,7 @@ impl AttestationCapabilities {
 /// The AttestationCapabilities structure includes an array of those descriptors
 /// for all the supported measurement registers.
 #[repr(C)]
-#[derive(Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct MeasurementRegisterDescriptor {
     tcb_layer_index: u8,
     fwid_index: u8,
@@ -140,10 +229,59 @@ impl MeasurementRegisterDescriptor {
             runtime,
         }
     }
+
+    /// The size of `MeasurementRegisterDescriptor` in bytes.
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Decodes a `MeasurementRegisterDescriptor` from `bytes`. Returns `InvalidParam` if `bytes`
+    /// isn't exactly `SIZE` bytes long or `runtime` is neither 0 nor 1.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        check_size(bytes, Self::SIZE)?;
+        let runtime = match get_u8(bytes, offset_of!(Self, runtime)) {
+            0 => false,
+            1 => true,
+            _ => return Err(Error::InvalidParam),
+        };
+        Ok(Self {
+            tcb_layer_index: get_u8(bytes, offset_of!(Self, tcb_layer_index)),
+            fwid_index: get_u8(bytes, offset_of!(Self, fwid_index)),
+            tcg_pcr_index: get_u8(bytes, offset_of!(Self, tcg_pcr_index)),
+            runtime,
+        })
+    }
+
+    /// Encodes this descriptor in the layout used by `AttestationCapabilities`.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        put_u8(
+            &mut bytes,
+            offset_of!(Self, tcb_layer_index),
+            self.tcb_layer_index,
+        );
+        put_u8(&mut bytes, offset_of!(Self, fwid_index), self.fwid_index);
+        put_u8(
+            &mut bytes,
+            offset_of!(Self, tcg_pcr_index),
+            self.tcg_pcr_index,
+        );
+        put_u8(&mut bytes, offset_of!(Self, runtime), self.runtime as u8);
+        bytes
+    }
+}
+
+fn _assert_capabilities_layout() {
+    const_assert!(MeasurementRegisterDescriptor::SIZE == 4);
+    const_assert!(AttestationCapabilities::SIZE == 144);
+    const_assert!(offset_of!(AttestationCapabilities, tcb_svn) == 0);
+    const_assert!(offset_of!(AttestationCapabilities, hash_algorithm) == 8);
+    const_assert!(offset_of!(AttestationCapabilities, evidence_formats) == 9);
+    const_assert!(offset_of!(AttestationCapabilities, static_measurements) == 10);
+    const_assert!(offset_of!(AttestationCapabilities, runtime_measurements) == 11);
+    const_assert!(offset_of!(AttestationCapabilities, measurement_registers) == 12);
 }
 
 /// Functions provided by the attestation extension.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
 pub enum AttestationFunction {
     /// Get the SBI implementation attestation capabilities.
     /// The attestation capabilities let the SBI implementations expose which
@@ -154,10 +292,13 @@ pub enum AttestationFunction {
     /// a6 = 0
     /// a0 = Attestation capabilities buffer
     /// a1 = Attestation capabilities buffer size
+    #[sbi(fid = 0)]
     GetCapabilities {
         /// a0 = Capabilities structure address
+        #[sbi(reg = a0)]
         caps_addr_out: u64,
         /// a1 = Capabilities structure length
+        #[sbi(reg = a1)]
         caps_size: u64,
     },
 
@@ -179,18 +320,25 @@ pub enum AttestationFunction {
     /// a3 = Attestation evidence format
     /// a4 = Generated certificate address
     /// a5 = Reserved length for the generated certificate address
+    #[sbi(fid = 1)]
     GetEvidence {
         /// a0 = CSR address
+        #[sbi(reg = a0)]
         cert_request_addr: u64,
         /// a1 = CSR length
+        #[sbi(reg = a1)]
         cert_request_size: u64,
         /// a2 = User data blob
+        #[sbi(reg = a2)]
         request_data_addr: u64,
         /// a3 = Attestation evidence format
+        #[sbi(reg = a3)]
         evidence_format: u64,
         /// a4 = Generated Certificate address
+        #[sbi(reg = a4)]
         cert_addr_out: u64,
         /// a5 = Reserved length for the generated certificate address
+        #[sbi(reg = a5)]
         cert_size: u64,
     },
 
@@ -210,12 +358,16 @@ pub enum AttestationFunction {
     /// a0 = Measurement data buffer address
     /// a1 = Measurement data buffer length
     /// a2 = Measurement register index.
+    #[sbi(fid = 2)]
     ExtendMeasurement {
         /// a0 = measurement data buffer address
+        #[sbi(reg = a0)]
         measurement_data_addr: u64,
         /// a1 = measurement data buffer length
+        #[sbi(reg = a1)]
         measurement_data_size: u64,
         /// a2 = measurement register index
+        #[sbi(reg = a2)]
         measurement_index: u64,
     },
 
@@ -235,216 +387,16 @@ pub enum AttestationFunction {
     /// a0 = Measurement data buffer address
     /// a1 = Measurement data buffer length
     /// a2 = Measurement register index.
+    #[sbi(fid = 3)]
     ReadMeasurement {
         /// a0 = measurement data buffer address
+        #[sbi(reg = a0)]
         measurement_data_addr_out: u64,
         /// a1 = measurement data buffer length
+        #[sbi(reg = a1)]
         measurement_data_size: u64,
         /// a2 = measurement register index
+        #[sbi(reg = a2)]
         measurement_index: u64,
     },
 }
-
-impl AttestationFunction {
-    /// Attempts to parse `Self` from the passed in `a0-a7`.
-    pub(crate) fn from_regs(args: &[u64]) -> Result<Self> {
-        use AttestationFunction::*;
-        match args[6] {
-            0 => Ok(GetCapabilities {
-                caps_addr_out: args[0],
-                caps_size: args[1],
-            }),
-
-            1 => Ok(GetEvidence {
-                cert_request_addr: args[0],
-                cert_request_size: args[1],
-                request_data_addr: args[2],
-                evidence_format: args[3],
-                cert_addr_out: args[4],
-                cert_size: args[5],
-            }),
-
-            2 => Ok(ExtendMeasurement {
-                measurement_data_addr: args[0],
-                measurement_data_size: args[1],
-                measurement_index: args[2],
-            }),
-
-            3 => Ok(ReadMeasurement {
-                measurement_data_addr_out: args[0],
-                measurement_data_size: args[1],
-                measurement_index: args[2],
-            }),
-
-            _ => Err(Error::InvalidParam),
-        }
-    }
-}
-
-impl SbiFunction for AttestationFunction {
-    fn a6(&self) -> u64 {
-        use AttestationFunction::*;
-        match self {
-            GetCapabilities {
-                caps_addr_out: _,
-                caps_size: _,
-            } => 0,
-
-            GetEvidence {
-                cert_request_addr: _,
-                cert_request_size: _,
-                request_data_addr: _,
-                evidence_format: _,
-                cert_addr_out: _,
-                cert_size: _,
-            } => 1,
-
-            ExtendMeasurement {
-                measurement_data_addr: _,
-                measurement_data_size: _,
-                measurement_index: _,
-            } => 2,
-
-            ReadMeasurement {
-                measurement_data_addr_out: _,
-                measurement_data_size: _,
-                measurement_index: _,
-            } => 3,
-        }
-    }
-
-    fn a5(&self) -> u64 {
-        use AttestationFunction::*;
-        match self {
-            GetEvidence {
-                cert_request_addr: _,
-                cert_request_size: _,
-                request_data_addr: _,
-                evidence_format: _,
-                cert_addr_out: _,
-                cert_size,
-            } => *cert_size,
-            _ => 0,
-        }
-    }
-
-    fn a4(&self) -> u64 {
-        use AttestationFunction::*;
-        match self {
-            GetEvidence {
-                cert_request_addr: _,
-                cert_request_size: _,
-                request_data_addr: _,
-                evidence_format: _,
-                cert_addr_out,
-                cert_size: _,
-            } => *cert_addr_out,
-            _ => 0,
-        }
-    }
-
-    fn a3(&self) -> u64 {
-        use AttestationFunction::*;
-        match self {
-            GetEvidence {
-                cert_request_addr: _,
-                cert_request_size: _,
-                request_data_addr: _,
-                evidence_format,
-                cert_addr_out: _,
-                cert_size: _,
-            } => *evidence_format,
-            _ => 0,
-        }
-    }
-
-    fn a2(&self) -> u64 {
-        use AttestationFunction::*;
-        match self {
-            GetEvidence {
-                cert_request_addr: _,
-                cert_request_size: _,
-                request_data_addr,
-                evidence_format: _,
-                cert_addr_out: _,
-                cert_size: _,
-            } => *request_data_addr,
-
-            ExtendMeasurement {
-                measurement_data_addr: _,
-                measurement_data_size: _,
-                measurement_index,
-            } => *measurement_index,
-
-            ReadMeasurement {
-                measurement_data_addr_out: _,
-                measurement_data_size: _,
-                measurement_index,
-            } => *measurement_index,
-
-            _ => 0,
-        }
-    }
-
-    fn a1(&self) -> u64 {
-        use AttestationFunction::*;
-        match self {
-            GetCapabilities {
-                caps_addr_out: _,
-                caps_size,
-            } => *caps_size,
-
-            GetEvidence {
-                cert_request_addr: _,
-                cert_request_size,
-                request_data_addr: _,
-                evidence_format: _,
-                cert_addr_out: _,
-                cert_size: _,
-            } => *cert_request_size,
-
-            ExtendMeasurement {
-                measurement_data_addr: _,
-                measurement_data_size,
-                measurement_index: _,
-            } => *measurement_data_size,
-
-            ReadMeasurement {
-                measurement_data_addr_out: _,
-                measurement_data_size,
-                measurement_index: _,
-            } => *measurement_data_size,
-        }
-    }
-
-    fn a0(&self) -> u64 {
-        use AttestationFunction::*;
-        match self {
-            GetCapabilities {
-                caps_addr_out,
-                caps_size: _,
-            } => *caps_addr_out,
-
-            GetEvidence {
-                cert_request_addr,
-                cert_request_size: _,
-                request_data_addr: _,
-                evidence_format: _,
-                cert_addr_out: _,
-                cert_size: _,
-            } => *cert_request_addr,
-
-            ExtendMeasurement {
-                measurement_data_addr,
-                measurement_data_size: _,
-                measurement_index: _,
-            } => *measurement_data_addr,
-
-            ReadMeasurement {
-                measurement_data_addr_out,
-                measurement_data_size: _,
-                measurement_index: _,
-            } => *measurement_data_addr_out,
-        }
-    }
-}
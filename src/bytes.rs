@@ -0,0 +1,171 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Little-endian field accessors used to convert the `repr(C)` structures shared with the SBI
+//! implementation to and from bytes. Offsets are expected to be in range; callers check the length
+//! of the buffer with `check_size` first.
+
+use crate::{Error, Result};
+
+/// Returns `InvalidParam` unless `bytes` is exactly `size` bytes long.
+pub(crate) fn check_size(bytes: &[u8], size: usize) -> Result<()> {
+    if bytes.len() != size {
+        return Err(Error::InvalidParam);
+    }
+    Ok(())
+}
+
+pub(crate) fn get_u8(bytes: &[u8], offset: usize) -> u8 {
+    bytes[offset]
+}
+
+pub(crate) fn get_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+pub(crate) fn get_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+// Fills `values` from consecutive u64s starting at `offset`.
+pub(crate) fn get_u64s(bytes: &[u8], offset: usize, values: &mut [u64]) {
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = get_u64(bytes, offset + i * 8);
+    }
+}
+
+pub(crate) fn put_u8(bytes: &mut [u8], offset: usize, value: u8) {
+    bytes[offset] = value;
+}
+
+pub(crate) fn put_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn put_u64(bytes: &mut [u8], offset: usize, value: u64) {
+    bytes[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+// Writes `values` as consecutive u64s starting at `offset`.
+pub(crate) fn put_u64s(bytes: &mut [u8], offset: usize, values: &[u64]) {
+    for (i, value) in values.iter().enumerate() {
+        put_u64(bytes, offset + i * 8, *value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn round_trip() {
+        let info = TsmInfo {
+            tsm_state: TsmState::TsmReady,
+            tsm_version: 3,
+            tvm_state_pages: 4,
+            tvm_max_vcpus: 8,
+            tvm_vcpu_state_pages: 2,
+        };
+        assert_eq!(TsmInfo::from_bytes(&info.to_bytes()), Ok(info));
+
+        let params = TvmCreateParams {
+            tvm_page_directory_addr: 0x8000_4000,
+            tvm_state_addr: 0x8001_0000,
+        };
+        assert_eq!(TvmCreateParams::from_bytes(&params.to_bytes()), Ok(params));
+
+        let params = TvmAiaParams {
+            imsic_base_addr: 0x2800_0000,
+            group_index_bits: 1,
+            group_index_shift: 24,
+            hart_index_bits: 2,
+            guest_index_bits: 3,
+            guests_per_hart: 4,
+        };
+        assert_eq!(TvmAiaParams::from_bytes(&params.to_bytes()), Ok(params));
+
+        let mut caps = AttestationCapabilities::new(
+            5,
+            HashAlgorithm::Sha512,
+            EvidenceFormat::DiceTcbInfo | EvidenceFormat::OpenDice,
+            1,
+            1,
+        );
+        caps.add_measurement_register(MeasurementRegisterDescriptor::new(0, 1, 2, false), 0)
+            .unwrap();
+        caps.add_measurement_register(MeasurementRegisterDescriptor::new(1, 2, 3, true), 1)
+            .unwrap();
+        assert_eq!(
+            AttestationCapabilities::from_bytes(&caps.to_bytes()),
+            Ok(caps)
+        );
+
+        let mut shmem = NaclShmem::from_bytes(&[0; NaclShmem::SIZE]).unwrap();
+        shmem.scratch[1] = 1;
+        shmem.hfence_queue[2] = NaclHfenceEntry::new(NaclHfenceType::Vvma, 0, 1, 2, 3, 4);
+        shmem.dirty_bitmap[3] = 6;
+        shmem.csrs[NaclShmem::csr_index(0x200)] = 5;
+        let bytes = shmem.to_bytes();
+        assert_eq!(super::get_u64(&bytes, NaclShmem::csr_offset(0x200)), 5);
+        let decoded = NaclShmem::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.scratch, shmem.scratch);
+        assert_eq!(decoded.hfence_queue, shmem.hfence_queue);
+        assert_eq!(decoded.dirty_bitmap, shmem.dirty_bitmap);
+        assert_eq!(decoded.csrs, shmem.csrs);
+        assert_eq!(decoded.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn invalid_input() {
+        let info = TsmInfo::default().to_bytes();
+        assert!(TsmInfo::from_bytes(&info[..TsmInfo::SIZE - 1]).is_err());
+        assert!(TsmInfo::from_bytes(&[0; TsmInfo::SIZE + 1]).is_err());
+        let mut bad_state = info;
+        bad_state[0] = 3;
+        assert_eq!(TsmInfo::from_bytes(&bad_state), Err(Error::InvalidParam));
+
+        let caps = AttestationCapabilities::default().to_bytes();
+        let mut bad_hash = caps;
+        bad_hash[8] = 0;
+        assert_eq!(
+            AttestationCapabilities::from_bytes(&bad_hash),
+            Err(Error::InvalidParam)
+        );
+        let mut bad_formats = caps;
+        bad_formats[9] = 0x80;
+        assert_eq!(
+            AttestationCapabilities::from_bytes(&bad_formats),
+            Err(Error::InvalidParam)
+        );
+        let mut too_many = caps;
+        too_many[10] = MAX_MEASUREMENT_REGISTERS as u8;
+        too_many[11] = 1;
+        assert_eq!(
+            AttestationCapabilities::from_bytes(&too_many),
+            Err(Error::InvalidParam)
+        );
+        // The register counts used to overflow when summed.
+        let mut oversized = AttestationCapabilities::new(
+            0,
+            HashAlgorithm::Sha512,
+            EvidenceFormat::DiceTcbInfo,
+            255,
+            255,
+        );
+        assert!(oversized
+            .add_measurement_register(MeasurementRegisterDescriptor::default(), 300)
+            .is_err());
+        let mut bad_runtime = caps;
+        bad_runtime[12 + 3] = 2;
+        assert_eq!(
+            AttestationCapabilities::from_bytes(&bad_runtime),
+            Err(Error::InvalidParam)
+        );
+
+        assert!(TvmCreateParams::from_bytes(&[0; 8]).is_err());
+        assert!(TvmAiaParams::from_bytes(&[0; 40]).is_err());
+        assert!(NaclShmem::from_bytes(&[0; 16]).is_err());
+        assert!(NaclShmem::from_bytes(&[0; NaclShmem::SIZE + 8]).is_err());
+    }
+}
@@ -5,11 +5,29 @@
 #![allow(missing_docs, dead_code)]
 
 // Extension constants
+pub const EXT_LEGACY_SET_TIMER: u64 = 0x00;
 pub const EXT_PUT_CHAR: u64 = 0x01;
+pub const EXT_LEGACY_GET_CHAR: u64 = 0x02;
+pub const EXT_LEGACY_CLEAR_IPI: u64 = 0x03;
+pub const EXT_LEGACY_SEND_IPI: u64 = 0x04;
+pub const EXT_LEGACY_REMOTE_FENCE_I: u64 = 0x05;
+pub const EXT_LEGACY_REMOTE_SFENCE_VMA: u64 = 0x06;
+pub const EXT_LEGACY_REMOTE_SFENCE_VMA_ASID: u64 = 0x07;
+pub const EXT_LEGACY_SHUTDOWN: u64 = 0x08;
 pub const EXT_BASE: u64 = 0x10;
 pub const EXT_HART_STATE: u64 = 0x48534D;
+pub const EXT_TIME: u64 = 0x54494D45; // TIME
+pub const EXT_IPI: u64 = 0x735049; // sPI
+pub const EXT_RFENCE: u64 = 0x52464E43; // RFNC
 pub const EXT_PMU: u64 = 0x504D55;
+pub const EXT_CPPC: u64 = 0x43505043; // CPPC
+pub const EXT_STA: u64 = 0x535441; // STA
+pub const EXT_FWFT: u64 = 0x46574654; // FWFT
+pub const EXT_DBTR: u64 = 0x44425452; // DBTR
+pub const EXT_SSE: u64 = 0x535345; // SSE
+pub const EXT_MPXY: u64 = 0x4D505859; // MPXY
 pub const EXT_RESET: u64 = 0x53525354;
+pub const EXT_SUSP: u64 = 0x53555350; // SUSP
 pub const EXT_DBCN: u64 = 0x4442434E; // DBCN
 pub const EXT_ATTESTATION: u64 = 0x41545354; // ATST
 pub const EXT_NACL: u64 = 0x4E41434C; // NACL
@@ -17,8 +35,12 @@ pub const EXT_COVE_HOST: u64 = 0x434F5648; // COVH
 pub const EXT_COVE_INTERRUPT: u64 = 0x434F5649; // COVI
 pub const EXT_COVE_GUEST: u64 = 0x434F5647; // COVG
 
+pub const EXT_EXPERIMENTAL_RANGE_START: u64 = 0x08000000;
+pub const EXT_EXPERIMENTAL_RANGE_END: u64 = 0x08FFFFFF;
 pub const EXT_VENDOR_RANGE_START: u64 = 0x09000000;
 pub const EXT_VENDOR_RANGE_END: u64 = 0x09FFFFFF;
+pub const EXT_FIRMWARE_RANGE_START: u64 = 0x0A000000;
+pub const EXT_FIRMWARE_RANGE_END: u64 = 0x0AFFFFFF;
 
 pub const SBI_SUCCESS: i64 = 0;
 pub const SBI_ERR_INVALID_ADDRESS: i64 = -5;
@@ -2,11 +2,10 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::error::*;
 use crate::function::*;
 
 /// Functions provided by the COVE Guest extension to TVM guests.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
 pub enum CoveGuestFunction {
     /// Marks the specified range of guest physical address space as used for emulated MMIO. Upon
     /// return, all accesses by the TVM within the range are trapped and may be emulated by the
@@ -16,10 +15,13 @@ pub enum CoveGuestFunction {
     /// memory regions. Returns 0 on success.
     ///
     /// a6 = 0
+    #[sbi(fid = 0)]
     AddMmioRegion {
         /// a0 = start address of the region
+        #[sbi(reg = a0)]
         addr: u64,
         /// a1 = length of the region
+        #[sbi(reg = a1)]
         len: u64,
     },
     /// Removes the specified range of guest physical address space from the emulated MMIO regions. Upon
@@ -28,10 +30,13 @@ pub enum CoveGuestFunction {
     /// Both `addr` and `len` must be 4kB-aligned.
     ///
     /// a6 = 1
+    #[sbi(fid = 1)]
     RemoveMmioRegion {
         /// a0 = start address of the region
+        #[sbi(reg = a0)]
         addr: u64,
         /// a1 = length of the region
+        #[sbi(reg = a1)]
         len: u64,
     },
     /// Requests conversion of the specified range of guest physical address space from confidential
@@ -58,10 +63,13 @@ pub enum CoveGuestFunction {
     /// of confidential memory.
     ///
     /// a6 = 2
+    #[sbi(fid = 2)]
     ShareMemory {
         /// a0 = start address of the region
+        #[sbi(reg = a0)]
         addr: u64,
         /// a1 = length of the region
+        #[sbi(reg = a1)]
         len: u64,
     },
     /// Requests conversion of the specified range of guest physical address space from shared to
@@ -89,10 +97,13 @@ pub enum CoveGuestFunction {
     /// of shared memory.
     ///
     /// a6 = 3
+    #[sbi(fid = 3)]
     UnshareMemory {
         /// a0 = start address of the region
+        #[sbi(reg = a0)]
         addr: u64,
         /// a1 = length of the region
+        #[sbi(reg = a1)]
         len: u64,
     },
     /// Allows injection of the specified external interrupt ID into the calling TVM vCPU. Passing
@@ -102,8 +113,10 @@ pub enum CoveGuestFunction {
     /// Returns an error if the specified external interrupt ID is invalid.
     ///
     /// a6 = 4
+    #[sbi(fid = 4)]
     AllowExternalInterrupt {
         /// a0 = interrupt ID
+        #[sbi(reg = a0)]
         id: i64,
     },
     /// Denies injection of the specified external interrupt ID into the calling TVM vCPU. Passing
@@ -112,73 +125,10 @@ pub enum CoveGuestFunction {
     /// Returns an error if the specified external interrupt ID is invalid.
     ///
     /// a6 = 5
+    #[sbi(fid = 5)]
     DenyExternalInterrupt {
         /// a0 = interrupt ID
+        #[sbi(reg = a0)]
         id: i64,
     },
 }
-
-impl CoveGuestFunction {
-    /// Attempts to parse `Self` from the passed in `a0-a7`.
-    pub(crate) fn from_regs(args: &[u64]) -> Result<Self> {
-        use CoveGuestFunction::*;
-        match args[6] {
-            0 => Ok(AddMmioRegion {
-                addr: args[0],
-                len: args[1],
-            }),
-            1 => Ok(RemoveMmioRegion {
-                addr: args[0],
-                len: args[1],
-            }),
-            2 => Ok(ShareMemory {
-                addr: args[0],
-                len: args[1],
-            }),
-            3 => Ok(UnshareMemory {
-                addr: args[0],
-                len: args[1],
-            }),
-            4 => Ok(AllowExternalInterrupt { id: args[0] as i64 }),
-            5 => Ok(DenyExternalInterrupt { id: args[0] as i64 }),
-            _ => Err(Error::NotSupported),
-        }
-    }
-}
-
-impl SbiFunction for CoveGuestFunction {
-    fn a6(&self) -> u64 {
-        use CoveGuestFunction::*;
-        match self {
-            AddMmioRegion { .. } => 0,
-            RemoveMmioRegion { .. } => 1,
-            ShareMemory { .. } => 2,
-            UnshareMemory { .. } => 3,
-            AllowExternalInterrupt { .. } => 4,
-            DenyExternalInterrupt { .. } => 5,
-        }
-    }
-
-    fn a0(&self) -> u64 {
-        use CoveGuestFunction::*;
-        match self {
-            AddMmioRegion { addr, len: _ } => *addr,
-            RemoveMmioRegion { addr, len: _ } => *addr,
-            ShareMemory { addr, len: _ } => *addr,
-            UnshareMemory { addr, len: _ } => *addr,
-            AllowExternalInterrupt { id } => *id as u64,
-            DenyExternalInterrupt { id } => *id as u64,
-        }
-    }
-
-    fn a1(&self) -> u64 {
-        use CoveGuestFunction::*;
-        match self {
-            AddMmioRegion { addr: _, len } => *len,
-            RemoveMmioRegion { addr: _, len } => *len,
-            ShareMemory { addr: _, len } => *len,
-            UnshareMemory { addr: _, len } => *len,
-            _ => 0,
-        }
-    }
-}
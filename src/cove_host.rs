@@ -2,8 +2,13 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::bytes::*;
 use crate::error::*;
 use crate::function::*;
+use crate::guest_memory::{read_struct, GuestMemory};
+
+use core::mem::{align_of, offset_of, size_of};
+use static_assertions::const_assert;
 
 /// Layout of `scratch` in the `NaclShmem` structure when used with `TvmCpuRun`. Used to communicate
 /// a TVM's exit status to the host.
@@ -35,7 +40,7 @@ impl Default for TsmShmemScratch {
 
 /// Provides the state of the confidential VM supervisor.
 #[repr(u32)]
-#[derive(Copy, Clone, PartialEq, Eq, Default)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub enum TsmState {
     /// TSM has not been loaded on this platform.
     #[default]
@@ -46,10 +51,23 @@ pub enum TsmState {
     TsmReady = 2,
 }
 
+impl TsmState {
+    /// Creates a TSM state from its raw value or returns an error if the value is unknown.
+    pub fn from_raw_value(value: u32) -> Result<Self> {
+        use TsmState::*;
+        match value {
+            0 => Ok(TsmNotLoaded),
+            1 => Ok(TsmLoaded),
+            2 => Ok(TsmReady),
+            _ => Err(Error::InvalidParam),
+        }
+    }
+}
+
 /// Information returned from the system about the entity that manages confidential VMs and
 /// confidential memory isolation.
 #[repr(C)]
-#[derive(Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct TsmInfo {
     /// The current state of the TSM. If the state is not `TsmReady`, the remaining fields are
     /// invalid and will be initialized to 0.
@@ -65,8 +83,54 @@ pub struct TsmInfo {
     pub tvm_vcpu_state_pages: u64,
 }
 
+impl TsmInfo {
+    /// The size of `TsmInfo` in bytes.
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Decodes a `TsmInfo` as written by the TSM. Returns `InvalidParam` if `bytes` isn't exactly
+    /// `SIZE` bytes long or `tsm_state` holds an unknown state.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        check_size(bytes, Self::SIZE)?;
+        Ok(Self {
+            tsm_state: TsmState::from_raw_value(get_u32(bytes, offset_of!(Self, tsm_state)))?,
+            tsm_version: get_u32(bytes, offset_of!(Self, tsm_version)),
+            tvm_state_pages: get_u64(bytes, offset_of!(Self, tvm_state_pages)),
+            tvm_max_vcpus: get_u64(bytes, offset_of!(Self, tvm_max_vcpus)),
+            tvm_vcpu_state_pages: get_u64(bytes, offset_of!(Self, tvm_vcpu_state_pages)),
+        })
+    }
+
+    /// Encodes this `TsmInfo` in the layout the TSM returns it in.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        put_u32(
+            &mut bytes,
+            offset_of!(Self, tsm_state),
+            self.tsm_state as u32,
+        );
+        put_u32(&mut bytes, offset_of!(Self, tsm_version), self.tsm_version);
+        put_u64(
+            &mut bytes,
+            offset_of!(Self, tvm_state_pages),
+            self.tvm_state_pages,
+        );
+        put_u64(
+            &mut bytes,
+            offset_of!(Self, tvm_max_vcpus),
+            self.tvm_max_vcpus,
+        );
+        put_u64(
+            &mut bytes,
+            offset_of!(Self, tvm_vcpu_state_pages),
+            self.tvm_vcpu_state_pages,
+        );
+        bytes
+    }
+}
+
 /// Parameters used for creating a new confidential VM.
 #[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct TvmCreateParams {
     /// The base physical address of the 16kB confidential memory region that should be used for the
     /// TVM's page directory. Must be 16kB-aligned.
@@ -76,6 +140,57 @@ pub struct TvmCreateParams {
     pub tvm_state_addr: u64,
 }
 
+impl TvmCreateParams {
+    /// The size of `TvmCreateParams` in bytes.
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Decodes `TvmCreateParams` from `bytes`. Returns `InvalidParam` if `bytes` isn't exactly
+    /// `SIZE` bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        check_size(bytes, Self::SIZE)?;
+        Ok(Self {
+            tvm_page_directory_addr: get_u64(bytes, offset_of!(Self, tvm_page_directory_addr)),
+            tvm_state_addr: get_u64(bytes, offset_of!(Self, tvm_state_addr)),
+        })
+    }
+
+    /// Encodes these parameters in the layout `TvmCreate` takes them in.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        put_u64(
+            &mut bytes,
+            offset_of!(Self, tvm_page_directory_addr),
+            self.tvm_page_directory_addr,
+        );
+        put_u64(
+            &mut bytes,
+            offset_of!(Self, tvm_state_addr),
+            self.tvm_state_addr,
+        );
+        bytes
+    }
+
+    /// Reads the parameters passed to `TvmCreate` from the guest physical address `addr`, where
+    /// `len` is the length passed with it. Returns `InvalidAddress` if `addr` is misaligned, `len`
+    /// is too short, or the memory can't be read.
+    pub fn read_from(mem: &impl GuestMemory, addr: u64, len: u64) -> Result<Self> {
+        let bytes = read_struct::<{ TvmCreateParams::SIZE }>(mem, addr, len, align_of::<Self>())?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+fn _assert_tsm_abi_layout() {
+    const_assert!(TsmInfo::SIZE == 32);
+    const_assert!(offset_of!(TsmInfo, tsm_state) == 0);
+    const_assert!(offset_of!(TsmInfo, tsm_version) == 4);
+    const_assert!(offset_of!(TsmInfo, tvm_state_pages) == 8);
+    const_assert!(offset_of!(TsmInfo, tvm_max_vcpus) == 16);
+    const_assert!(offset_of!(TsmInfo, tvm_vcpu_state_pages) == 24);
+    const_assert!(TvmCreateParams::SIZE == 16);
+    const_assert!(offset_of!(TvmCreateParams, tvm_page_directory_addr) == 0);
+    const_assert!(offset_of!(TvmCreateParams, tvm_state_addr) == 8);
+}
+
 /// Types of pages allowed to used for creating or managing confidential VMs.
 #[repr(u64)]
 #[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
@@ -116,17 +231,30 @@ impl TsmPageType {
     }
 }
 
+impl SbiReg for TsmPageType {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_reg(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        *self as u64
+    }
+}
+
 /// Functions provided by the COVE Host extension.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
 pub enum CoveHostFunction {
     /// Writes up to `len` bytes of the `TsmInfo` structure to the non-confidential physical address
     /// `dest_addr`. Returns the number of bytes written.
     ///
     /// a6 = 0
+    #[sbi(fid = 0)]
     TsmGetInfo {
         /// a0 = destination address of the `TsmInfo` structure
+        #[sbi(reg = a0)]
         dest_addr: u64,
         /// a1 = maximum number of bytes to be written
+        #[sbi(reg = a1)]
         len: u64,
     },
     /// Converts `num_pages` of 4kB page-size non-confidential memory starting at `page_addr`. The converted pages
@@ -134,20 +262,26 @@ pub enum CoveHostFunction {
     /// fence procedure, described below, has been completed.
     ///
     /// a6 = 1
+    #[sbi(fid = 1)]
     TsmConvertPages {
         /// a0 = base address of pages to convert
+        #[sbi(reg = a0)]
         page_addr: u64,
         /// a1 = number of pages
+        #[sbi(reg = a1)]
         num_pages: u64,
     },
     /// Reclaims `num_pages` of 4kB page-size confidential memory starting at `page_addr`. The pages must not
     /// be currently assigned to an active TVM.
     ///
     /// a6 = 2
+    #[sbi(fid = 2)]
     TsmReclaimPages {
         /// a0 = base address of pages to reclaim
+        #[sbi(reg = a0)]
         page_addr: u64,
         /// a1 = number of pages
+        #[sbi(reg = a1)]
         num_pages: u64,
     },
     /// Initiates a TLB invalidation sequence for all pages marked for conversion via calls to
@@ -158,21 +292,26 @@ pub enum CoveHostFunction {
     /// invalidation sequence is already in progress.
     ///
     /// a6 = 3
+    #[sbi(fid = 3)]
     TsmInitiateFence,
     /// Invalidates TLB entries for all pages pending conversion by an in-progress TLB invalidation
     /// operation on the local CPU.
     ///
     /// a6 = 4
+    #[sbi(fid = 4)]
     TsmLocalFence,
     /// Creates a TVM from the parameters in the `TvmCreateParams` structure at the non-confidential
     /// physical address `params_addr`. Returns a guest ID that can be used to refer to the TVM in
     /// TVM management TEECALLs.
     ///
     /// a6 = 5
+    #[sbi(fid = 5)]
     TvmCreate {
         /// a0 = base physical address of the `TvmCreateParams` structure
+        #[sbi(reg = a0)]
         params_addr: u64,
         /// a1 = length of the `TvmCreateParams` structure in bytes
+        #[sbi(reg = a1)]
         len: u64,
     },
     /// Moves a VM from the "Initializing" state to the "Runnable" state, finalizing the
@@ -180,19 +319,25 @@ pub enum CoveHostFunction {
     /// entry point (SEPC and opaque argument passed in A1) for the boot vCPU of the TVM.
     ///
     /// a6 = 6
+    #[sbi(fid = 6)]
     Finalize {
         /// a0 = guest id
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = entry SEPC
+        #[sbi(reg = a1)]
         entry_sepc: u64,
         /// a2 = entry argument (A1)
+        #[sbi(reg = a2)]
         entry_arg: u64,
     },
     /// Message to destroy a TVM created with `TvmCreate`.
     ///
     /// a6 = 7
+    #[sbi(fid = 7)]
     TvmDestroy {
         /// a0 = guest id returned from `TvmCreate`.
+        #[sbi(reg = a0)]
         guest_id: u64,
     },
     /// Adds a memory region to the TVM identified by `guest_id` at the specified range of guest
@@ -204,24 +349,32 @@ pub enum CoveHostFunction {
     /// regions. Memory regions may only be added prior to TVM finalization.
     ///
     /// a6 = 8
+    #[sbi(fid = 8)]
     TvmAddMemoryRegion {
         /// a0 = guest id
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = start of the region
+        #[sbi(reg = a1)]
         guest_addr: u64,
         /// a2 = length of the region
+        #[sbi(reg = a2)]
         len: u64,
     },
     /// Adds `num_pages` 4kB pages of confidential memory starting at `page_addr` to the page-table
     /// page pool for the specified guest.
     ///
     /// a6 = 9
+    #[sbi(fid = 9)]
     AddPageTablePages {
         /// a0 = guest_id
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = address of the first page
+        #[sbi(reg = a1)]
         page_addr: u64,
         /// a2 = number of pages
+        #[sbi(reg = a2)]
         num_pages: u64,
     },
     /// Copies `num_pages` pages from non-confidential memory at `src_addr` to confidential
@@ -231,18 +384,25 @@ pub enum CoveHostFunction {
     /// finalization.
     ///
     /// a6 = 10
+    #[sbi(fid = 10)]
     TvmAddMeasuredPages {
         /// a0 = guest_id
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = physical address of the pages to copy from
+        #[sbi(reg = a1)]
         src_addr: u64,
         /// a2 = physical address of the pages to insert
+        #[sbi(reg = a2)]
         dest_addr: u64,
         /// a3 = page size
+        #[sbi(reg = a3)]
         page_type: TsmPageType,
         /// a4 = number of pages
+        #[sbi(reg = a4)]
         num_pages: u64,
         /// a5 = guest physical address
+        #[sbi(reg = a5)]
         guest_addr: u64,
     },
     /// Maps `num_pages` zero-filled pages of confidential memory starting at `page_addr` into the
@@ -251,32 +411,44 @@ pub enum CoveHostFunction {
     /// the TVM has been finalized.
     ///
     /// a6 = 11
+    #[sbi(fid = 11)]
     TvmAddZeroPages {
         /// a0 = guest_id
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = physical address of the pages to insert
+        #[sbi(reg = a1)]
         page_addr: u64,
         /// a2 = page size
+        #[sbi(reg = a2)]
         page_type: TsmPageType,
         /// a3 = number of pages
+        #[sbi(reg = a3)]
         num_pages: u64,
         /// a4 = guest physical address
+        #[sbi(reg = a4)]
         guest_addr: u64,
     },
     /// Maps non-confidential shared pages in a region of shared memory previously registered by
     /// the guest via `ShareMemory` in the COVE-Guest API.
     ///
     /// a6 = 12
+    #[sbi(fid = 12)]
     TvmAddSharedPages {
         /// a0 = guest id
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = start of the shared memory region
+        #[sbi(reg = a1)]
         page_addr: u64,
         /// a2 = page size (must be Page4k for now)
+        #[sbi(reg = a2)]
         page_type: TsmPageType,
         /// a3 = number of pages
+        #[sbi(reg = a3)]
         num_pages: u64,
         /// a4 = guest physical address
+        #[sbi(reg = a4)]
         guest_addr: u64,
     },
     /// Adds a vCPU with ID `vcpu_id` to the guest `guest_id`, using the memory at `stage_page_addr`
@@ -288,12 +460,16 @@ pub enum CoveHostFunction {
     /// vCPUs may not be added after the TVM is finalized.
     ///
     /// a6 = 13
+    #[sbi(fid = 13)]
     TvmCpuCreate {
         /// a0 = guest id
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = vCPU id
+        #[sbi(reg = a1)]
         vcpu_id: u64,
         /// a2 = address of the first page donated for the vCPU state
+        #[sbi(reg = a2)]
         state_page_addr: u64,
     },
     /// Runs the given vCPU in the TVM
@@ -327,10 +503,13 @@ pub enum CoveHostFunction {
     ///     this physical CPU
     ///
     /// a6 = 14
+    #[sbi(fid = 14)]
     TvmCpuRun {
         /// a0 = guest id
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = vCPU id
+        #[sbi(reg = a1)]
         vcpu_id: u64,
     },
     /// Initiates a TLB invalidation sequence for all pages that have been invalidated in the
@@ -341,8 +520,10 @@ pub enum CoveHostFunction {
     /// returned if a TLB invalidation sequence is already in progress for the TVM.
     ///
     /// a6 = 15
+    #[sbi(fid = 15)]
     TvmInitiateFence {
         /// a0 = guest id
+        #[sbi(reg = a0)]
         guest_id: u64,
     },
     /// Invalidates the pages in the specified range of guest physical address space.
@@ -365,12 +546,16 @@ pub enum CoveHostFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 16
+    #[sbi(fid = 16)]
     TvmBlockPages {
         /// a0 = guest_id   
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = guest physical address
+        #[sbi(reg = a1)]
         guest_addr: u64,
         /// a2 = length of the range
+        #[sbi(reg = a2)]
         len: u64,
     },
     /// Marks the invalidated pages in the specified range of guest physical address space
@@ -393,12 +578,16 @@ pub enum CoveHostFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 17
+    #[sbi(fid = 17)]
     TvmUnblockPages {
         /// a0 = guest_id   
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = guest physical address
+        #[sbi(reg = a1)]
         guest_addr: u64,
         /// a2 = length of the range
+        #[sbi(reg = a2)]
         len: u64,
     },
     /// Promotes a set of contiguous mappings to the requested page size.
@@ -424,12 +613,16 @@ pub enum CoveHostFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 18
+    #[sbi(fid = 18)]
     TvmPromotePage {
         /// a0 = guest_id   
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = guest physical address
+        #[sbi(reg = a1)]
         guest_addr: u64,
         /// a2 = page size
+        #[sbi(reg = a2)]
         page_type: TsmPageType,
     },
     /// Demotes a huge page mapping to a set of contiguous mappings at the target size.
@@ -455,12 +648,16 @@ pub enum CoveHostFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 19
+    #[sbi(fid = 19)]
     TvmDemotePage {
         /// a0 = guest_id   
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = guest physical address
+        #[sbi(reg = a1)]
         guest_addr: u64,
         /// a2 = page size
+        #[sbi(reg = a2)]
         page_type: TsmPageType,
     },
     /// Removes mappings from a TVM. The range to be unmapped must already have been invalidated
@@ -487,543 +684,16 @@ pub enum CoveHostFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 20
+    #[sbi(fid = 20)]
     TvmRemovePages {
         /// a0 = guest_id   
+        #[sbi(reg = a0)]
         guest_id: u64,
         /// a1 = guest physical address
+        #[sbi(reg = a1)]
         guest_addr: u64,
         /// a2 = length of the range
+        #[sbi(reg = a2)]
         len: u64,
     },
 }
-
-impl CoveHostFunction {
-    /// Attempts to parse `Self` from the passed in `a0-a7`.
-    pub(crate) fn from_regs(args: &[u64]) -> Result<Self> {
-        use CoveHostFunction::*;
-        match args[6] {
-            0 => Ok(TsmGetInfo {
-                dest_addr: args[0],
-                len: args[1],
-            }),
-            1 => Ok(TsmConvertPages {
-                page_addr: args[0],
-                num_pages: args[1],
-            }),
-            2 => Ok(TsmReclaimPages {
-                page_addr: args[0],
-                num_pages: args[1],
-            }),
-            3 => Ok(TsmInitiateFence),
-            4 => Ok(TsmLocalFence),
-            5 => Ok(TvmCreate {
-                params_addr: args[0],
-                len: args[1],
-            }),
-            6 => Ok(Finalize {
-                guest_id: args[0],
-                entry_sepc: args[1],
-                entry_arg: args[2],
-            }),
-            7 => Ok(TvmDestroy { guest_id: args[0] }),
-            8 => Ok(TvmAddMemoryRegion {
-                guest_id: args[0],
-                guest_addr: args[1],
-                len: args[2],
-            }),
-            9 => Ok(AddPageTablePages {
-                guest_id: args[0],
-                page_addr: args[1],
-                num_pages: args[2],
-            }),
-            10 => Ok(TvmAddMeasuredPages {
-                guest_id: args[0],
-                src_addr: args[1],
-                dest_addr: args[2],
-                page_type: TsmPageType::from_reg(args[3])?,
-                num_pages: args[4],
-                guest_addr: args[5],
-            }),
-            11 => Ok(TvmAddZeroPages {
-                guest_id: args[0],
-                page_addr: args[1],
-                page_type: TsmPageType::from_reg(args[2])?,
-                num_pages: args[3],
-                guest_addr: args[4],
-            }),
-            12 => Ok(TvmAddSharedPages {
-                guest_id: args[0],
-                page_addr: args[1],
-                page_type: TsmPageType::from_reg(args[2])?,
-                num_pages: args[3],
-                guest_addr: args[4],
-            }),
-            13 => Ok(TvmCpuCreate {
-                guest_id: args[0],
-                vcpu_id: args[1],
-                state_page_addr: args[2],
-            }),
-            14 => Ok(TvmCpuRun {
-                guest_id: args[0],
-                vcpu_id: args[1],
-            }),
-            15 => Ok(TvmInitiateFence { guest_id: args[0] }),
-            16 => Ok(TvmBlockPages {
-                guest_id: args[0],
-                guest_addr: args[1],
-                len: args[2],
-            }),
-            17 => Ok(TvmUnblockPages {
-                guest_id: args[0],
-                guest_addr: args[1],
-                len: args[2],
-            }),
-            18 => Ok(TvmPromotePage {
-                guest_id: args[0],
-                guest_addr: args[1],
-                page_type: TsmPageType::from_reg(args[2])?,
-            }),
-            19 => Ok(TvmDemotePage {
-                guest_id: args[0],
-                guest_addr: args[1],
-                page_type: TsmPageType::from_reg(args[2])?,
-            }),
-            20 => Ok(TvmRemovePages {
-                guest_id: args[0],
-                guest_addr: args[1],
-                len: args[2],
-            }),
-            _ => Err(Error::NotSupported),
-        }
-    }
-}
-
-impl SbiFunction for CoveHostFunction {
-    fn a6(&self) -> u64 {
-        use CoveHostFunction::*;
-        match self {
-            TsmGetInfo {
-                dest_addr: _,
-                len: _,
-            } => 0,
-            TsmConvertPages {
-                page_addr: _,
-                num_pages: _,
-            } => 1,
-            TsmReclaimPages {
-                page_addr: _,
-                num_pages: _,
-            } => 2,
-            TsmInitiateFence => 3,
-            TsmLocalFence => 4,
-            TvmCreate {
-                params_addr: _,
-                len: _,
-            } => 5,
-            Finalize {
-                guest_id: _,
-                entry_sepc: _,
-                entry_arg: _,
-            } => 6,
-            TvmDestroy { guest_id: _ } => 7,
-            TvmAddMemoryRegion {
-                guest_id: _,
-                guest_addr: _,
-                len: _,
-            } => 8,
-            AddPageTablePages {
-                guest_id: _,
-                page_addr: _,
-                num_pages: _,
-            } => 9,
-            TvmAddMeasuredPages {
-                guest_id: _,
-                src_addr: _,
-                dest_addr: _,
-                page_type: _,
-                num_pages: _,
-                guest_addr: _,
-            } => 10,
-            TvmAddZeroPages {
-                guest_id: _,
-                page_addr: _,
-                page_type: _,
-                num_pages: _,
-                guest_addr: _,
-            } => 11,
-            TvmAddSharedPages {
-                guest_id: _,
-                page_addr: _,
-                page_type: _,
-                num_pages: _,
-                guest_addr: _,
-            } => 12,
-            TvmCpuCreate {
-                guest_id: _,
-                vcpu_id: _,
-                state_page_addr: _,
-            } => 13,
-            TvmCpuRun {
-                guest_id: _,
-                vcpu_id: _,
-            } => 14,
-            TvmInitiateFence { guest_id: _ } => 15,
-            TvmBlockPages {
-                guest_id: _,
-                guest_addr: _,
-                len: _,
-            } => 16,
-            TvmUnblockPages {
-                guest_id: _,
-                guest_addr: _,
-                len: _,
-            } => 17,
-            TvmPromotePage {
-                guest_id: _,
-                guest_addr: _,
-                page_type: _,
-            } => 18,
-            TvmDemotePage {
-                guest_id: _,
-                guest_addr: _,
-                page_type: _,
-            } => 19,
-            TvmRemovePages {
-                guest_id: _,
-                guest_addr: _,
-                len: _,
-            } => 20,
-        }
-    }
-
-    fn a0(&self) -> u64 {
-        use CoveHostFunction::*;
-        match self {
-            TvmCreate {
-                params_addr,
-                len: _,
-            } => *params_addr,
-            TvmDestroy { guest_id } => *guest_id,
-            AddPageTablePages {
-                guest_id,
-                page_addr: _,
-                num_pages: _,
-            } => *guest_id,
-            TvmAddZeroPages {
-                guest_id,
-                page_addr: _,
-                page_type: _,
-                num_pages: _,
-                guest_addr: _,
-            } => *guest_id,
-            Finalize {
-                guest_id,
-                entry_sepc: _,
-                entry_arg: _,
-            } => *guest_id,
-            TvmCpuRun {
-                guest_id,
-                vcpu_id: _,
-            } => *guest_id,
-            TvmCpuCreate {
-                guest_id,
-                vcpu_id: _,
-                state_page_addr: _,
-            } => *guest_id,
-            TsmGetInfo { dest_addr, len: _ } => *dest_addr,
-            TvmAddMeasuredPages {
-                guest_id,
-                src_addr: _,
-                dest_addr: _,
-                page_type: _,
-                num_pages: _,
-                guest_addr: _,
-            } => *guest_id,
-            TsmConvertPages {
-                page_addr,
-                num_pages: _,
-            } => *page_addr,
-            TsmReclaimPages {
-                page_addr,
-                num_pages: _,
-            } => *page_addr,
-            TvmAddMemoryRegion {
-                guest_id,
-                guest_addr: _,
-                len: _,
-            } => *guest_id,
-            TvmAddSharedPages {
-                guest_id,
-                page_addr: _,
-                page_type: _,
-                num_pages: _,
-                guest_addr: _,
-            } => *guest_id,
-            TvmInitiateFence { guest_id } => *guest_id,
-            TvmBlockPages {
-                guest_id,
-                guest_addr: _,
-                len: _,
-            } => *guest_id,
-            TvmUnblockPages {
-                guest_id,
-                guest_addr: _,
-                len: _,
-            } => *guest_id,
-            TvmPromotePage {
-                guest_id,
-                guest_addr: _,
-                page_type: _,
-            } => *guest_id,
-            TvmDemotePage {
-                guest_id,
-                guest_addr: _,
-                page_type: _,
-            } => *guest_id,
-            TvmRemovePages {
-                guest_id,
-                guest_addr: _,
-                len: _,
-            } => *guest_id,
-            _ => 0,
-        }
-    }
-
-    fn a1(&self) -> u64 {
-        use CoveHostFunction::*;
-        match self {
-            TvmCreate {
-                params_addr: _,
-                len,
-            } => *len,
-            AddPageTablePages {
-                guest_id: _,
-                page_addr,
-                num_pages: _,
-            } => *page_addr,
-            TvmAddZeroPages {
-                guest_id: _,
-                page_addr,
-                page_type: _,
-                num_pages: _,
-                guest_addr: _,
-            } => *page_addr,
-            Finalize {
-                guest_id: _,
-                entry_sepc,
-                entry_arg: _,
-            } => *entry_sepc,
-            TvmCpuRun {
-                guest_id: _,
-                vcpu_id,
-            } => *vcpu_id,
-            TvmCpuCreate {
-                guest_id: _,
-                vcpu_id,
-                state_page_addr: _,
-            } => *vcpu_id,
-            TsmGetInfo { dest_addr: _, len } => *len,
-            TvmAddMeasuredPages {
-                guest_id: _,
-                src_addr,
-                dest_addr: _,
-                page_type: _,
-                num_pages: _,
-                guest_addr: _,
-            } => *src_addr,
-            TsmConvertPages {
-                page_addr: _,
-                num_pages,
-            } => *num_pages,
-            TsmReclaimPages {
-                page_addr: _,
-                num_pages,
-            } => *num_pages,
-            TvmAddMemoryRegion {
-                guest_id: _,
-                guest_addr,
-                len: _,
-            } => *guest_addr,
-            TvmAddSharedPages {
-                guest_id: _,
-                page_addr,
-                page_type: _,
-                num_pages: _,
-                guest_addr: _,
-            } => *page_addr,
-            TvmBlockPages {
-                guest_id: _,
-                guest_addr,
-                len: _,
-            } => *guest_addr,
-            TvmUnblockPages {
-                guest_id: _,
-                guest_addr,
-                len: _,
-            } => *guest_addr,
-            TvmPromotePage {
-                guest_id: _,
-                guest_addr,
-                page_type: _,
-            } => *guest_addr,
-            TvmDemotePage {
-                guest_id: _,
-                guest_addr,
-                page_type: _,
-            } => *guest_addr,
-            TvmRemovePages {
-                guest_id: _,
-                guest_addr,
-                len: _,
-            } => *guest_addr,
-            _ => 0,
-        }
-    }
-
-    fn a2(&self) -> u64 {
-        use CoveHostFunction::*;
-        match self {
-            AddPageTablePages {
-                guest_id: _,
-                page_addr: _,
-                num_pages,
-            } => *num_pages,
-            TvmAddZeroPages {
-                guest_id: _,
-                page_addr: _,
-                page_type,
-                num_pages: _,
-                guest_addr: _,
-            } => *page_type as u64,
-            Finalize {
-                guest_id: _,
-                entry_sepc: _,
-                entry_arg,
-            } => *entry_arg,
-            TvmCpuCreate {
-                guest_id: _,
-                vcpu_id: _,
-                state_page_addr,
-            } => *state_page_addr,
-            TvmAddMeasuredPages {
-                guest_id: _,
-                src_addr: _,
-                dest_addr,
-                page_type: _,
-                num_pages: _,
-                guest_addr: _,
-            } => *dest_addr,
-            TvmAddMemoryRegion {
-                guest_id: _,
-                guest_addr: _,
-                len,
-            } => *len,
-            TvmAddSharedPages {
-                guest_id: _,
-                page_addr: _,
-                page_type,
-                num_pages: _,
-                guest_addr: _,
-            } => *page_type as u64,
-            TvmBlockPages {
-                guest_id: _,
-                guest_addr: _,
-                len,
-            } => *len,
-            TvmUnblockPages {
-                guest_id: _,
-                guest_addr: _,
-                len,
-            } => *len,
-            TvmPromotePage {
-                guest_id: _,
-                guest_addr: _,
-                page_type,
-            } => *page_type as u64,
-            TvmDemotePage {
-                guest_id: _,
-                guest_addr: _,
-                page_type,
-            } => *page_type as u64,
-            TvmRemovePages {
-                guest_id: _,
-                guest_addr: _,
-                len,
-            } => *len,
-            _ => 0,
-        }
-    }
-
-    fn a3(&self) -> u64 {
-        use CoveHostFunction::*;
-        match self {
-            TvmAddZeroPages {
-                guest_id: _,
-                page_addr: _,
-                page_type: _,
-                num_pages,
-                guest_addr: _,
-            } => *num_pages,
-            TvmAddMeasuredPages {
-                guest_id: _,
-                src_addr: _,
-                dest_addr: _,
-                page_type,
-                num_pages: _,
-                guest_addr: _,
-            } => *page_type as u64,
-            TvmAddSharedPages {
-                guest_id: _,
-                page_addr: _,
-                page_type: _,
-                num_pages,
-                guest_addr: _,
-            } => *num_pages,
-            _ => 0,
-        }
-    }
-
-    fn a4(&self) -> u64 {
-        use CoveHostFunction::*;
-        match self {
-            TvmAddZeroPages {
-                guest_id: _,
-                page_addr: _,
-                page_type: _,
-                num_pages: _,
-                guest_addr,
-            } => *guest_addr,
-            TvmAddMeasuredPages {
-                guest_id: _,
-                src_addr: _,
-                dest_addr: _,
-                page_type: _,
-                num_pages,
-                guest_addr: _,
-            } => *num_pages,
-            TvmAddSharedPages {
-                guest_id: _,
-                page_addr: _,
-                page_type: _,
-                num_pages: _,
-                guest_addr,
-            } => *guest_addr,
-            _ => 0,
-        }
-    }
-
-    fn a5(&self) -> u64 {
-        use CoveHostFunction::*;
-        match self {
-            TvmAddMeasuredPages {
-                guest_id: _,
-                src_addr: _,
-                dest_addr: _,
-                page_type: _,
-                num_pages: _,
-                guest_addr,
-            } => *guest_addr,
-            _ => 0,
-        }
-    }
-}
@@ -6,12 +6,17 @@
 //! virtualization using the RISC-V Advanced Interrupt Architecture (AIA) on platforms which
 //! support it.
 
+use crate::bytes::*;
 use crate::error::*;
 use crate::function::*;
+use crate::guest_memory::{read_struct, GuestMemory};
+
+use core::mem::{align_of, offset_of, size_of};
+use static_assertions::const_assert;
 
 /// Describes a TVM's AIA configuration.
 #[repr(C)]
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
 pub struct TvmAiaParams {
     /// The base address of the virtualized IMSIC in guest physical address space.
     ///
@@ -39,8 +44,81 @@ pub struct TvmAiaParams {
     pub guests_per_hart: u32,
 }
 
+impl TvmAiaParams {
+    /// The size of `TvmAiaParams` in bytes, including trailing padding.
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Decodes `TvmAiaParams` from `bytes`. Returns `InvalidParam` if `bytes` isn't exactly `SIZE`
+    /// bytes long. The trailing padding is ignored.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        check_size(bytes, Self::SIZE)?;
+        Ok(Self {
+            imsic_base_addr: get_u64(bytes, offset_of!(Self, imsic_base_addr)),
+            group_index_bits: get_u32(bytes, offset_of!(Self, group_index_bits)),
+            group_index_shift: get_u32(bytes, offset_of!(Self, group_index_shift)),
+            hart_index_bits: get_u32(bytes, offset_of!(Self, hart_index_bits)),
+            guest_index_bits: get_u32(bytes, offset_of!(Self, guest_index_bits)),
+            guests_per_hart: get_u32(bytes, offset_of!(Self, guests_per_hart)),
+        })
+    }
+
+    /// Encodes these parameters in the layout `TvmAiaInit` takes them in, with zeroed padding.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        put_u64(
+            &mut bytes,
+            offset_of!(Self, imsic_base_addr),
+            self.imsic_base_addr,
+        );
+        put_u32(
+            &mut bytes,
+            offset_of!(Self, group_index_bits),
+            self.group_index_bits,
+        );
+        put_u32(
+            &mut bytes,
+            offset_of!(Self, group_index_shift),
+            self.group_index_shift,
+        );
+        put_u32(
+            &mut bytes,
+            offset_of!(Self, hart_index_bits),
+            self.hart_index_bits,
+        );
+        put_u32(
+            &mut bytes,
+            offset_of!(Self, guest_index_bits),
+            self.guest_index_bits,
+        );
+        put_u32(
+            &mut bytes,
+            offset_of!(Self, guests_per_hart),
+            self.guests_per_hart,
+        );
+        bytes
+    }
+
+    /// Reads the parameters passed to `TvmAiaInit` from the guest physical address `addr`, where
+    /// `len` is the length passed with it. Returns `InvalidAddress` if `addr` is misaligned, `len`
+    /// is too short, or the memory can't be read.
+    pub fn read_from(mem: &impl GuestMemory, addr: u64, len: u64) -> Result<Self> {
+        let bytes = read_struct::<{ TvmAiaParams::SIZE }>(mem, addr, len, align_of::<Self>())?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+fn _assert_aia_params_layout() {
+    const_assert!(TvmAiaParams::SIZE == 32);
+    const_assert!(offset_of!(TvmAiaParams, imsic_base_addr) == 0);
+    const_assert!(offset_of!(TvmAiaParams, group_index_bits) == 8);
+    const_assert!(offset_of!(TvmAiaParams, group_index_shift) == 12);
+    const_assert!(offset_of!(TvmAiaParams, hart_index_bits) == 16);
+    const_assert!(offset_of!(TvmAiaParams, guest_index_bits) == 20);
+    const_assert!(offset_of!(TvmAiaParams, guests_per_hart) == 24);
+}
+
 /// Functions provided by the COVE Interrupt extension.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
 pub enum CoveInterruptFunction {
     /// Configures AIA virtualization for the TVM identified by `tvm_id` from the parameters in
     /// the `TvmAiaParams` structure at the non-confidential physical address `params_addr`.
@@ -50,12 +128,16 @@ pub enum CoveInterruptFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 0
+    #[sbi(fid = 0)]
     TvmAiaInit {
         /// a0 = TVM ID
+        #[sbi(reg = a0)]
         tvm_id: u64,
         /// a1 = physical address of the `TvmAiaParams` structure
+        #[sbi(reg = a1)]
         params_addr: u64,
         /// a2 = length of the `TvmAiaParams` structure in bytes
+        #[sbi(reg = a2)]
         len: u64,
     },
     /// Sets the guest physical address of the specified vCPU's virtualized IMSIC to `imsic_addr`.
@@ -69,12 +151,16 @@ pub enum CoveInterruptFunction {
     /// Returns 0 on success.
     ///
     /// a6 - 1
+    #[sbi(fid = 1)]
     TvmCpuSetImsicAddr {
         /// a0 = TVM ID
+        #[sbi(reg = a0)]
         tvm_id: u64,
         /// a1 = vCPU ID
+        #[sbi(reg = a1)]
         vcpu_id: u64,
         /// a2 = guest physical address of vCPU's IMSIC
+        #[sbi(reg = a2)]
         imsic_addr: u64,
     },
     /// Converts the non-confidential guest interrupt file at `imsic_addr` for use with a TVM. A TSM
@@ -83,8 +169,10 @@ pub enum CoveInterruptFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 2
+    #[sbi(fid = 2)]
     TsmConvertImsic {
         /// a0 = physical address of interrupt file to be converted
+        #[sbi(reg = a0)]
         imsic_addr: u64,
     },
     /// Reclaims the confidential guest interrupt file at `imsic_addr`. The interrupt file must not
@@ -93,8 +181,10 @@ pub enum CoveInterruptFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 3
+    #[sbi(fid = 3)]
     TsmReclaimImsic {
         /// a0 = physical address of interrupt file to be reclaimed
+        #[sbi(reg = a0)]
         imsic_addr: u64,
     },
     /// Binds a TVM vCPU to the current physical CPU using the confidential guest interrupt files
@@ -108,12 +198,16 @@ pub enum CoveInterruptFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 4
+    #[sbi(fid = 4)]
     TvmCpuBindImsic {
         /// a0 = TVM ID
+        #[sbi(reg = a0)]
         tvm_id: u64,
         /// a1 = vCPU ID
+        #[sbi(reg = a1)]
         vcpu_id: u64,
         /// a2 = bit mask of interrupt files to be bound
+        #[sbi(reg = a2)]
         imsic_mask: u64,
     },
     /// Begins the unbind process for the specified vCPU from its guest interrupt files. The
@@ -124,10 +218,13 @@ pub enum CoveInterruptFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 5
+    #[sbi(fid = 5)]
     TvmCpuUnbindImsicBegin {
         /// a0 = TVM ID
+        #[sbi(reg = a0)]
         tvm_id: u64,
         /// a1 = vCPU ID
+        #[sbi(reg = a1)]
         vcpu_id: u64,
     },
     /// Completes the unbind process for the specified vCPU from its guest interrupt files after
@@ -140,10 +237,13 @@ pub enum CoveInterruptFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 6
+    #[sbi(fid = 6)]
     TvmCpuUnbindImsicEnd {
         /// a0 = TVM ID
+        #[sbi(reg = a0)]
         tvm_id: u64,
         /// a1 = vCPU ID
+        #[sbi(reg = a1)]
         vcpu_id: u64,
     },
     /// Injects an external interrupt with the given `interrupt_id` into the specified vCPU. If the
@@ -155,12 +255,16 @@ pub enum CoveInterruptFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 7
+    #[sbi(fid = 7)]
     TvmCpuInjectExternalInterrupt {
         /// a0 = TVM ID
+        #[sbi(reg = a0)]
         tvm_id: u64,
         /// a1 = vCPU ID
+        #[sbi(reg = a1)]
         vcpu_id: u64,
         /// a2 = interrupt ID
+        #[sbi(reg = a2)]
         interrupt_id: u64,
     },
     /// Begins the rebinding process for the specified vCPU to this physical CPU and the specified
@@ -171,12 +275,16 @@ pub enum CoveInterruptFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 8
+    #[sbi(fid = 8)]
     TvmCpuRebindImsicBegin {
         /// a0 = TVM ID
+        #[sbi(reg = a0)]
         tvm_id: u64,
         /// a1 = vCPU ID
+        #[sbi(reg = a1)]
         vcpu_id: u64,
         /// a2 = bit mask of interrupt files to be bound
+        #[sbi(reg = a2)]
         imsic_mask: u64,
     },
     /// Clones the old guest interrupt file of the specified vCPU. Caller must make sure to invoke this from
@@ -186,10 +294,13 @@ pub enum CoveInterruptFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 9
+    #[sbi(fid = 9)]
     TvmCpuRebindImsicClone {
         /// a0 = TVM ID
+        #[sbi(reg = a0)]
         tvm_id: u64,
         /// a1 = vCPU ID
+        #[sbi(reg = a1)]
         vcpu_id: u64,
     },
     /// Completes the rebind process for the specified vCPU from this physical CPU and its guest
@@ -198,203 +309,13 @@ pub enum CoveInterruptFunction {
     /// Returns 0 on success.
     ///
     /// a6 = 10
+    #[sbi(fid = 10)]
     TvmCpuRebindImsicEnd {
         /// a0 = TVM ID
+        #[sbi(reg = a0)]
         tvm_id: u64,
         /// a1 = vCPU ID
+        #[sbi(reg = a1)]
         vcpu_id: u64,
     },
 }
-
-impl CoveInterruptFunction {
-    /// Attempts to parse `Self` from the register values passed in `a0-a7`.
-    pub(crate) fn from_regs(args: &[u64]) -> Result<Self> {
-        use CoveInterruptFunction::*;
-        match args[6] {
-            0 => Ok(TvmAiaInit {
-                tvm_id: args[0],
-                params_addr: args[1],
-                len: args[2],
-            }),
-            1 => Ok(TvmCpuSetImsicAddr {
-                tvm_id: args[0],
-                vcpu_id: args[1],
-                imsic_addr: args[2],
-            }),
-            2 => Ok(TsmConvertImsic {
-                imsic_addr: args[0],
-            }),
-            3 => Ok(TsmReclaimImsic {
-                imsic_addr: args[0],
-            }),
-            4 => Ok(TvmCpuBindImsic {
-                tvm_id: args[0],
-                vcpu_id: args[1],
-                imsic_mask: args[2],
-            }),
-            5 => Ok(TvmCpuUnbindImsicBegin {
-                tvm_id: args[0],
-                vcpu_id: args[1],
-            }),
-            6 => Ok(TvmCpuUnbindImsicEnd {
-                tvm_id: args[0],
-                vcpu_id: args[1],
-            }),
-            7 => Ok(TvmCpuInjectExternalInterrupt {
-                tvm_id: args[0],
-                vcpu_id: args[1],
-                interrupt_id: args[2],
-            }),
-            8 => Ok(TvmCpuRebindImsicBegin {
-                tvm_id: args[0],
-                vcpu_id: args[1],
-                imsic_mask: args[2],
-            }),
-            9 => Ok(TvmCpuRebindImsicClone {
-                tvm_id: args[0],
-                vcpu_id: args[1],
-            }),
-            10 => Ok(TvmCpuRebindImsicEnd {
-                tvm_id: args[0],
-                vcpu_id: args[1],
-            }),
-            _ => Err(Error::NotSupported),
-        }
-    }
-}
-
-impl SbiFunction for CoveInterruptFunction {
-    fn a6(&self) -> u64 {
-        use CoveInterruptFunction::*;
-        match self {
-            TvmAiaInit { .. } => 0,
-            TvmCpuSetImsicAddr { .. } => 1,
-            TsmConvertImsic { .. } => 2,
-            TsmReclaimImsic { .. } => 3,
-            TvmCpuBindImsic { .. } => 4,
-            TvmCpuUnbindImsicBegin { .. } => 5,
-            TvmCpuUnbindImsicEnd { .. } => 6,
-            TvmCpuInjectExternalInterrupt { .. } => 7,
-            TvmCpuRebindImsicBegin { .. } => 8,
-            TvmCpuRebindImsicClone { .. } => 9,
-            TvmCpuRebindImsicEnd { .. } => 10,
-        }
-    }
-
-    fn a0(&self) -> u64 {
-        use CoveInterruptFunction::*;
-        match self {
-            TvmAiaInit {
-                tvm_id,
-                params_addr: _,
-                len: _,
-            } => *tvm_id,
-            TvmCpuSetImsicAddr {
-                tvm_id,
-                vcpu_id: _,
-                imsic_addr: _,
-            } => *tvm_id,
-            TsmConvertImsic { imsic_addr } => *imsic_addr,
-            TsmReclaimImsic { imsic_addr } => *imsic_addr,
-            TvmCpuBindImsic {
-                tvm_id,
-                vcpu_id: _,
-                imsic_mask: _,
-            } => *tvm_id,
-            TvmCpuUnbindImsicBegin { tvm_id, vcpu_id: _ } => *tvm_id,
-            TvmCpuUnbindImsicEnd { tvm_id, vcpu_id: _ } => *tvm_id,
-            TvmCpuInjectExternalInterrupt {
-                tvm_id,
-                vcpu_id: _,
-                interrupt_id: _,
-            } => *tvm_id,
-            TvmCpuRebindImsicBegin {
-                tvm_id,
-                vcpu_id: _,
-                imsic_mask: _,
-            } => *tvm_id,
-            TvmCpuRebindImsicEnd { tvm_id, vcpu_id: _ } => *tvm_id,
-            TvmCpuRebindImsicClone { tvm_id, vcpu_id: _ } => *tvm_id,
-        }
-    }
-
-    fn a1(&self) -> u64 {
-        use CoveInterruptFunction::*;
-        match self {
-            TvmAiaInit {
-                tvm_id: _,
-                params_addr,
-                len: _,
-            } => *params_addr,
-            TvmCpuSetImsicAddr {
-                tvm_id: _,
-                vcpu_id,
-                imsic_addr: _,
-            } => *vcpu_id,
-            TvmCpuBindImsic {
-                tvm_id: _,
-                vcpu_id,
-                imsic_mask: _,
-            } => *vcpu_id,
-            TvmCpuUnbindImsicBegin { tvm_id: _, vcpu_id } => *vcpu_id,
-            TvmCpuUnbindImsicEnd { tvm_id: _, vcpu_id } => *vcpu_id,
-            TvmCpuInjectExternalInterrupt {
-                tvm_id: _,
-                vcpu_id,
-                interrupt_id: _,
-            } => *vcpu_id,
-            TvmCpuRebindImsicBegin {
-                tvm_id: _,
-                vcpu_id,
-                imsic_mask: _,
-            } => *vcpu_id,
-            TvmCpuRebindImsicEnd { tvm_id: _, vcpu_id } => *vcpu_id,
-            TvmCpuRebindImsicClone { tvm_id: _, vcpu_id } => *vcpu_id,
-            _ => 0,
-        }
-    }
-
-    fn a2(&self) -> u64 {
-        use CoveInterruptFunction::*;
-        match self {
-            TvmAiaInit {
-                tvm_id: _,
-                params_addr: _,
-                len,
-            } => *len,
-            TvmCpuSetImsicAddr {
-                tvm_id: _,
-                vcpu_id: _,
-                imsic_addr,
-            } => *imsic_addr,
-            TvmCpuBindImsic {
-                tvm_id: _,
-                vcpu_id: _,
-                imsic_mask,
-            } => *imsic_mask,
-            TvmCpuInjectExternalInterrupt {
-                tvm_id: _,
-                vcpu_id: _,
-                interrupt_id,
-            } => *interrupt_id,
-            TvmCpuRebindImsicBegin {
-                tvm_id: _,
-                vcpu_id: _,
-                imsic_mask,
-            } => *imsic_mask,
-            _ => 0,
-        }
-    }
-
-    fn a3(&self) -> u64 {
-        0
-    }
-
-    fn a4(&self) -> u64 {
-        0
-    }
-
-    fn a5(&self) -> u64 {
-        0
-    }
-}
@@ -0,0 +1,292 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::*;
+use crate::function::*;
+
+/// Functions for the Collaborative Processor Performance Control (CPPC) extension
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
+pub enum CppcFunction {
+    /// Checks whether the given CPPC register is implemented. Returns the width of the register
+    /// in bits, or 0 if it isn't implemented.
+    ///
+    /// a6 = 0
+    #[sbi(fid = 0)]
+    Probe {
+        /// a0 = CPPC register ID
+        #[sbi(reg = a0)]
+        reg_id: CppcRegister,
+    },
+    /// Reads the given CPPC register. On RV32 only the lower 32 bits are returned.
+    ///
+    /// a6 = 1
+    #[sbi(fid = 1)]
+    Read {
+        /// a0 = CPPC register ID
+        #[sbi(reg = a0)]
+        reg_id: CppcRegister,
+    },
+    /// Reads the upper 32 bits of the given CPPC register on RV32. Always returns 0 on RV64.
+    ///
+    /// a6 = 2
+    #[sbi(fid = 2)]
+    ReadHi {
+        /// a0 = CPPC register ID
+        #[sbi(reg = a0)]
+        reg_id: CppcRegister,
+    },
+    /// Writes `value` to the given CPPC register.
+    ///
+    /// a6 = 3
+    #[sbi(fid = 3)]
+    Write {
+        /// a0 = CPPC register ID
+        #[sbi(reg = a0)]
+        reg_id: CppcRegister,
+        /// a1 = value to write. On RV32, a1 holds the lower 32 bits and a2 holds the upper 32
+        /// bits.
+        #[sbi(regs(a1, a2))]
+        value: u64,
+    },
+}
+
+/// CPPC register IDs. IDs below `TransitionLatency` match the registers defined by the ACPI
+/// specification.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CppcRegister {
+    /// Highest performance this processor can achieve (read-only, 32-bit).
+    HighestPerformance,
+    /// Highest sustained performance level of the processor (read-only, 32-bit).
+    NominalPerformance,
+    /// Lowest performance level at which nonlinear power savings are achieved (read-only, 32-bit).
+    LowestNonlinearPerformance,
+    /// Lowest performance this processor can achieve (read-only, 32-bit).
+    LowestPerformance,
+    /// Current maximum sustained performance level of the processor (read-only, 32-bit).
+    GuaranteedPerformance,
+    /// Performance level desired by the OS (read-write, 32-bit).
+    DesiredPerformance,
+    /// Minimum performance level the platform may deliver (read-write, 32-bit).
+    MinimumPerformance,
+    /// Maximum performance level the platform may deliver (read-write, 32-bit).
+    MaximumPerformance,
+    /// Deviation below the desired performance the OS can tolerate (read-write, 32-bit).
+    PerformanceReductionTolerance,
+    /// Minimum time window over which the desired performance is delivered (read-write, 32-bit).
+    TimeWindow,
+    /// Minimum time before the performance counters wrap around (read-only, 32 or 64-bit).
+    CounterWraparoundTime,
+    /// Counter incrementing at the reference performance rate (read-only, 32 or 64-bit).
+    ReferencePerformanceCounter,
+    /// Counter incrementing at the delivered performance rate (read-only, 32 or 64-bit).
+    DeliveredPerformanceCounter,
+    /// Indicates that delivered performance was limited by the platform (read-write, 32-bit).
+    PerformanceLimited,
+    /// Enables CPPC on the processor (read-write, 32-bit).
+    CppcEnable,
+    /// Enables autonomous performance level selection (read-write, 32-bit).
+    AutonomousSelectionEnable,
+    /// Activity window for autonomous performance selection (read-write, 32-bit).
+    AutonomousActivityWindow,
+    /// Energy versus performance preference for autonomous selection (read-write, 32-bit).
+    EnergyPerformancePreference,
+    /// Performance level at which the reference counter increments (read-only, 32-bit).
+    ReferencePerformance,
+    /// Lowest frequency of the processor in MHz (read-only, 32-bit).
+    LowestFrequency,
+    /// Nominal frequency of the processor in MHz (read-only, 32-bit).
+    NominalFrequency,
+    /// Maximum latency of a performance change in nanoseconds (read-only, 32-bit).
+    TransitionLatency,
+    /// A platform-specific register ID, in the range `0x80001000..=0x8000FFFF`. Calls for one the
+    /// platform doesn't implement fail with `NotSupported`.
+    PlatformSpecific(PlatformCppcRegister),
+    /// A register ID in one of the ranges reserved for future use: `0x00000015..=0x7FFFFFFF`,
+    /// `0x80000001..=0x80000FFF` and `0x80010000..=0xFFFFFFFF`. Calls for these fail with
+    /// `InvalidParam`.
+    Reserved(ReservedCppcRegister),
+}
+
+/// A platform-specific CPPC register ID. Created with `CppcRegister::platform_specific`, which
+/// checks that the ID is in the platform-specific range.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PlatformCppcRegister(u32);
+
+impl PlatformCppcRegister {
+    /// Returns the raw register ID.
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
+/// A CPPC register ID reserved for future use. Created with `CppcRegister::reserved`, which checks
+/// that the ID is in one of the reserved ranges.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ReservedCppcRegister(u32);
+
+impl ReservedCppcRegister {
+    /// Returns the raw register ID.
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
+impl CppcRegister {
+    /// Creates a platform-specific register ID. Returns `InvalidParam` unless `raw` is in the range
+    /// `0x80001000..=0x8000FFFF`.
+    pub fn platform_specific(raw: u32) -> Result<Self> {
+        match raw {
+            0x8000_1000..=0x8000_ffff => {
+                Ok(CppcRegister::PlatformSpecific(PlatformCppcRegister(raw)))
+            }
+            _ => Err(Error::InvalidParam),
+        }
+    }
+
+    /// Creates a reserved register ID. Returns `InvalidParam` unless `raw` is in one of the reserved
+    /// ranges.
+    pub fn reserved(raw: u32) -> Result<Self> {
+        match raw {
+            0x15..=0x7fff_ffff | 0x8000_0001..=0x8000_0fff | 0x8001_0000..=0xffff_ffff => {
+                Ok(CppcRegister::Reserved(ReservedCppcRegister(raw)))
+            }
+            _ => Err(Error::InvalidParam),
+        }
+    }
+
+    /// Creates a register ID from the a0 register value or returns an error if the value doesn't
+    /// fit in 32 bits.
+    pub fn from_reg(reg: u64) -> Result<Self> {
+        use CppcRegister::*;
+        let raw = u32::try_from(reg).map_err(|_| Error::InvalidParam)?;
+        Ok(match raw {
+            0x00 => HighestPerformance,
+            0x01 => NominalPerformance,
+            0x02 => LowestNonlinearPerformance,
+            0x03 => LowestPerformance,
+            0x04 => GuaranteedPerformance,
+            0x05 => DesiredPerformance,
+            0x06 => MinimumPerformance,
+            0x07 => MaximumPerformance,
+            0x08 => PerformanceReductionTolerance,
+            0x09 => TimeWindow,
+            0x0a => CounterWraparoundTime,
+            0x0b => ReferencePerformanceCounter,
+            0x0c => DeliveredPerformanceCounter,
+            0x0d => PerformanceLimited,
+            0x0e => CppcEnable,
+            0x0f => AutonomousSelectionEnable,
+            0x10 => AutonomousActivityWindow,
+            0x11 => EnergyPerformancePreference,
+            0x12 => ReferencePerformance,
+            0x13 => LowestFrequency,
+            0x14 => NominalFrequency,
+            0x8000_0000 => TransitionLatency,
+            0x8000_1000..=0x8000_ffff => return Self::platform_specific(raw),
+            _ => return Self::reserved(raw),
+        })
+    }
+
+    /// Returns the raw value of this register ID.
+    pub fn raw(&self) -> u32 {
+        use CppcRegister::*;
+        match self {
+            HighestPerformance => 0x00,
+            NominalPerformance => 0x01,
+            LowestNonlinearPerformance => 0x02,
+            LowestPerformance => 0x03,
+            GuaranteedPerformance => 0x04,
+            DesiredPerformance => 0x05,
+            MinimumPerformance => 0x06,
+            MaximumPerformance => 0x07,
+            PerformanceReductionTolerance => 0x08,
+            TimeWindow => 0x09,
+            CounterWraparoundTime => 0x0a,
+            ReferencePerformanceCounter => 0x0b,
+            DeliveredPerformanceCounter => 0x0c,
+            PerformanceLimited => 0x0d,
+            CppcEnable => 0x0e,
+            AutonomousSelectionEnable => 0x0f,
+            AutonomousActivityWindow => 0x10,
+            EnergyPerformancePreference => 0x11,
+            ReferencePerformance => 0x12,
+            LowestFrequency => 0x13,
+            NominalFrequency => 0x14,
+            TransitionLatency => 0x8000_0000,
+            PlatformSpecific(id) => id.raw(),
+            Reserved(id) => id.raw(),
+        }
+    }
+}
+
+impl SbiReg for CppcRegister {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_reg(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.raw() as u64
+    }
+}
+
+impl CppcFunction {
+    /// Returns the CPPC register accessed by this function.
+    pub fn reg_id(&self) -> CppcRegister {
+        use CppcFunction::*;
+        match self {
+            Probe { reg_id } | Read { reg_id } | ReadHi { reg_id } | Write { reg_id, .. } => {
+                *reg_id
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_ranges() {
+        use CppcRegister::*;
+        let platform = |raw| CppcRegister::platform_specific(raw).unwrap();
+        let reserved = |raw| CppcRegister::reserved(raw).unwrap();
+        for (reg, expected) in [
+            (0x0000_0000, HighestPerformance),
+            (0x0000_0014, NominalFrequency),
+            (0x0000_0015, reserved(0x0000_0015)),
+            (0x7fff_ffff, reserved(0x7fff_ffff)),
+            (0x8000_0000, TransitionLatency),
+            (0x8000_0001, reserved(0x8000_0001)),
+            (0x8000_0fff, reserved(0x8000_0fff)),
+            (0x8000_1000, platform(0x8000_1000)),
+            (0x8000_ffff, platform(0x8000_ffff)),
+            (0x8001_0000, reserved(0x8001_0000)),
+            (0xffff_ffff, reserved(0xffff_ffff)),
+        ] {
+            assert_eq!(CppcRegister::from_reg(reg), Ok(expected), "{reg:#x}");
+            assert_eq!(SbiReg::to_reg(&expected), reg);
+        }
+        assert_eq!(
+            CppcRegister::from_reg(0x1_0000_0000),
+            Err(Error::InvalidParam)
+        );
+
+        // IDs of standard registers, or from the other range, can't be wrapped as reserved or
+        // platform-specific registers.
+        for raw in [0x00, 0x14, 0x8000_0000, 0x8000_1000, 0x8000_ffff] {
+            assert_eq!(
+                CppcRegister::reserved(raw),
+                Err(Error::InvalidParam),
+                "{raw:#x}"
+            );
+        }
+        for raw in [0x00, 0x15, 0x8000_0000, 0x8000_0fff, 0x8001_0000] {
+            assert_eq!(
+                CppcRegister::platform_specific(raw),
+                Err(Error::InvalidParam),
+                "{raw:#x}"
+            );
+        }
+    }
+}
@@ -0,0 +1,362 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use static_assertions::const_assert;
+
+use crate::function::*;
+
+/// Trigger data exchanged through the debug trigger shared memory by `ReadTriggers`,
+/// `InstallTriggers` and `UpdateTriggers`. Each field is an XLEN-wide `unsigned long`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DbtrTriggerData {
+    /// The state of the trigger, written by `ReadTriggers`. Ignored by `InstallTriggers`.
+    pub tstate: usize,
+    /// The trigger's `tdata1` CSR value.
+    pub tdata1: usize,
+    /// The trigger's `tdata2` CSR value.
+    pub tdata2: usize,
+    /// The trigger's `tdata3` CSR value.
+    pub tdata3: usize,
+}
+
+impl DbtrTriggerData {
+    /// Creates trigger data for installing or updating a trigger.
+    pub fn new(tdata1: usize, tdata2: usize, tdata3: usize) -> Self {
+        Self {
+            tstate: 0,
+            tdata1,
+            tdata2,
+            tdata3,
+        }
+    }
+
+    /// Returns trigger data for a breakpoint on execution of `addr` in S and U modes.
+    pub fn breakpoint(addr: usize) -> Self {
+        let tdata1 = Mcontrol6::new()
+            .set_match(DbtrMatch::Equal)
+            .set_execute()
+            .set_s()
+            .set_u();
+        Self::new(tdata1.raw(), addr, 0)
+    }
+
+    /// Returns trigger data for a watchpoint on loads from (`load`) and/or stores to (`store`)
+    /// `addr` in S and U modes.
+    pub fn watchpoint(addr: usize, load: bool, store: bool) -> Self {
+        let mut tdata1 = Mcontrol6::new().set_match(DbtrMatch::Equal).set_s().set_u();
+        if load {
+            tdata1 = tdata1.set_load();
+        }
+        if store {
+            tdata1 = tdata1.set_store();
+        }
+        Self::new(tdata1.raw(), addr, 0)
+    }
+}
+
+/// The trigger index exchanged through the debug trigger shared memory. Written by
+/// `InstallTriggers` and read by `UpdateTriggers`. Overlaps `DbtrTriggerData::tstate`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DbtrTriggerId {
+    /// The index of the trigger.
+    pub idx: usize,
+}
+
+/// An entry in the debug trigger shared memory area registered with `SetShmem`. The area is an
+/// array of these entries, one per trigger passed to `ReadTriggers`, `InstallTriggers` or
+/// `UpdateTriggers`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union DbtrShmemEntry {
+    /// The trigger data.
+    pub data: DbtrTriggerData,
+    /// The trigger index.
+    pub id: DbtrTriggerId,
+}
+
+impl Default for DbtrShmemEntry {
+    fn default() -> Self {
+        Self {
+            data: DbtrTriggerData::default(),
+        }
+    }
+}
+
+fn _assert_dbtr_shmem_entry_size() {
+    const_assert!(core::mem::size_of::<DbtrShmemEntry>() == 4 * core::mem::size_of::<usize>());
+}
+
+/// Address match conditions for `Mcontrol6` triggers.
+#[repr(usize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DbtrMatch {
+    /// Matches when the value equals `tdata2`.
+    Equal = 0,
+    /// Matches when the top bits of the value match the NAPOT range in `tdata2`.
+    Napot = 1,
+    /// Matches when the value is greater than or equal to `tdata2`.
+    GreaterEqual = 2,
+    /// Matches when the value is less than `tdata2`.
+    Less = 3,
+    /// Matches when the low half of the value, masked by the high half of `tdata2`, equals the low
+    /// half of `tdata2`.
+    MaskLow = 4,
+    /// Matches when the high half of the value, masked by the high half of `tdata2`, equals the low
+    /// half of `tdata2`.
+    MaskHigh = 5,
+    /// Matches when the value doesn't equal `tdata2`.
+    NotEqual = 8,
+    /// Matches when the value doesn't match the NAPOT range in `tdata2`.
+    NotNapot = 9,
+    /// The inverse of `MaskLow`.
+    NotMaskLow = 12,
+    /// The inverse of `MaskHigh`.
+    NotMaskHigh = 13,
+}
+
+/// Builder for the XLEN-wide `tdata1` value of an address/data match (`mcontrol6`, type 6)
+/// trigger.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Mcontrol6(usize);
+
+impl Mcontrol6 {
+    const TYPE: usize = 6;
+    const TYPE_SHIFT: u32 = usize::BITS - 4;
+    const VS: usize = 1 << 24;
+    const VU: usize = 1 << 23;
+    const SELECT: usize = 1 << 21;
+    const CHAIN: usize = 1 << 11;
+    const MATCH_SHIFT: u32 = 7;
+    const MATCH_MASK: usize = 0xf << Self::MATCH_SHIFT;
+    const S: usize = 1 << 4;
+    const U: usize = 1 << 3;
+    const EXECUTE: usize = 1 << 2;
+    const STORE: usize = 1 << 1;
+    const LOAD: usize = 1;
+
+    /// Creates an `mcontrol6` trigger that matches nothing until configured.
+    pub fn new() -> Self {
+        Self(Self::TYPE << Self::TYPE_SHIFT)
+    }
+
+    /// Returns the raw `tdata1` value.
+    pub fn raw(&self) -> usize {
+        self.0
+    }
+
+    /// Sets the address match condition.
+    pub fn set_match(self, m: DbtrMatch) -> Self {
+        Self((self.0 & !Self::MATCH_MASK) | ((m as usize) << Self::MATCH_SHIFT))
+    }
+
+    /// Fires the trigger on instruction execution.
+    pub fn set_execute(self) -> Self {
+        Self(self.0 | Self::EXECUTE)
+    }
+
+    /// Fires the trigger on loads.
+    pub fn set_load(self) -> Self {
+        Self(self.0 | Self::LOAD)
+    }
+
+    /// Fires the trigger on stores.
+    pub fn set_store(self) -> Self {
+        Self(self.0 | Self::STORE)
+    }
+
+    /// Enables the trigger in S-mode (HS-mode if the H extension is present).
+    pub fn set_s(self) -> Self {
+        Self(self.0 | Self::S)
+    }
+
+    /// Enables the trigger in U-mode.
+    pub fn set_u(self) -> Self {
+        Self(self.0 | Self::U)
+    }
+
+    /// Enables the trigger in VS-mode.
+    pub fn set_vs(self) -> Self {
+        Self(self.0 | Self::VS)
+    }
+
+    /// Enables the trigger in VU-mode.
+    pub fn set_vu(self) -> Self {
+        Self(self.0 | Self::VU)
+    }
+
+    /// Matches on the data value of loads and stores instead of the address.
+    pub fn set_select_data(self) -> Self {
+        Self(self.0 | Self::SELECT)
+    }
+
+    /// Chains this trigger with the next one, so it only fires if both match.
+    pub fn set_chain(self) -> Self {
+        Self(self.0 | Self::CHAIN)
+    }
+}
+
+impl Default for Mcontrol6 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Functions for the Debug Triggers (DBTR) extension
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
+pub enum DbtrFunction {
+    /// Returns the number of debug triggers on the calling hart that can support the trigger
+    /// configuration in `trig_tdata1`, or the total number of triggers if `trig_tdata1` is 0.
+    ///
+    /// a6 = 0
+    #[sbi(fid = 0)]
+    NumTriggers {
+        /// a0 = trigger configuration to check
+        #[sbi(reg = a0)]
+        trig_tdata1: u64,
+    },
+    /// Registers the array of `DbtrShmemEntry` used by the calling hart. Passing all-ones in both
+    /// `shmem_lo` and `shmem_hi` unregisters the shared memory.
+    ///
+    /// a6 = 1
+    #[sbi(fid = 1)]
+    SetShmem {
+        /// a0 = lower XLEN bits of the physical address of the shared memory
+        #[sbi(reg = a0)]
+        shmem_lo: u64,
+        /// a1 = upper bits of the physical address of the shared memory (RV32 only)
+        #[sbi(reg = a1)]
+        shmem_hi: u64,
+        /// a2 = flags, must be 0
+        #[sbi(reg = a2)]
+        flags: u64,
+    },
+    /// Reads the state and configuration of `trig_count` triggers starting at `trig_idx_base` into
+    /// the shared memory.
+    ///
+    /// a6 = 2
+    #[sbi(fid = 2)]
+    ReadTriggers {
+        /// a0 = index of the first trigger to read
+        #[sbi(reg = a0)]
+        trig_idx_base: u64,
+        /// a1 = number of triggers to read
+        #[sbi(reg = a1)]
+        trig_count: u64,
+    },
+    /// Installs `trig_count` triggers configured in the shared memory, writing the index of each
+    /// installed trigger back to its entry.
+    ///
+    /// a6 = 3
+    #[sbi(fid = 3)]
+    InstallTriggers {
+        /// a0 = number of triggers to install
+        #[sbi(reg = a0)]
+        trig_count: u64,
+    },
+    /// Updates the configuration of `trig_count` already-installed triggers using the indices and
+    /// configurations in the shared memory.
+    ///
+    /// a6 = 4
+    #[sbi(fid = 4)]
+    UpdateTriggers {
+        /// a0 = number of triggers to update
+        #[sbi(reg = a0)]
+        trig_count: u64,
+    },
+    /// Uninstalls the triggers selected by `trig_idx_base` and `trig_idx_mask`.
+    ///
+    /// a6 = 5
+    #[sbi(fid = 5)]
+    UninstallTriggers {
+        /// a0 = index of bit 0 of `trig_idx_mask`
+        #[sbi(reg = a0)]
+        trig_idx_base: u64,
+        /// a1 = bitmask of triggers
+        #[sbi(reg = a1)]
+        trig_idx_mask: u64,
+    },
+    /// Enables the triggers selected by `trig_idx_base` and `trig_idx_mask`.
+    ///
+    /// a6 = 6
+    #[sbi(fid = 6)]
+    EnableTriggers {
+        /// a0 = index of bit 0 of `trig_idx_mask`
+        #[sbi(reg = a0)]
+        trig_idx_base: u64,
+        /// a1 = bitmask of triggers
+        #[sbi(reg = a1)]
+        trig_idx_mask: u64,
+    },
+    /// Disables the triggers selected by `trig_idx_base` and `trig_idx_mask`.
+    ///
+    /// a6 = 7
+    #[sbi(fid = 7)]
+    DisableTriggers {
+        /// a0 = index of bit 0 of `trig_idx_mask`
+        #[sbi(reg = a0)]
+        trig_idx_base: u64,
+        /// a1 = bitmask of triggers
+        #[sbi(reg = a1)]
+        trig_idx_mask: u64,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn mcontrol6_bits() {
+        assert_eq!(Mcontrol6::new().raw(), 0x6000_0000_0000_0000);
+        assert_eq!(
+            DbtrTriggerData::breakpoint(0x8000_0000),
+            DbtrTriggerData::new(0x6000_0000_0000_001c, 0x8000_0000, 0)
+        );
+        assert_eq!(
+            DbtrTriggerData::watchpoint(0x8000_0000, true, true).tdata1,
+            0x6000_0000_0000_001b
+        );
+        assert_eq!(
+            DbtrTriggerData::watchpoint(0x8000_0000, false, true).tdata1,
+            0x6000_0000_0000_001a
+        );
+
+        // Setting the match condition replaces the previous one.
+        let tdata1 = Mcontrol6::new()
+            .set_match(DbtrMatch::NotMaskHigh)
+            .set_match(DbtrMatch::Napot);
+        assert_eq!(tdata1.raw(), 0x6000_0000_0000_0080);
+        let tdata1 = Mcontrol6::new()
+            .set_vs()
+            .set_vu()
+            .set_select_data()
+            .set_chain();
+        assert_eq!(tdata1.raw(), 0x6000_0000_01a0_0800);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn mcontrol6_bits() {
+        assert_eq!(Mcontrol6::new().raw(), 0x6000_0000);
+        assert_eq!(
+            DbtrTriggerData::breakpoint(0x8000_0000),
+            DbtrTriggerData::new(0x6000_001c, 0x8000_0000, 0)
+        );
+    }
+
+    #[test]
+    fn shmem_entry_id_overlaps_tstate() {
+        let entry = DbtrShmemEntry {
+            data: DbtrTriggerData {
+                tstate: 7,
+                ..Default::default()
+            },
+        };
+        // Safety: Both union fields are plain integers.
+        assert_eq!(unsafe { entry.id }.idx, 7);
+    }
+}
@@ -2,38 +2,68 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use core::fmt;
+
 /// Errors passed over the SBI protocol.
 ///
 /// Constants from the SBI [spec](https://github.com/riscv-non-isa/riscv-sbi-doc/releases).
-#[repr(i64)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Error {
     /// Generic failure in execution of the SBI call.
-    Failed = -1,
+    Failed,
     /// Extension or function is not supported.
-    NotSupported = -2,
+    NotSupported,
     /// Parameter passed isn't valid.
-    InvalidParam = -3,
+    InvalidParam,
     /// Permission denied.
-    Denied = -4,
+    Denied,
     /// Address passed is invalid.
-    InvalidAddress = -5,
+    InvalidAddress,
     /// The given hart has already been started.
-    AlreadyAvailable = -6,
+    AlreadyAvailable,
     /// Some of the given counters have already been started.
-    AlreadyStarted = -7,
+    AlreadyStarted,
     /// Some of the given counters have already been stopped.
-    AlreadyStopped = -8,
+    AlreadyStopped,
     /// The buffer passed as a parameter is not large enough.
-    InsufficientBufferCapacity = -9,
+    InsufficientBufferCapacity,
+    /// Shared memory isn't available.
+    NoShmem,
+    /// The operation isn't valid in the current state.
+    InvalidState,
+    /// The given range is bad or invalid.
+    BadRange,
+    /// The operation timed out.
+    Timeout,
+    /// An input or output error occurred.
+    Io,
+    /// The requested change is denied because the feature is locked.
+    DeniedLocked,
+    /// An error code not defined by the SBI spec, such as a vendor-specific one. Holds the raw
+    /// error code so it can be passed on unchanged.
+    Other(OtherErrorCode),
+}
+
+/// An error code that isn't 0 and isn't one of the codes defined by the SBI spec. Created by
+/// `Error::from_code` or `Error::other`, so `Error::from_code(e.code())` always gives back the same
+/// error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OtherErrorCode(i64);
+
+impl OtherErrorCode {
+    /// Returns the raw error code.
+    pub fn code(&self) -> i64 {
+        self.0
+    }
 }
 
 impl Error {
-    /// Parse the given error code to an `Error` enum.
+    /// Parse the given error code to an `Error` enum. A code of 0, which is success rather than an
+    /// error, parses as `Failed`.
     pub fn from_code(e: i64) -> Self {
         use Error::*;
         match e {
-            -1 => Failed,
+            0 | -1 => Failed,
             -2 => NotSupported,
             -3 => InvalidParam,
             -4 => Denied,
@@ -42,10 +72,109 @@ impl Error {
             -7 => AlreadyStarted,
             -8 => AlreadyStopped,
             -9 => InsufficientBufferCapacity,
-            _ => Failed,
+            -10 => NoShmem,
+            -11 => InvalidState,
+            -12 => BadRange,
+            -13 => Timeout,
+            -14 => Io,
+            -15 => DeniedLocked,
+            e => Other(OtherErrorCode(e)),
+        }
+    }
+
+    /// Creates an `Error::Other` holding `code`. Returns `None` if `code` is 0 or one of the error
+    /// codes defined by the SBI spec, which `from_code` parses as the matching variant instead.
+    pub fn other(code: i64) -> Option<Self> {
+        match Self::from_code(code) {
+            e @ Error::Other(_) if code != 0 => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the error code for this error as passed in a0. This is never 0.
+    ///
+    /// Use this in place of the `as i64` casts `Error` supported when it was `repr(i64)`.
+    pub fn code(&self) -> i64 {
+        use Error::*;
+        match self {
+            Failed => -1,
+            NotSupported => -2,
+            InvalidParam => -3,
+            Denied => -4,
+            InvalidAddress => -5,
+            AlreadyAvailable => -6,
+            AlreadyStarted => -7,
+            AlreadyStopped => -8,
+            InsufficientBufferCapacity => -9,
+            NoShmem => -10,
+            InvalidState => -11,
+            BadRange => -12,
+            Timeout => -13,
+            Io => -14,
+            DeniedLocked => -15,
+            Other(e) => e.code(),
         }
     }
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Error::*;
+        let desc = match self {
+            Failed => "failed",
+            NotSupported => "not supported",
+            InvalidParam => "invalid parameter",
+            Denied => "denied",
+            InvalidAddress => "invalid address",
+            AlreadyAvailable => "already available",
+            AlreadyStarted => "already started",
+            AlreadyStopped => "already stopped",
+            InsufficientBufferCapacity => "insufficient buffer capacity",
+            NoShmem => "no shared memory",
+            InvalidState => "invalid state",
+            BadRange => "bad range",
+            Timeout => "timed out",
+            Io => "I/O error",
+            DeniedLocked => "denied, feature locked",
+            Other(_) => "unknown error",
+        };
+        write!(f, "SBI error {} ({})", self.code(), desc)
+    }
+}
+
+impl core::error::Error for Error {}
+
 /// Holds the result of a SBI operation.
 pub type Result<T> = core::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_round_trip() {
+        for code in (-20..0).chain([i64::MIN, -0x1000, 1, 0x8000_0000]) {
+            assert_eq!(Error::from_code(code).code(), code);
+        }
+        assert_eq!(Error::from_code(-13), Error::Timeout);
+        assert_eq!(Error::from_code(-100), Error::other(-100).unwrap());
+    }
+
+    #[test]
+    fn other_codes() {
+        // Codes with a variant of their own, and 0, can't be held by `Other`.
+        for code in -15..=0 {
+            assert_eq!(Error::other(code), None, "{code}");
+        }
+        for code in [i64::MIN, -16, 1, i64::MAX] {
+            let error = Error::other(code).unwrap();
+            assert_eq!(error.code(), code);
+            assert_eq!(Error::from_code(error.code()), error);
+        }
+    }
+
+    #[test]
+    fn zero_is_not_success() {
+        assert_eq!(Error::from_code(0), Error::Failed);
+    }
+}
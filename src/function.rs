@@ -4,6 +4,12 @@
 
 use crate::error::*;
 
+use flagset::{FlagSet, Flags};
+
+/// Derives `SbiFunction` and `from_regs()` for an enum of SBI functions from `#[sbi(...)]`
+/// attributes giving the function ID of each variant and the register(s) of each field.
+pub use sbi_rs_derive::SbiFunction;
+
 /// A Trait for an SbiFunction. Implementers use this trait to specify how to parse from and
 /// serialize into the a0-a7 registers used to make SBI calls.
 pub trait SbiFunction {
@@ -50,3 +56,73 @@ pub trait SbiFunction {
         }
     }
 }
+
+/// A type that's passed to or returned from an SBI function in a single register.
+pub trait SbiReg: Sized {
+    /// Parses `Self` from a register value, returning an error if the value isn't valid.
+    fn from_reg(reg: u64) -> Result<Self>;
+    /// Returns the register value representing `self`.
+    fn to_reg(&self) -> u64;
+}
+
+/// A type that's passed to an SBI function in `N` registers.
+pub trait SbiRegs<const N: usize>: Sized {
+    /// Parses `Self` from register values, returning an error if the values aren't valid.
+    fn from_regs(regs: [u64; N]) -> Result<Self>;
+    /// Returns the register values representing `self`.
+    fn to_regs(&self) -> [u64; N];
+}
+
+impl SbiReg for u64 {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Ok(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        *self
+    }
+}
+
+impl SbiReg for i64 {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Ok(reg as i64)
+    }
+
+    fn to_reg(&self) -> u64 {
+        *self as u64
+    }
+}
+
+impl<F: Flags<Type = u64>> SbiReg for FlagSet<F> {
+    fn from_reg(reg: u64) -> Result<Self> {
+        FlagSet::new(reg).map_err(|_| Error::InvalidParam)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.bits()
+    }
+}
+
+// 64-bit values passed in a pair of registers, holding the lower and upper 32 bits on RV32. On
+// RV64 the whole value is passed in the first register and the second is unused.
+#[cfg(target_pointer_width = "32")]
+impl SbiRegs<2> for u64 {
+    fn from_regs(regs: [u64; 2]) -> Result<Self> {
+        Ok((regs[0] & 0xffff_ffff) | (regs[1] << 32))
+    }
+
+    fn to_regs(&self) -> [u64; 2] {
+        [*self & 0xffff_ffff, *self >> 32]
+    }
+}
+
+#[cfg(not(target_pointer_width = "32"))]
+impl SbiRegs<2> for u64 {
+    fn from_regs(regs: [u64; 2]) -> Result<Self> {
+        Ok(regs[0])
+    }
+
+    fn to_regs(&self) -> [u64; 2] {
+        [*self, 0]
+    }
+}
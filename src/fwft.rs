@@ -0,0 +1,231 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::*;
+use crate::function::*;
+
+use flagset::{flags, FlagSet};
+
+flags! {
+    /// Flags accepted by `FwftFunction::Set`.
+    #[repr(u64)]
+    pub enum FwftFlag: u64 {
+        /// Locks the feature's value until the next hart reset. Further attempts to set it return
+        /// `DeniedLocked`.
+        Lock = 1,
+    }
+}
+
+/// Firmware feature IDs. Bit 31 of the raw value marks a feature as global to all harts.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FwftFeature {
+    /// Enables delegation of misaligned load/store exceptions to supervisor mode.
+    MisalignedExceptionDelegation,
+    /// Enables landing pad checks (Zicfilp) for supervisor mode.
+    LandingPad,
+    /// Enables shadow stacks (Zicfiss) for supervisor mode.
+    ShadowStack,
+    /// Enables double trap detection (Ssdbltrp) for supervisor mode.
+    DoubleTrap,
+    /// Enables hardware updating of the PTE A/D bits (Svadu) for supervisor mode.
+    PteAdHardwareUpdating,
+    /// Sets the pointer masking length (Ssnpm) for supervisor mode.
+    PointerMaskingPmlen,
+    /// A feature ID in one of the reserved ranges, `0x00000006..=0x3FFFFFFF` (local) and
+    /// `0x80000000..=0xBFFFFFFF` (global). Implementations return `InvalidParam` for these.
+    Reserved(ReservedFwftFeature),
+    /// A platform-specific feature ID, in the ranges `0x40000000..=0x7FFFFFFF` (local) and
+    /// `0xC0000000..=0xFFFFFFFF` (global).
+    PlatformSpecific(PlatformFwftFeature),
+}
+
+/// A firmware feature ID reserved for future use. Created with `FwftFeature::reserved`, which
+/// checks that the ID is in one of the reserved ranges.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ReservedFwftFeature(u32);
+
+impl ReservedFwftFeature {
+    /// Returns the raw feature ID.
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
+/// A platform-specific firmware feature ID. Created with `FwftFeature::platform_specific`, which
+/// checks that the ID is in one of the platform-specific ranges.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PlatformFwftFeature(u32);
+
+impl PlatformFwftFeature {
+    /// Returns the raw feature ID.
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
+impl FwftFeature {
+    /// Creates a reserved feature ID. Returns `InvalidParam` unless `raw` is in one of the reserved
+    /// ranges.
+    pub fn reserved(raw: u32) -> Result<Self> {
+        match raw {
+            0x6..=0x3fff_ffff | 0x8000_0000..=0xbfff_ffff => {
+                Ok(FwftFeature::Reserved(ReservedFwftFeature(raw)))
+            }
+            _ => Err(Error::InvalidParam),
+        }
+    }
+
+    /// Creates a platform-specific feature ID. Returns `InvalidParam` unless `raw` is in one of the
+    /// platform-specific ranges.
+    pub fn platform_specific(raw: u32) -> Result<Self> {
+        match raw {
+            0x4000_0000..=0x7fff_ffff | 0xc000_0000..=0xffff_ffff => {
+                Ok(FwftFeature::PlatformSpecific(PlatformFwftFeature(raw)))
+            }
+            _ => Err(Error::InvalidParam),
+        }
+    }
+
+    /// Creates a feature ID from the a0 register value or returns an error if the value doesn't fit
+    /// in 32 bits.
+    pub fn from_reg(reg: u64) -> Result<Self> {
+        use FwftFeature::*;
+        let raw = u32::try_from(reg).map_err(|_| Error::InvalidParam)?;
+        Ok(match raw {
+            0 => MisalignedExceptionDelegation,
+            1 => LandingPad,
+            2 => ShadowStack,
+            3 => DoubleTrap,
+            4 => PteAdHardwareUpdating,
+            5 => PointerMaskingPmlen,
+            0x4000_0000..=0x7fff_ffff | 0xc000_0000..=0xffff_ffff => {
+                return Self::platform_specific(raw)
+            }
+            _ => return Self::reserved(raw),
+        })
+    }
+
+    /// Returns the raw value of this feature ID.
+    pub fn raw(&self) -> u32 {
+        use FwftFeature::*;
+        match self {
+            MisalignedExceptionDelegation => 0,
+            LandingPad => 1,
+            ShadowStack => 2,
+            DoubleTrap => 3,
+            PteAdHardwareUpdating => 4,
+            PointerMaskingPmlen => 5,
+            Reserved(id) => id.raw(),
+            PlatformSpecific(id) => id.raw(),
+        }
+    }
+
+    /// Returns true if this feature applies to all harts rather than only the calling hart.
+    pub fn is_global(&self) -> bool {
+        self.raw() & (1 << 31) != 0
+    }
+}
+
+impl SbiReg for FwftFeature {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_reg(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.raw() as u64
+    }
+}
+
+/// Functions for the Firmware Features (FWFT) extension
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
+pub enum FwftFunction {
+    /// Sets the value of the given firmware feature.
+    ///
+    /// a6 = 0
+    #[sbi(fid = 0)]
+    Set {
+        /// a0 = feature ID
+        #[sbi(reg = a0)]
+        feature: FwftFeature,
+        /// a1 = value to set, the meaning of which depends on the feature
+        #[sbi(reg = a1)]
+        value: u64,
+        /// a2 = flags
+        #[sbi(reg = a2)]
+        flags: FlagSet<FwftFlag>,
+    },
+    /// Returns the current value of the given firmware feature.
+    ///
+    /// a6 = 1
+    #[sbi(fid = 1)]
+    Get {
+        /// a0 = feature ID
+        #[sbi(reg = a0)]
+        feature: FwftFeature,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feature_ranges() {
+        use FwftFeature::*;
+        let reserved = |raw| FwftFeature::reserved(raw).unwrap();
+        let platform = |raw| FwftFeature::platform_specific(raw).unwrap();
+        for (reg, expected, global) in [
+            (0x0000_0000, MisalignedExceptionDelegation, false),
+            (0x0000_0005, PointerMaskingPmlen, false),
+            (0x0000_0006, reserved(0x0000_0006), false),
+            (0x3fff_ffff, reserved(0x3fff_ffff), false),
+            (0x4000_0000, platform(0x4000_0000), false),
+            (0x7fff_ffff, platform(0x7fff_ffff), false),
+            (0x8000_0000, reserved(0x8000_0000), true),
+            (0xbfff_ffff, reserved(0xbfff_ffff), true),
+            (0xc000_0000, platform(0xc000_0000), true),
+            (0xffff_ffff, platform(0xffff_ffff), true),
+        ] {
+            assert_eq!(FwftFeature::from_reg(reg), Ok(expected), "{reg:#x}");
+            assert_eq!(expected.raw() as u64, reg);
+            assert_eq!(expected.is_global(), global, "{reg:#x}");
+        }
+        assert_eq!(
+            FwftFeature::from_reg(0x1_0000_0000),
+            Err(Error::InvalidParam)
+        );
+
+        // Standard feature IDs, or IDs from the other range, can't be wrapped as reserved or
+        // platform-specific features.
+        for raw in [0x0, 0x5, 0x4000_0000, 0xc000_0000] {
+            assert_eq!(
+                FwftFeature::reserved(raw),
+                Err(Error::InvalidParam),
+                "{raw:#x}"
+            );
+        }
+        for raw in [0x0, 0x5, 0x3fff_ffff, 0x8000_0000, 0xbfff_ffff] {
+            assert_eq!(
+                FwftFeature::platform_specific(raw),
+                Err(Error::InvalidParam),
+                "{raw:#x}"
+            );
+        }
+    }
+
+    #[test]
+    fn set_flags() {
+        let set = |flags| FwftFunction::from_regs(&[1, 1, flags, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            set(1),
+            Ok(FwftFunction::Set {
+                feature: FwftFeature::LandingPad,
+                value: 1,
+                flags: FwftFlag::Lock.into(),
+            })
+        );
+        // Undefined flag bits are rejected.
+        assert_eq!(set(2), Err(Error::InvalidParam));
+    }
+}
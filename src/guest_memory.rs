@@ -0,0 +1,175 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, Result};
+
+/// Access to the memory of the caller of an SBI function, for SBI implementations decoding the
+/// pointer arguments of a message such as `TvmCreate::params_addr`.
+pub trait GuestMemory {
+    /// Reads `buf.len()` bytes starting at the guest physical address `addr` into `buf`. Returns
+    /// `InvalidAddress` if any part of the range can't be translated or read.
+    fn read(&self, addr: u64, buf: &mut [u8]) -> Result<()>;
+
+    /// Writes `buf` to the guest physical address `addr`. Returns `InvalidAddress` if any part of
+    /// the range can't be translated or written.
+    fn write(&mut self, addr: u64, buf: &[u8]) -> Result<()>;
+}
+
+/// Reads the `N` bytes of a structure with alignment `align` from the guest physical address
+/// `addr`, where `len` is the length of the structure claimed by the caller. Returns
+/// `InvalidAddress` if `addr` is misaligned, `len` is smaller than `N`, or the memory can't be read.
+pub(crate) fn read_struct<const N: usize>(
+    mem: &impl GuestMemory,
+    addr: u64,
+    len: u64,
+    align: usize,
+) -> Result<[u8; N]> {
+    if addr % align as u64 != 0 || len < N as u64 {
+        return Err(Error::InvalidAddress);
+    }
+    let mut bytes = [0; N];
+    mem.read(addr, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Reads the `len` bytes at the guest physical address `addr` into the start of `buf` and returns
+/// them. This is for pointer arguments whose length is chosen by the caller, such as the CSR at
+/// `AttestationFunction::GetEvidence::cert_request_addr` or the string passed to
+/// `DebugConsoleFunction::Write`.
+///
+/// `buf.len()` is the longest length accepted: larger values of `len` fail with `InvalidParam`.
+/// Returns `InvalidAddress` if the range wraps around the address space or can't be read.
+pub fn read_bytes<'a>(
+    mem: &impl GuestMemory,
+    addr: u64,
+    len: u64,
+    buf: &'a mut [u8],
+) -> Result<&'a [u8]> {
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|&len| len <= buf.len())
+        .ok_or(Error::InvalidParam)?;
+    addr.checked_add(len as u64).ok_or(Error::InvalidAddress)?;
+    let bytes = &mut buf[..len];
+    mem.read(addr, bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TvmAiaParams, TvmCreateParams};
+    use core::mem::size_of;
+
+    const BASE: u64 = 0x8000_0000;
+
+    // A 256-byte window of guest memory at `BASE`.
+    struct Window([u8; 256]);
+
+    impl Window {
+        fn range(&self, addr: u64, len: usize) -> Result<core::ops::Range<usize>> {
+            let start = addr.checked_sub(BASE).ok_or(Error::InvalidAddress)? as usize;
+            let end = start.checked_add(len).ok_or(Error::InvalidAddress)?;
+            if end > self.0.len() {
+                return Err(Error::InvalidAddress);
+            }
+            Ok(start..end)
+        }
+    }
+
+    impl GuestMemory for Window {
+        fn read(&self, addr: u64, buf: &mut [u8]) -> Result<()> {
+            buf.copy_from_slice(&self.0[self.range(addr, buf.len())?]);
+            Ok(())
+        }
+
+        fn write(&mut self, addr: u64, buf: &[u8]) -> Result<()> {
+            let range = self.range(addr, buf.len())?;
+            self.0[range].copy_from_slice(buf);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn read_params() {
+        let mut mem = Window([0; 256]);
+        mem.write(BASE + 0x10, &0x8000_4000u64.to_le_bytes())
+            .unwrap();
+        mem.write(BASE + 0x18, &0x8001_0000u64.to_le_bytes())
+            .unwrap();
+        let len = size_of::<TvmCreateParams>() as u64;
+        assert_eq!(
+            TvmCreateParams::read_from(&mem, BASE + 0x10, len),
+            Ok(TvmCreateParams {
+                tvm_page_directory_addr: 0x8000_4000,
+                tvm_state_addr: 0x8001_0000,
+            })
+        );
+
+        mem.write(BASE + 0x40, &0x2800_0000u64.to_le_bytes())
+            .unwrap();
+        mem.write(BASE + 0x4c, &24u32.to_le_bytes()).unwrap();
+        let params = TvmAiaParams::read_from(&mem, BASE + 0x40, 32).unwrap();
+        assert_eq!(params.imsic_base_addr, 0x2800_0000);
+        assert_eq!(params.group_index_shift, 24);
+    }
+
+    #[test]
+    fn invalid_address() {
+        let mem = Window([0; 256]);
+        let len = size_of::<TvmCreateParams>() as u64;
+        // Misaligned.
+        assert_eq!(
+            TvmCreateParams::read_from(&mem, BASE + 4, len),
+            Err(Error::InvalidAddress)
+        );
+        // Too short.
+        assert_eq!(
+            TvmCreateParams::read_from(&mem, BASE, len - 8),
+            Err(Error::InvalidAddress)
+        );
+        // Partly outside guest memory.
+        assert_eq!(
+            TvmCreateParams::read_from(&mem, BASE + 0xf8, len),
+            Err(Error::InvalidAddress)
+        );
+        assert_eq!(
+            TvmCreateParams::read_from(&mem, 0, len),
+            Err(Error::InvalidAddress)
+        );
+    }
+
+    #[test]
+    fn read_caller_sized() {
+        let mut mem = Window([0; 256]);
+        mem.write(BASE + 0x20, b"hello").unwrap();
+        let mut buf = [0; 16];
+        // The `len` and `addr` of a DebugConsole Write of "hello".
+        assert_eq!(
+            read_bytes(&mem, BASE + 0x20, 5, &mut buf),
+            Ok(&b"hello"[..])
+        );
+        assert_eq!(read_bytes(&mem, BASE + 0x20, 0, &mut buf), Ok(&[][..]));
+        // Longer than the buffer.
+        assert_eq!(
+            read_bytes(&mem, BASE + 0x20, 17, &mut buf),
+            Err(Error::InvalidParam)
+        );
+        assert_eq!(
+            read_bytes(&mem, BASE + 0x20, u64::MAX, &mut buf),
+            Err(Error::InvalidParam)
+        );
+        // A GetEvidence `cert_request_addr` whose `cert_request_size` runs past the end of guest
+        // memory.
+        assert_eq!(
+            read_bytes(&mem, BASE + 0xfc, 8, &mut buf),
+            Err(Error::InvalidAddress)
+        );
+        // Wraps around the address space.
+        assert_eq!(
+            read_bytes(&mem, u64::MAX - 3, 8, &mut buf),
+            Err(Error::InvalidAddress)
+        );
+    }
+}
@@ -0,0 +1,171 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Result, SbiRegs};
+
+/// Number of harts that can be selected by a single `HartMask`.
+pub const HART_MASK_BITS: u64 = u64::BITS as u64;
+
+/// A set of harts as passed to SBI functions taking a `hart_mask` and `hart_mask_base` pair.
+///
+/// Bit N of `mask` selects hart `base + N`. A `base` of -1 (`u64::MAX`) selects all harts in the
+/// system and `mask` is ignored.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct HartMask {
+    mask: u64,
+    base: u64,
+}
+
+impl HartMask {
+    /// The `hart_mask_base` value that selects all harts.
+    pub const ALL_HARTS_BASE: u64 = u64::MAX;
+
+    /// Creates a `HartMask` from the raw `hart_mask` and `hart_mask_base` register values.
+    pub fn new(mask: u64, base: u64) -> Self {
+        Self { mask, base }
+    }
+
+    /// Returns a `HartMask` that selects all harts in the system.
+    pub fn all() -> Self {
+        Self {
+            mask: 0,
+            base: Self::ALL_HARTS_BASE,
+        }
+    }
+
+    /// Returns a `HartMask` that selects only `hart_id`.
+    ///
+    /// A base of `u64::MAX` would select all harts, so hart `u64::MAX` is selected by the top bit
+    /// of a mask based at `u64::MAX - 63` instead.
+    pub fn single(hart_id: u64) -> Self {
+        let base = hart_id.min(Self::ALL_HARTS_BASE - (HART_MASK_BITS - 1));
+        Self {
+            mask: 1 << (hart_id - base),
+            base,
+        }
+    }
+
+    /// Returns an iterator of `HartMask`s that together select every hart in `hart_ids`.
+    ///
+    /// Consecutive IDs that fall within `HART_MASK_BITS` of the first ID of a window are merged
+    /// into the same mask, so passing the IDs in ascending order produces the fewest masks.
+    pub fn from_hart_ids<I: IntoIterator<Item = u64>>(hart_ids: I) -> HartMaskIter<I::IntoIter> {
+        HartMaskIter {
+            hart_ids: hart_ids.into_iter(),
+            pending: None,
+        }
+    }
+
+    /// Returns the raw `hart_mask` register value.
+    pub fn mask(&self) -> u64 {
+        self.mask
+    }
+
+    /// Returns the raw `hart_mask_base` register value.
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Returns true if this mask selects all harts in the system.
+    pub fn is_all(&self) -> bool {
+        self.base == Self::ALL_HARTS_BASE
+    }
+
+    /// Returns true if `hart_id` is selected by this mask.
+    pub fn contains(&self, hart_id: u64) -> bool {
+        if self.is_all() {
+            return true;
+        }
+        hart_id
+            .checked_sub(self.base)
+            .filter(|&bit| bit < HART_MASK_BITS)
+            .is_some_and(|bit| self.mask & (1 << bit) != 0)
+    }
+
+    // Adds `hart_id` to this mask if it falls within its window, returning false otherwise.
+    fn try_add(&mut self, hart_id: u64) -> bool {
+        match hart_id.checked_sub(self.base) {
+            Some(bit) if bit < HART_MASK_BITS => {
+                self.mask |= 1 << bit;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl SbiRegs<2> for HartMask {
+    fn from_regs(regs: [u64; 2]) -> Result<Self> {
+        Ok(Self::new(regs[0], regs[1]))
+    }
+
+    fn to_regs(&self) -> [u64; 2] {
+        [self.mask, self.base]
+    }
+}
+
+/// Iterator returned by `HartMask::from_hart_ids`.
+pub struct HartMaskIter<I> {
+    hart_ids: I,
+    pending: Option<HartMask>,
+}
+
+impl<I: Iterator<Item = u64>> Iterator for HartMaskIter<I> {
+    type Item = HartMask;
+
+    fn next(&mut self) -> Option<HartMask> {
+        for hart_id in self.hart_ids.by_ref() {
+            if self.pending.as_mut().is_some_and(|m| m.try_add(hart_id)) {
+                continue;
+            }
+            if let Some(mask) = self.pending.replace(HartMask::single(hart_id)) {
+                return Some(mask);
+            }
+        }
+        self.pending.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_harts() {
+        let mask = HartMask::all();
+        assert_eq!(mask.base(), u64::MAX);
+        assert!(mask.is_all());
+        assert!(mask.contains(1234));
+    }
+
+    #[test]
+    fn single_max_hart() {
+        let mask = HartMask::single(u64::MAX);
+        assert_eq!(mask, HartMask::new(1 << 63, u64::MAX - 63));
+        assert!(!mask.is_all());
+        assert!(mask.contains(u64::MAX));
+        assert!(!mask.contains(u64::MAX - 1));
+
+        let mut masks = HartMask::from_hart_ids([u64::MAX, u64::MAX - 1]);
+        assert_eq!(masks.next(), Some(HartMask::new(0b11 << 62, u64::MAX - 63)));
+        assert_eq!(masks.next(), None);
+    }
+
+    #[test]
+    fn single_window() {
+        let mut masks = HartMask::from_hart_ids([2, 3, 5, 65]);
+        assert_eq!(masks.next(), Some(HartMask::new((1 << 63) | 0b1011, 2)));
+        assert_eq!(masks.next(), None);
+    }
+
+    #[test]
+    fn split_windows() {
+        let mut masks = HartMask::from_hart_ids([0, 1, 64, 200, 70]);
+        assert_eq!(masks.next(), Some(HartMask::new(0b11, 0)));
+        assert_eq!(masks.next(), Some(HartMask::new(1, 64)));
+        assert_eq!(masks.next(), Some(HartMask::new(1, 200)));
+        assert_eq!(masks.next(), Some(HartMask::new(1, 70)));
+        assert_eq!(masks.next(), None);
+    }
+}
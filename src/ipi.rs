@@ -0,0 +1,35 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::function::*;
+use crate::HartMask;
+
+/// Functions for the IPI extension
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
+pub enum IpiFunction {
+    /// Sends an inter-processor interrupt to all the harts defined in `hart_mask` and
+    /// `hart_mask_base`. Interprocessor interrupts manifest at the receiving harts as Supervisor
+    /// Software Interrupts.
+    ///
+    /// a6 = 0
+    #[sbi(fid = 0)]
+    SendIpi {
+        /// a0 = bitmask of the harts to interrupt, relative to `hart_mask_base`
+        #[sbi(reg = a0)]
+        hart_mask: u64,
+        /// a1 = hart ID of bit 0 of `hart_mask`, or -1 to select all harts
+        #[sbi(reg = a1)]
+        hart_mask_base: u64,
+    },
+}
+
+impl IpiFunction {
+    /// Creates an operation to send an IPI to the harts selected by `mask`.
+    pub fn send_ipi(mask: HartMask) -> Self {
+        IpiFunction::SendIpi {
+            hart_mask: mask.mask(),
+            hart_mask_base: mask.base(),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::consts::*;
+use crate::function::*;
+
+/// Functions from the legacy (v0.1) SBI extensions, other than console_putchar which is handled by
+/// `SbiMessage::PutChar`. Each legacy function has its own extension ID in a7 and ignores a6.
+///
+/// Legacy functions return a single value in a0, see `SbiReturnType::Legacy`.
+///
+/// Hart masks are passed to the legacy functions as the address of an `unsigned long` bitmask in
+/// the caller's address space, where bit N selects hart N. An address of 0 selects all harts. The
+/// decoded functions keep the raw address; reading the mask from the caller's memory is up to the
+/// implementation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
+#[sbi(fid_reg = a7)]
+pub enum LegacyFunction {
+    /// Programs the clock for the next event after `stime_value` time.
+    ///
+    /// a7 = 0x00
+    #[sbi(fid = EXT_LEGACY_SET_TIMER)]
+    SetTimer {
+        /// a0 = absolute time of the next timer event (a0 and a1 on RV32)
+        #[sbi(regs(a0, a1))]
+        stime_value: u64,
+    },
+    /// Reads a byte from the debug console. Returns the byte read, or -1 if there is none.
+    ///
+    /// a7 = 0x02
+    #[sbi(fid = EXT_LEGACY_GET_CHAR)]
+    GetChar,
+    /// Clears the pending IPI, if any. Returns 0 if no IPI was pending, a positive value otherwise.
+    ///
+    /// a7 = 0x03
+    #[sbi(fid = EXT_LEGACY_CLEAR_IPI)]
+    ClearIpi,
+    /// Sends an inter-processor interrupt to the harts in the hart mask.
+    ///
+    /// a7 = 0x04
+    #[sbi(fid = EXT_LEGACY_SEND_IPI)]
+    SendIpi {
+        /// a0 = address of the hart mask
+        #[sbi(reg = a0)]
+        hart_mask_addr: u64,
+    },
+    /// Instructs the remote harts in the hart mask to execute a FENCE.I instruction.
+    ///
+    /// a7 = 0x05
+    #[sbi(fid = EXT_LEGACY_REMOTE_FENCE_I)]
+    RemoteFenceI {
+        /// a0 = address of the hart mask
+        #[sbi(reg = a0)]
+        hart_mask_addr: u64,
+    },
+    /// Instructs the remote harts in the hart mask to execute one or more SFENCE.VMA instructions
+    /// covering the given range of virtual addresses.
+    ///
+    /// a7 = 0x06
+    #[sbi(fid = EXT_LEGACY_REMOTE_SFENCE_VMA)]
+    RemoteSfenceVma {
+        /// a0 = address of the hart mask
+        #[sbi(reg = a0)]
+        hart_mask_addr: u64,
+        /// a1 = start of the virtual address range
+        #[sbi(reg = a1)]
+        start_addr: u64,
+        /// a2 = size of the virtual address range
+        #[sbi(reg = a2)]
+        size: u64,
+    },
+    /// Instructs the remote harts in the hart mask to execute one or more SFENCE.VMA instructions
+    /// covering the given range of virtual addresses, for the given ASID only.
+    ///
+    /// a7 = 0x07
+    #[sbi(fid = EXT_LEGACY_REMOTE_SFENCE_VMA_ASID)]
+    RemoteSfenceVmaAsid {
+        /// a0 = address of the hart mask
+        #[sbi(reg = a0)]
+        hart_mask_addr: u64,
+        /// a1 = start of the virtual address range
+        #[sbi(reg = a1)]
+        start_addr: u64,
+        /// a2 = size of the virtual address range
+        #[sbi(reg = a2)]
+        size: u64,
+        /// a3 = address space ID
+        #[sbi(reg = a3)]
+        asid: u64,
+    },
+    /// Puts all the harts to shutdown state from the supervisor point of view. Doesn't return.
+    ///
+    /// a7 = 0x08
+    #[sbi(fid = EXT_LEGACY_SHUTDOWN)]
+    Shutdown,
+}
+
+impl LegacyFunction {
+    /// Returns the extension ID of this legacy function.
+    pub fn eid(&self) -> u64 {
+        self.fid()
+    }
+
+    /// Returns the address of the hart mask passed to this function, if it takes one.
+    pub fn hart_mask_addr(&self) -> Option<u64> {
+        use LegacyFunction::*;
+        match self {
+            SendIpi { hart_mask_addr }
+            | RemoteFenceI { hart_mask_addr }
+            | RemoteSfenceVma { hart_mask_addr, .. }
+            | RemoteSfenceVmaAsid { hart_mask_addr, .. } => Some(*hart_mask_addr),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,373 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! A mock SBI implementation for testing code built on the `api` layer on the host.
+//!
+//! `MockSbi::install()` routes the SBI calls made by the calling thread to a new mock until it is
+//! dropped. The mock records every message and answers it from a script of replies, a handler
+//! closure, or with success. Each thread has its own mock, so tests can run in parallel.
+//!
+//! ```
+//! use sbi_rs::mock::{MockReply, MockSbi};
+//! use sbi_rs::{api, CoveHostFunction, SbiMessage};
+//!
+//! let mock = MockSbi::install();
+//! mock.push_reply(MockReply::success(5));
+//! assert_eq!(api::cove_host::tvm_create(0x8000_0000, 0x8001_0000), Ok(5));
+//! assert!(matches!(
+//!     mock.calls()[..],
+//!     [SbiMessage::CoveHost(CoveHostFunction::TvmCreate { .. })]
+//! ));
+//! ```
+
+use std::boxed::Box;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use crate::{
+    override_transport, AttestationFunction, CoveHostFunction, DebugConsoleFunction, Error, Result,
+    SbiMessage, SbiReturn, SbiTransport, TransportOverride,
+};
+
+/// The reply of a `MockSbi` to an SBI call.
+pub enum MockReply {
+    /// Returns the given values without touching memory.
+    Return(SbiReturn),
+    /// Writes to the output buffer of the call and returns the number of bytes written. The
+    /// closure is passed the buffer address and writes the given number of bytes to it.
+    ///
+    /// Returns `InsufficientBufferCapacity` instead if the buffer is too small. Panics if the call
+    /// doesn't take an output buffer.
+    Write(Box<dyn FnOnce(*mut u8)>, usize),
+}
+
+impl MockReply {
+    /// Returns a successful reply with `value` in a1.
+    pub fn success(value: i64) -> Self {
+        MockReply::Return(SbiReturn::success(value))
+    }
+
+    /// Returns a reply that writes `value` to the output buffer of the call, as for `TsmGetInfo`.
+    /// Structures with padding should be written with `write_bytes` and their `to_bytes()` instead.
+    pub fn write<T: Copy + 'static>(value: T) -> Self {
+        MockReply::Write(
+            Box::new(move |dest| {
+                // Safety: `MockTransport` only calls this with a buffer of at least
+                // `size_of::<T>()` bytes, which the caller of the SBI function guarantees is
+                // valid to write.
+                unsafe { (dest as *mut T).write_unaligned(value) }
+            }),
+            core::mem::size_of::<T>(),
+        )
+    }
+
+    /// Returns a reply that copies `bytes` to the output buffer of the call, as for `GetEvidence`.
+    pub fn write_bytes(bytes: &[u8]) -> Self {
+        let bytes = bytes.to_vec();
+        let len = bytes.len();
+        MockReply::Write(
+            Box::new(move |dest| {
+                // Safety: As for `write()`.
+                unsafe { core::ptr::copy_nonoverlapping(bytes.as_ptr(), dest, bytes.len()) }
+            }),
+            len,
+        )
+    }
+}
+
+impl From<SbiReturn> for MockReply {
+    fn from(ret: SbiReturn) -> Self {
+        MockReply::Return(ret)
+    }
+}
+
+impl From<Error> for MockReply {
+    fn from(error: Error) -> Self {
+        MockReply::Return(error.into())
+    }
+}
+
+impl From<Result<i64>> for MockReply {
+    fn from(result: Result<i64>) -> Self {
+        MockReply::Return(result.into())
+    }
+}
+
+type MockHandler = Box<dyn FnMut(&SbiMessage) -> MockReply>;
+
+#[derive(Default)]
+struct MockState {
+    calls: Vec<SbiMessage>,
+    replies: VecDeque<MockReply>,
+    handler: Option<MockHandler>,
+}
+
+/// A mock SBI implementation for the calling thread. SBI calls are answered, in order of
+/// preference, by the next reply pushed with `push_reply`, by the handler set with `set_handler`,
+/// or with success and a return value of 0.
+///
+/// The mock is uninstalled when dropped, restoring the transport the thread had before.
+pub struct MockSbi {
+    state: Rc<RefCell<MockState>>,
+    _override: TransportOverride,
+}
+
+impl MockSbi {
+    /// Creates a mock and installs it as the SBI implementation for the calling thread until it is
+    /// dropped. Mocks installed on the same thread must be dropped in the reverse order they were
+    /// installed.
+    pub fn install() -> Self {
+        let state = Rc::new(RefCell::new(MockState::default()));
+        let transport = MockTransport {
+            state: state.clone(),
+        };
+        Self {
+            state,
+            _override: override_transport(Rc::new(transport)),
+        }
+    }
+
+    /// Queues `reply` as the reply to the first SBI call not answered by an earlier reply.
+    pub fn push_reply(&self, reply: impl Into<MockReply>) {
+        self.state.borrow_mut().replies.push_back(reply.into());
+    }
+
+    /// Sets the handler used to answer SBI calls once the queued replies run out.
+    pub fn set_handler(&self, handler: impl FnMut(&SbiMessage) -> MockReply + 'static) {
+        self.state.borrow_mut().handler = Some(Box::new(handler));
+    }
+
+    /// Returns the messages sent so far, in order.
+    pub fn calls(&self) -> Vec<SbiMessage> {
+        self.state.borrow().calls.clone()
+    }
+
+    /// Returns the messages sent so far, in order, and clears the record.
+    pub fn take_calls(&self) -> Vec<SbiMessage> {
+        core::mem::take(&mut self.state.borrow_mut().calls)
+    }
+
+    /// Returns the number of queued replies that haven't been used yet.
+    pub fn pending_replies(&self) -> usize {
+        self.state.borrow().replies.len()
+    }
+}
+
+// Returns the address and size of the buffer `msg` has the firmware write to, if any.
+fn output_buffer(msg: &SbiMessage) -> Option<(u64, u64)> {
+    use SbiMessage::*;
+    match *msg {
+        CoveHost(CoveHostFunction::TsmGetInfo { dest_addr, len }) => Some((dest_addr, len)),
+        Attestation(AttestationFunction::GetCapabilities {
+            caps_addr_out,
+            caps_size,
+        }) => Some((caps_addr_out, caps_size)),
+        Attestation(AttestationFunction::GetEvidence {
+            cert_addr_out,
+            cert_size,
+            ..
+        }) => Some((cert_addr_out, cert_size)),
+        Attestation(AttestationFunction::ReadMeasurement {
+            measurement_data_addr_out,
+            measurement_data_size,
+            ..
+        }) => Some((measurement_data_addr_out, measurement_data_size)),
+        DebugConsole(DebugConsoleFunction::Read { len, addr, .. }) => Some((addr, len)),
+        _ => None,
+    }
+}
+
+// Answers SBI calls on behalf of a `MockSbi`.
+struct MockTransport {
+    state: Rc<RefCell<MockState>>,
+}
+
+impl SbiTransport for MockTransport {
+    unsafe fn ecall(&self, msg: &SbiMessage) -> SbiReturn {
+        let state = &self.state;
+        state.borrow_mut().calls.push(*msg);
+        let reply = state.borrow_mut().replies.pop_front();
+        let reply = match reply {
+            Some(reply) => reply,
+            None => {
+                // Take the handler out while it runs so it can use the mock itself.
+                let handler = state.borrow_mut().handler.take();
+                match handler {
+                    Some(mut handler) => {
+                        let reply = handler(msg);
+                        state.borrow_mut().handler.get_or_insert(handler);
+                        reply
+                    }
+                    None => MockReply::success(0),
+                }
+            }
+        };
+
+        match reply {
+            MockReply::Return(ret) => ret,
+            MockReply::Write(write, size) => {
+                let (addr, len) = output_buffer(msg)
+                    .unwrap_or_else(|| panic!("{msg:?} doesn't take an output buffer"));
+                if len < size as u64 {
+                    return Error::InsufficientBufferCapacity.into();
+                }
+                write(addr as *mut u8);
+                SbiReturn::success(size as i64)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{attestation, cove_host, legacy};
+    use crate::{
+        AttestationCapabilities, EvidenceFormat, HashAlgorithm, TsmInfo, TsmState,
+        EVIDENCE_DATA_BLOB_SIZE,
+    };
+
+    #[test]
+    fn records_calls() {
+        let mock = MockSbi::install();
+        mock.push_reply(MockReply::success(7));
+        // Safety: No memory is touched by the mock.
+        unsafe { cove_host::convert_pages(0x8000_0000, 4) }.unwrap();
+        cove_host::tsm_initiate_fence().unwrap();
+        assert_eq!(cove_host::tvm_create(0x8000_0000, 0x8000_4000), Ok(0));
+
+        let calls = mock.take_calls();
+        assert_eq!(
+            calls[..2],
+            [
+                SbiMessage::CoveHost(CoveHostFunction::TsmConvertPages {
+                    page_addr: 0x8000_0000,
+                    num_pages: 4,
+                }),
+                SbiMessage::CoveHost(CoveHostFunction::TsmInitiateFence),
+            ]
+        );
+        assert!(matches!(
+            calls[2],
+            SbiMessage::CoveHost(CoveHostFunction::TvmCreate { .. })
+        ));
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn nested() {
+        let outer = MockSbi::install();
+        {
+            let inner = MockSbi::install();
+            cove_host::tsm_initiate_fence().unwrap();
+            assert_eq!(inner.calls().len(), 1);
+        }
+        cove_host::tsm_initiate_fence().unwrap();
+        assert_eq!(outer.calls().len(), 1);
+    }
+
+    #[test]
+    fn legacy_getchar() {
+        let mock = MockSbi::install();
+        // Legacy extensions return their value in a0.
+        mock.push_reply(MockReply::Return(SbiReturn {
+            error_code: b'x' as i64,
+            return_value: 0,
+        }));
+        mock.push_reply(MockReply::Return(SbiReturn {
+            error_code: -1,
+            return_value: 0,
+        }));
+        assert_eq!(legacy::console_getchar(), Ok(Some(b'x')));
+        assert_eq!(legacy::console_getchar(), Ok(None));
+        assert_eq!(legacy::console_putchar(b'x'), Ok(()));
+    }
+
+    #[test]
+    fn scripted_errors() {
+        let mock = MockSbi::install();
+        mock.push_reply(Error::Denied);
+        mock.push_reply(Ok(3));
+        assert_eq!(cove_host::tvm_destroy(1), Err(Error::Denied));
+        assert_eq!(cove_host::tvm_run(1, 0), Ok(3));
+        assert_eq!(cove_host::tvm_destroy(1), Ok(()));
+        assert_eq!(mock.pending_replies(), 0);
+    }
+
+    #[test]
+    fn handler() {
+        let mock = MockSbi::install();
+        mock.set_handler(|msg| match msg {
+            SbiMessage::CoveHost(CoveHostFunction::TvmCreate { params_addr, .. }) => {
+                // Safety: `tvm_create` passes a pointer to its `TvmCreateParams`.
+                let params = unsafe { (*params_addr as *const crate::TvmCreateParams).read() };
+                MockReply::success((params.tvm_page_directory_addr >> 14) as i64)
+            }
+            _ => Error::NotSupported.into(),
+        });
+        assert_eq!(
+            cove_host::tvm_create(0x8000_4000, 0x8001_0000),
+            Ok(0x2_0001)
+        );
+        assert_eq!(cove_host::tvm_destroy(1), Err(Error::NotSupported));
+    }
+
+    #[test]
+    fn memory_writes() {
+        let mock = MockSbi::install();
+        mock.push_reply(MockReply::write(TsmInfo {
+            tsm_state: TsmState::TsmReady,
+            tsm_version: 2,
+            tvm_state_pages: 4,
+            tvm_max_vcpus: 8,
+            tvm_vcpu_state_pages: 1,
+        }));
+        mock.push_reply(MockReply::write_bytes(
+            &AttestationCapabilities::new(
+                3,
+                HashAlgorithm::Sha512,
+                EvidenceFormat::DiceTcbInfo,
+                1,
+                2,
+            )
+            .to_bytes(),
+        ));
+        mock.push_reply(MockReply::write_bytes(b"certificate"));
+
+        let info = cove_host::get_info().unwrap();
+        assert_eq!(info.tsm_state, TsmState::TsmReady);
+        assert_eq!(info.tvm_max_vcpus, 8);
+
+        let caps = attestation::get_capabilities().unwrap();
+        assert_eq!(caps.tcb_svn, 3);
+        assert_eq!(caps.runtime_measurements, 2);
+
+        let cert = attestation::get_evidence(
+            b"csr",
+            &[0; EVIDENCE_DATA_BLOB_SIZE],
+            EvidenceFormat::DiceTcbInfo,
+        )
+        .unwrap();
+        assert_eq!(cert.as_slice(), b"certificate");
+    }
+
+    #[test]
+    fn invalid_tsm_output() {
+        let mock = MockSbi::install();
+        let mut info = TsmInfo::default().to_bytes();
+        info[0] = 7;
+        mock.push_reply(MockReply::write_bytes(&info));
+        let mut caps = AttestationCapabilities::default().to_bytes();
+        caps[8] = 0xff;
+        mock.push_reply(MockReply::write_bytes(&caps));
+
+        assert_eq!(cove_host::get_info().unwrap_err(), Error::InvalidParam);
+        assert_eq!(
+            attestation::get_capabilities().unwrap_err(),
+            Error::InvalidParam
+        );
+    }
+}
@@ -0,0 +1,344 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use static_assertions::const_assert;
+
+use crate::error::*;
+use crate::function::*;
+
+/// Required alignment of the MPXY shared memory.
+pub const MPXY_SHMEM_ALIGN: u64 = 4096;
+
+/// Channel attribute IDs, used with `ReadAttributes` and `WriteAttributes`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MpxyAttrId {
+    /// The ID of the message protocol used by the channel. Read-only.
+    MsgProtId,
+    /// The version of the message protocol used by the channel. Read-only.
+    MsgProtVersion,
+    /// The maximum message data length in bytes. Read-only.
+    MsgMaxLen,
+    /// The message send timeout in microseconds. Read-only.
+    MsgSendTimeout,
+    /// The message completion timeout in microseconds. Read-only.
+    MsgCompletionTimeout,
+    /// The channel's capabilities. Read-only.
+    ChannelCapability,
+    /// The SSE event ID used for the channel's notifications. Read-only.
+    SseEventId,
+    /// Enables or disables MSI-based notifications.
+    MsiControl,
+    /// The lower 32 bits of the MSI address.
+    MsiAddrLo,
+    /// The upper 32 bits of the MSI address.
+    MsiAddrHi,
+    /// The MSI data value.
+    MsiData,
+    /// Enables or disables reporting of the event state with notifications.
+    EventsStateControl,
+    /// A standard attribute ID that isn't defined by the spec, in `0x0000000C..=0x7FFFFFFF`.
+    Reserved(ReservedMpxyAttrId),
+    /// A message protocol-specific attribute ID, in `0x80000000..=0xFFFFFFFF`.
+    ProtocolSpecific(ProtocolMpxyAttrId),
+}
+
+/// A standard MPXY attribute ID that isn't defined by the spec. Created with
+/// `MpxyAttrId::reserved`, which checks that the ID is in the reserved range.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ReservedMpxyAttrId(u32);
+
+impl ReservedMpxyAttrId {
+    /// Returns the raw attribute ID.
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
+/// A message protocol-specific MPXY attribute ID. Created with `MpxyAttrId::protocol_specific`,
+/// which checks that the ID is in the protocol-specific range.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ProtocolMpxyAttrId(u32);
+
+impl ProtocolMpxyAttrId {
+    /// Returns the raw attribute ID.
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
+impl MpxyAttrId {
+    /// Creates a reserved attribute ID. Returns `InvalidParam` unless `raw` is in the range
+    /// `0x0000000C..=0x7FFFFFFF`.
+    pub fn reserved(raw: u32) -> Result<Self> {
+        match raw {
+            0x0c..=0x7fff_ffff => Ok(MpxyAttrId::Reserved(ReservedMpxyAttrId(raw))),
+            _ => Err(Error::InvalidParam),
+        }
+    }
+
+    /// Creates a message protocol-specific attribute ID. Returns `InvalidParam` unless `raw` is in
+    /// the range `0x80000000..=0xFFFFFFFF`.
+    pub fn protocol_specific(raw: u32) -> Result<Self> {
+        match raw {
+            0x8000_0000..=0xffff_ffff => Ok(MpxyAttrId::ProtocolSpecific(ProtocolMpxyAttrId(raw))),
+            _ => Err(Error::InvalidParam),
+        }
+    }
+
+    /// Creates an attribute ID from a register value or returns an error if the value doesn't fit
+    /// in 32 bits.
+    pub fn from_reg(reg: u64) -> Result<Self> {
+        use MpxyAttrId::*;
+        let raw = u32::try_from(reg).map_err(|_| Error::InvalidParam)?;
+        Ok(match raw {
+            0x00 => MsgProtId,
+            0x01 => MsgProtVersion,
+            0x02 => MsgMaxLen,
+            0x03 => MsgSendTimeout,
+            0x04 => MsgCompletionTimeout,
+            0x05 => ChannelCapability,
+            0x06 => SseEventId,
+            0x07 => MsiControl,
+            0x08 => MsiAddrLo,
+            0x09 => MsiAddrHi,
+            0x0a => MsiData,
+            0x0b => EventsStateControl,
+            0x0c..=0x7fff_ffff => return Self::reserved(raw),
+            0x8000_0000..=0xffff_ffff => return Self::protocol_specific(raw),
+        })
+    }
+
+    /// Returns the raw value of this attribute ID.
+    pub fn raw(&self) -> u32 {
+        use MpxyAttrId::*;
+        match self {
+            MsgProtId => 0x00,
+            MsgProtVersion => 0x01,
+            MsgMaxLen => 0x02,
+            MsgSendTimeout => 0x03,
+            MsgCompletionTimeout => 0x04,
+            ChannelCapability => 0x05,
+            SseEventId => 0x06,
+            MsiControl => 0x07,
+            MsiAddrLo => 0x08,
+            MsiAddrHi => 0x09,
+            MsiData => 0x0a,
+            EventsStateControl => 0x0b,
+            Reserved(id) => id.raw(),
+            ProtocolSpecific(id) => id.raw(),
+        }
+    }
+}
+
+impl SbiReg for MpxyAttrId {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_reg(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.raw() as u64
+    }
+}
+
+/// The header written to the start of the shared memory by `GetChannelIds`. It's followed by
+/// `returned` u32 channel IDs.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MpxyChannelIdsHeader {
+    /// The number of channel IDs remaining after the ones returned.
+    pub remaining: u32,
+    /// The number of channel IDs returned.
+    pub returned: u32,
+}
+
+/// The header written to the start of the shared memory by `GetNotificationEvents`. It's followed
+/// by `returned` bytes of notification event data.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MpxyNotificationHeader {
+    /// The number of bytes of event data remaining after the ones returned.
+    pub remaining: u32,
+    /// The number of bytes of event data returned.
+    pub returned: u32,
+    /// The number of events lost because the channel's event buffer was full.
+    pub lost: u32,
+    /// Reserved for future use.
+    pub reserved: u32,
+}
+
+fn _assert_mpxy_header_sizes() {
+    const_assert!(core::mem::size_of::<MpxyChannelIdsHeader>() == 8);
+    const_assert!(core::mem::size_of::<MpxyNotificationHeader>() == 16);
+}
+
+/// Functions for the Message Proxy (MPXY) extension
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
+pub enum MpxyFunction {
+    /// Returns the size in bytes of the shared memory required by the calling hart. The size is
+    /// always a multiple of 4KiB.
+    ///
+    /// a6 = 0
+    #[sbi(fid = 0)]
+    GetShmemSize,
+    /// Registers the shared memory used by the calling hart for message data. Passing all-ones in
+    /// both `shmem_phys_lo` and `shmem_phys_hi` unregisters the shared memory.
+    ///
+    /// a6 = 1
+    #[sbi(fid = 1)]
+    SetShmem {
+        /// a0 = lower XLEN bits of the physical address of the shared memory
+        #[sbi(reg = a0)]
+        shmem_phys_lo: u64,
+        /// a1 = upper bits of the physical address of the shared memory (RV32 only)
+        #[sbi(reg = a1)]
+        shmem_phys_hi: u64,
+        /// a2 = flags. 0 replaces any previous shared memory. 1 also writes the previous physical
+        /// address to the start of the new shared memory as two XLEN words.
+        #[sbi(reg = a2)]
+        flags: u64,
+    },
+    /// Writes an `MpxyChannelIdsHeader` followed by channel IDs, starting at `start_index`, to the
+    /// shared memory.
+    ///
+    /// a6 = 2
+    #[sbi(fid = 2)]
+    GetChannelIds {
+        /// a0 = index of the first channel ID to return
+        #[sbi(reg = a0)]
+        start_index: u64,
+    },
+    /// Reads `attr_count` u32 attributes of the channel, starting at `base_attr_id`, into the
+    /// shared memory.
+    ///
+    /// a6 = 3
+    #[sbi(fid = 3)]
+    ReadAttributes {
+        /// a0 = channel ID
+        #[sbi(reg = a0)]
+        channel_id: u64,
+        /// a1 = ID of the first attribute to read
+        #[sbi(reg = a1)]
+        base_attr_id: MpxyAttrId,
+        /// a2 = number of attributes to read
+        #[sbi(reg = a2)]
+        attr_count: u64,
+    },
+    /// Writes `attr_count` u32 attributes of the channel, starting at `base_attr_id`, from the
+    /// shared memory.
+    ///
+    /// a6 = 4
+    #[sbi(fid = 4)]
+    WriteAttributes {
+        /// a0 = channel ID
+        #[sbi(reg = a0)]
+        channel_id: u64,
+        /// a1 = ID of the first attribute to write
+        #[sbi(reg = a1)]
+        base_attr_id: MpxyAttrId,
+        /// a2 = number of attributes to write
+        #[sbi(reg = a2)]
+        attr_count: u64,
+    },
+    /// Sends the `msg_data_len` bytes of message data in the shared memory over the channel and
+    /// waits for the response. The response data is written to the shared memory and its length
+    /// returned.
+    ///
+    /// a6 = 5
+    #[sbi(fid = 5)]
+    SendMessageWithResponse {
+        /// a0 = channel ID
+        #[sbi(reg = a0)]
+        channel_id: u64,
+        /// a1 = message protocol-specific message ID
+        #[sbi(reg = a1)]
+        msg_id: u64,
+        /// a2 = length of the message data in bytes
+        #[sbi(reg = a2)]
+        msg_data_len: u64,
+    },
+    /// Sends the `msg_data_len` bytes of message data in the shared memory over the channel
+    /// without waiting for a response.
+    ///
+    /// a6 = 6
+    #[sbi(fid = 6)]
+    SendMessageWithoutResponse {
+        /// a0 = channel ID
+        #[sbi(reg = a0)]
+        channel_id: u64,
+        /// a1 = message protocol-specific message ID
+        #[sbi(reg = a1)]
+        msg_id: u64,
+        /// a2 = length of the message data in bytes
+        #[sbi(reg = a2)]
+        msg_data_len: u64,
+    },
+    /// Writes an `MpxyNotificationHeader` followed by the channel's pending notification event
+    /// data to the shared memory.
+    ///
+    /// a6 = 7
+    #[sbi(fid = 7)]
+    GetNotificationEvents {
+        /// a0 = channel ID
+        #[sbi(reg = a0)]
+        channel_id: u64,
+    },
+}
+
+impl MpxyFunction {
+    /// Returns the channel ID this function operates on, if any.
+    pub fn channel_id(&self) -> Option<u64> {
+        use MpxyFunction::*;
+        match self {
+            ReadAttributes { channel_id, .. }
+            | WriteAttributes { channel_id, .. }
+            | SendMessageWithResponse { channel_id, .. }
+            | SendMessageWithoutResponse { channel_id, .. }
+            | GetNotificationEvents { channel_id } => Some(*channel_id),
+            GetShmemSize | SetShmem { .. } | GetChannelIds { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attr_id_ranges() {
+        use MpxyAttrId::*;
+        let reserved = |raw| MpxyAttrId::reserved(raw).unwrap();
+        let protocol = |raw| MpxyAttrId::protocol_specific(raw).unwrap();
+        for (reg, expected) in [
+            (0x0000_0000, MsgProtId),
+            (0x0000_000b, EventsStateControl),
+            (0x0000_000c, reserved(0x0000_000c)),
+            (0x7fff_ffff, reserved(0x7fff_ffff)),
+            (0x8000_0000, protocol(0x8000_0000)),
+            (0xffff_ffff, protocol(0xffff_ffff)),
+        ] {
+            assert_eq!(MpxyAttrId::from_reg(reg), Ok(expected), "{reg:#x}");
+            assert_eq!(expected.raw() as u64, reg);
+        }
+        assert_eq!(
+            MpxyAttrId::from_reg(0x1_0000_0000),
+            Err(Error::InvalidParam)
+        );
+
+        // Standard attribute IDs can't be wrapped as reserved or protocol-specific ones.
+        for raw in [0x00, 0x0b, 0x8000_0000] {
+            assert_eq!(
+                MpxyAttrId::reserved(raw),
+                Err(Error::InvalidParam),
+                "{raw:#x}"
+            );
+        }
+        for raw in [0x00, 0x0c, 0x7fff_ffff] {
+            assert_eq!(
+                MpxyAttrId::protocol_specific(raw),
+                Err(Error::InvalidParam),
+                "{raw:#x}"
+            );
+        }
+    }
+}
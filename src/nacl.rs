@@ -2,12 +2,134 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use core::mem::{offset_of, size_of};
+use static_assertions::const_assert;
+
+use crate::bytes::*;
 use crate::error::*;
 use crate::function::*;
 
 /// Number of bytes in the `NaclShmem` scratch area.
 pub const NACL_SCRATCH_BYTES: usize = 2048;
 
+/// Number of entries in the `NaclShmem` HFENCE queue.
+pub const NACL_HFENCE_ENTRIES: usize = 60;
+
+/// Types of HFENCE requests in a `NaclHfenceEntry`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum NaclHfenceType {
+    /// HFENCE.GVMA of a range of guest physical pages.
+    Gvma = 0,
+    /// HFENCE.GVMA of all guest physical addresses.
+    GvmaAll = 1,
+    /// HFENCE.GVMA of a range of guest physical pages for a single VMID.
+    GvmaVmid = 2,
+    /// HFENCE.GVMA of all guest physical addresses for a single VMID.
+    GvmaVmidAll = 3,
+    /// HFENCE.VVMA of a range of guest virtual pages for a single VMID.
+    Vvma = 4,
+    /// HFENCE.VVMA of all guest virtual addresses for a single VMID.
+    VvmaAll = 5,
+    /// HFENCE.VVMA of a range of guest virtual pages for a single VMID and ASID.
+    VvmaAsid = 6,
+    /// HFENCE.VVMA of all guest virtual addresses for a single VMID and ASID.
+    VvmaAsidAll = 7,
+}
+
+impl NaclHfenceType {
+    /// Creates a HFENCE type from its raw value or returns an error if the value is reserved.
+    pub fn from_reg(reg: u64) -> Result<Self> {
+        use NaclHfenceType::*;
+        Ok(match reg {
+            0 => Gvma,
+            1 => GvmaAll,
+            2 => GvmaVmid,
+            3 => GvmaVmidAll,
+            4 => Vvma,
+            5 => VvmaAll,
+            6 => VvmaAsid,
+            7 => VvmaAsidAll,
+            _ => return Err(Error::InvalidParam),
+        })
+    }
+}
+
+/// An entry in the HFENCE queue of `NaclShmem`, processed by `SyncHfence` and `SyncSret`.
+///
+/// The `config` word holds, from most to least significant bit: the pending flag (bit 63), the
+/// HFENCE type (bits 59:56), the page order (bits 54:48), the VMID (bits 29:16) and the ASID (bits
+/// 15:0). Each page covers `1 << (order + 12)` bytes.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NaclHfenceEntry {
+    /// The configuration word, described above.
+    pub config: u64,
+    /// The number of the first page to fence, in units of the page size.
+    pub page_num: u64,
+    _reserved: u64,
+    /// The number of pages to fence.
+    pub page_count: u64,
+}
+
+impl NaclHfenceEntry {
+    const PENDING: u64 = 1 << 63;
+    const TYPE_SHIFT: u64 = 56;
+    const TYPE_MASK: u64 = 0xf;
+    const ORDER_SHIFT: u64 = 48;
+    const ORDER_MASK: u64 = 0x7f;
+    const VMID_SHIFT: u64 = 16;
+    const VMID_MASK: u64 = 0x3fff;
+    const ASID_MASK: u64 = 0xffff;
+
+    /// Creates a pending HFENCE entry. `vmid` and `asid` are ignored by types that don't use them,
+    /// as are `order`, `page_num` and `page_count` by the types that fence all addresses.
+    pub fn new(
+        hfence_type: NaclHfenceType,
+        order: u8,
+        vmid: u16,
+        asid: u16,
+        page_num: u64,
+        page_count: u64,
+    ) -> Self {
+        let config = Self::PENDING
+            | ((hfence_type as u64) << Self::TYPE_SHIFT)
+            | ((order as u64 & Self::ORDER_MASK) << Self::ORDER_SHIFT)
+            | ((vmid as u64 & Self::VMID_MASK) << Self::VMID_SHIFT)
+            | (asid as u64 & Self::ASID_MASK);
+        Self {
+            config,
+            page_num,
+            _reserved: 0,
+            page_count,
+        }
+    }
+
+    /// Returns true if this entry is waiting to be processed.
+    pub fn is_pending(&self) -> bool {
+        self.config & Self::PENDING != 0
+    }
+
+    /// Returns the type of this entry, or an error if the type is reserved.
+    pub fn hfence_type(&self) -> Result<NaclHfenceType> {
+        NaclHfenceType::from_reg((self.config >> Self::TYPE_SHIFT) & Self::TYPE_MASK)
+    }
+
+    /// Returns the page order of this entry.
+    pub fn order(&self) -> u8 {
+        ((self.config >> Self::ORDER_SHIFT) & Self::ORDER_MASK) as u8
+    }
+
+    /// Returns the VMID of this entry.
+    pub fn vmid(&self) -> u16 {
+        ((self.config >> Self::VMID_SHIFT) & Self::VMID_MASK) as u16
+    }
+
+    /// Returns the ASID of this entry.
+    pub fn asid(&self) -> u16 {
+        (self.config & Self::ASID_MASK) as u16
+    }
+}
+
 /// Layout of the shared-memory area registered with `SetShmem`.
 #[repr(C)]
 pub struct NaclShmem {
@@ -17,7 +139,8 @@ pub struct NaclShmem {
     /// For the `TvmCpuRun` function in the COVE-Host extension, the layout of this scratch space
     /// matches the `TsmShmemScratch` struct.
     pub scratch: [u64; NACL_SCRATCH_BYTES / 8],
-    _reserved: [u64; 240],
+    /// Queue of HFENCE requests to be processed by `SyncHfence` or `SyncSret`.
+    pub hfence_queue: [NaclHfenceEntry; NACL_HFENCE_ENTRIES],
     /// Bitmap indicating which CSRs in `csrs` the host wishes to sync.
     ///
     /// Currently unused in the COVE-related extensions and will not be read or written by the TSM.
@@ -36,13 +159,110 @@ impl NaclShmem {
     pub fn csr_index(csr_num: u16) -> usize {
         (((csr_num & 0xc00) >> 2) | (csr_num & 0xff)) as usize
     }
+
+    /// Returns the index in `dirty_bitmap` and the bit mask within that word that track the HS or
+    /// VS CSR at `csr_num`.
+    pub fn csr_dirty_bit(csr_num: u16) -> (usize, u64) {
+        let index = Self::csr_index(csr_num);
+        (index / 64, 1 << (index % 64))
+    }
+
+    /// The size of `NaclShmem` in bytes.
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Decodes a `NaclShmem` from `bytes`. Returns `InvalidParam` if `bytes` isn't exactly `SIZE`
+    /// bytes long.
+    ///
+    /// This copies the whole area; use the `*_offset` functions below to access single fields in
+    /// place.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        check_size(bytes, Self::SIZE)?;
+        let mut shmem = Self::default();
+        get_u64s(bytes, offset_of!(Self, scratch), &mut shmem.scratch);
+        for (i, entry) in shmem.hfence_queue.iter_mut().enumerate() {
+            let mut words = [0; 4];
+            get_u64s(bytes, Self::hfence_entry_offset(i), &mut words);
+            *entry = NaclHfenceEntry {
+                config: words[0],
+                page_num: words[1],
+                _reserved: words[2],
+                page_count: words[3],
+            };
+        }
+        get_u64s(
+            bytes,
+            offset_of!(Self, dirty_bitmap),
+            &mut shmem.dirty_bitmap,
+        );
+        get_u64s(bytes, offset_of!(Self, csrs), &mut shmem.csrs);
+        Ok(shmem)
+    }
+
+    /// Encodes this shared-memory area in the layout registered with `SetShmem`.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        put_u64s(&mut bytes, offset_of!(Self, scratch), &self.scratch);
+        for (i, entry) in self.hfence_queue.iter().enumerate() {
+            put_u64s(
+                &mut bytes,
+                Self::hfence_entry_offset(i),
+                &[
+                    entry.config,
+                    entry.page_num,
+                    entry._reserved,
+                    entry.page_count,
+                ],
+            );
+        }
+        put_u64s(
+            &mut bytes,
+            offset_of!(Self, dirty_bitmap),
+            &self.dirty_bitmap,
+        );
+        put_u64s(&mut bytes, offset_of!(Self, csrs), &self.csrs);
+        bytes
+    }
+
+    /// Returns the byte offset from the start of the area of the `index`th u64 of `scratch`.
+    pub fn scratch_offset(index: usize) -> usize {
+        assert!(index < NACL_SCRATCH_BYTES / 8);
+        offset_of!(Self, scratch) + index * 8
+    }
+
+    /// Returns the byte offset from the start of the area of the HFENCE queue entry at `index`,
+    /// which must be less than `NACL_HFENCE_ENTRIES`.
+    pub fn hfence_entry_offset(index: usize) -> usize {
+        assert!(index < NACL_HFENCE_ENTRIES);
+        offset_of!(Self, hfence_queue) + index * size_of::<NaclHfenceEntry>()
+    }
+
+    /// Returns the byte offset from the start of the area of the u64 of `dirty_bitmap` that tracks
+    /// the HS or VS CSR at `csr_num`, along with the bit mask within that word.
+    pub fn csr_dirty_offset(csr_num: u16) -> (usize, u64) {
+        let (word, bit) = Self::csr_dirty_bit(csr_num);
+        (offset_of!(Self, dirty_bitmap) + word * 8, bit)
+    }
+
+    /// Returns the byte offset from the start of the area of the HS or VS CSR at `csr_num`.
+    pub fn csr_offset(csr_num: u16) -> usize {
+        offset_of!(Self, csrs) + Self::csr_index(csr_num) * 8
+    }
+}
+
+fn _assert_nacl_shmem_layout() {
+    const_assert!(core::mem::size_of::<NaclHfenceEntry>() == 32);
+    const_assert!(NaclShmem::SIZE == 0x3000);
+    const_assert!(core::mem::offset_of!(NaclShmem, scratch) == 0);
+    const_assert!(core::mem::offset_of!(NaclShmem, hfence_queue) == 0x800);
+    const_assert!(core::mem::offset_of!(NaclShmem, dirty_bitmap) == 0xf80);
+    const_assert!(core::mem::offset_of!(NaclShmem, csrs) == 0x1000);
 }
 
 impl Default for NaclShmem {
     fn default() -> Self {
         Self {
             scratch: [0; 256],
-            _reserved: [0; 240],
+            hfence_queue: [NaclHfenceEntry::default(); NACL_HFENCE_ENTRIES],
             dirty_bitmap: [0; 16],
             csrs: [0; 1024],
         }
@@ -50,6 +270,7 @@ impl Default for NaclShmem {
 }
 
 /// NaclFunction::ProbeFeature feature IDs.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum NaclFeature {
     /// The synchronize CSR feature describes the ability of the SBI implementation
     /// (Salus) to allow supervisor software (Host) to write RISC-V H-extension CSRs
@@ -68,16 +289,32 @@ pub enum NaclFeature {
     AutoSwapCsr = 3,
 }
 
+impl NaclFeature {
+    /// Creates a feature ID from a register value or returns an error if the feature is unknown.
+    pub fn from_reg(reg: u64) -> Result<Self> {
+        use NaclFeature::*;
+        Ok(match reg {
+            0 => SyncCSR,
+            1 => SyncHfence,
+            2 => SyncSret,
+            3 => AutoSwapCsr,
+            _ => return Err(Error::InvalidParam),
+        })
+    }
+}
+
 /// Functions provided by the Nested Virtualization Acceleration (NACL) extension.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
 pub enum NaclFunction {
     /// Allows the nested hypervisor to query Nacl features supported by the host hypervisor.
     /// Features are given in `enum NaclFeature`. Salus doesn't support any of the features
     /// and only allows the host to read CSRs. Any writes are ignored.
     ///
     /// a6 = 0
+    #[sbi(fid = 0)]
     ProbeFeature {
         /// a0 = Feature ID being checked.
+        #[sbi(reg = a0)]
         feature_id: u64,
     },
     /// Registers the nested hypervisor <-> host hypervisor shared memory area for the calling CPU.
@@ -89,45 +326,117 @@ pub enum NaclFunction {
     /// the non-confidential state and cannot be converted.
     ///
     /// a6 = 1
+    #[sbi(fid = 1)]
     SetShmem {
         /// a0 = Address of the shared memory area.
+        #[sbi(reg = a0)]
         shmem_addr: u64,
     },
-    // There are other functions in the proposed NACL extension, but we ignore them as they aren't
-    // relevant to the COVE extensions. Note that this violates SBI policy, but since both the COVE and
-    // NACL extensions are in active development, we let it go for now.
+    /// Synchronizes the HS and VS CSRs marked in `dirty_bitmap` from the `csrs` array and clears
+    /// their dirty bits. Requires the `SyncCSR` feature.
+    ///
+    /// a6 = 2
+    #[sbi(fid = 2)]
+    SyncCsr {
+        /// a0 = CSR number to synchronize, or -1 to synchronize all dirty CSRs.
+        #[sbi(reg = a0)]
+        csr_num: u64,
+    },
+    /// Processes the pending entries in `hfence_queue` and clears their pending flags. Requires
+    /// the `SyncHfence` feature.
+    ///
+    /// a6 = 3
+    #[sbi(fid = 3)]
+    SyncHfence {
+        /// a0 = Index of the entry to process, or -1 to process all pending entries.
+        #[sbi(reg = a0)]
+        entry_index: u64,
+    },
+    /// Synchronizes all dirty CSRs and pending HFENCEs, then emulates an SRET to enter the guest
+    /// using the state in the scratch space. Doesn't return on success. Requires the `SyncSret`
+    /// feature.
+    ///
+    /// a6 = 4
+    #[sbi(fid = 4)]
+    SyncSret,
 }
 
-impl NaclFunction {
-    /// Attempts to parse `Self` from the passed in `a0-a7`.
-    pub(crate) fn from_regs(args: &[u64]) -> Result<Self> {
-        use NaclFunction::*;
-        match args[6] {
-            0 => Ok(ProbeFeature {
-                feature_id: args[0],
-            }),
-            1 => Ok(SetShmem {
-                shmem_addr: args[0],
-            }),
-            _ => Err(Error::NotSupported),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::nacl::NaclShmemRef;
+
+    // Returns the u64 at byte `offset` of `shmem`.
+    fn word_at(shmem: &NaclShmem, offset: usize) -> u64 {
+        // Safety: the offsets passed are within `NaclShmem` and 8-byte aligned.
+        unsafe {
+            (shmem as *const NaclShmem as *const u8)
+                .add(offset)
+                .cast::<u64>()
+                .read()
         }
     }
-}
 
-impl SbiFunction for NaclFunction {
-    fn a6(&self) -> u64 {
-        use NaclFunction::*;
-        match self {
-            ProbeFeature { .. } => 0,
-            SetShmem { .. } => 1,
-        }
+    #[test]
+    fn offsets() {
+        let mut shmem = NaclShmem::default();
+        // Safety: `shmem` outlives the reference.
+        let shmem_ref = unsafe { NaclShmemRef::new(&mut shmem) };
+        shmem_ref.set_scratch(1, 1);
+        let entry = NaclHfenceEntry::new(NaclHfenceType::Vvma, 0, 1, 2, 3, 4);
+        shmem_ref.set_hfence_entry(2, entry);
+        shmem_ref.set_csr(0x200, 5);
+        assert_eq!(shmem_ref.scratch(1), 1);
+        assert_eq!(shmem_ref.hfence_entry(2), entry);
+        assert!(shmem_ref.is_csr_dirty(0x200));
+
+        assert_eq!(word_at(&shmem, NaclShmem::scratch_offset(1)), 1);
+        let offset = NaclShmem::hfence_entry_offset(2);
+        assert_eq!(word_at(&shmem, offset), entry.config);
+        assert_eq!(word_at(&shmem, offset + 8), entry.page_num);
+        assert_eq!(word_at(&shmem, offset + 24), entry.page_count);
+        assert_eq!(word_at(&shmem, NaclShmem::csr_offset(0x200)), 5);
+        let (offset, bit) = NaclShmem::csr_dirty_offset(0x200);
+        assert_eq!(word_at(&shmem, offset), bit);
+        assert_eq!(NaclShmem::csr_offset(0xfff), NaclShmem::SIZE - 8);
+    }
+
+    #[test]
+    fn hfence_config() {
+        let entry = NaclHfenceEntry::new(NaclHfenceType::VvmaAsid, 9, 0x3fff, 0xffff, 0x1000, 4);
+        assert_eq!(entry.config, 0x8609_0000_3fff_ffff);
+        assert!(entry.is_pending());
+        assert_eq!(entry.hfence_type(), Ok(NaclHfenceType::VvmaAsid));
+        assert_eq!(entry.order(), 9);
+        assert_eq!(entry.vmid(), 0x3fff);
+        assert_eq!(entry.asid(), 0xffff);
+
+        // Out-of-range order and VMID bits are dropped rather than spilling into other fields.
+        let entry = NaclHfenceEntry::new(NaclHfenceType::Gvma, 0xff, 0xffff, 0, 0, 0);
+        assert_eq!(entry.config, 0x807f_0000_3fff_0000);
+
+        let reserved = NaclHfenceEntry {
+            config: 8 << 56,
+            ..Default::default()
+        };
+        assert!(!reserved.is_pending());
+        assert_eq!(reserved.hfence_type(), Err(Error::InvalidParam));
     }
 
-    fn a0(&self) -> u64 {
-        use NaclFunction::*;
-        match self {
-            ProbeFeature { feature_id } => *feature_id,
-            SetShmem { shmem_addr } => *shmem_addr,
+    #[test]
+    fn queue_hfence() {
+        let mut shmem = NaclShmem::default();
+        // Safety: `shmem` outlives the reference.
+        let shmem_ref = unsafe { NaclShmemRef::new(&mut shmem) };
+        let entry = NaclHfenceEntry::new(NaclHfenceType::GvmaAll, 0, 0, 0, 0, 0);
+        assert_eq!(shmem_ref.queue_hfence(entry), Some(0));
+        assert_eq!(shmem_ref.queue_hfence(entry), Some(1));
+        // Processed entries are reused.
+        shmem_ref.set_hfence_entry(0, NaclHfenceEntry::default());
+        assert_eq!(shmem_ref.queue_hfence(entry), Some(0));
+        for _ in 2..NACL_HFENCE_ENTRIES {
+            assert!(shmem_ref.queue_hfence(entry).is_some());
         }
+        assert_eq!(shmem_ref.queue_hfence(entry), None);
     }
 }
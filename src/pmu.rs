@@ -2,61 +2,116 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use static_assertions::const_assert;
+
 use crate::error::*;
 use crate::function::*;
 use ConfigFlagsValues::*;
 
 /// Functions for the Performance Monitoring Unit (PMU) extension
 /// Specific details can be found in the SBI documentation for the PMU extension.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
 pub enum PmuFunction {
     /// Returns the total number of performance counters (hardware and firmware).
+    #[sbi(fid = 0)]
     GetNumCounters,
     /// Returns information about hardware counter specified by the inner value.
-    GetCounterInfo(u64),
+    #[sbi(fid = 1)]
+    GetCounterInfo(#[sbi(reg = a0)] u64),
     /// Configures the counters selected by counter_index and counter_mask.
     /// See the sbi_pmu_counter_config_matching documentation for details.
+    #[sbi(fid = 2, check = Self::check_event_data)]
     ConfigureMatchingCounters {
         /// Counter index base.
+        #[sbi(reg = a0)]
         counter_index: u64,
         /// Counter index mask.
+        #[sbi(reg = a1)]
         counter_mask: u64,
         /// Counter configuration flags.
+        #[sbi(reg = a2)]
         config_flags: PmuCounterConfigFlags,
         /// Counter event type.
+        #[sbi(reg = a3)]
         event_type: PmuEventType,
         /// Counter event data.
+        #[sbi(reg = a4)]
         event_data: u64,
     },
     /// Starts the counters selected by counter_index and counter_mask.
     /// See the sbi_pmu_counter_start documentation for details.
+    #[sbi(fid = 3)]
     StartCounters {
         /// Counter index base.
+        #[sbi(reg = a0)]
         counter_index: u64,
         /// Counter index mask.
+        #[sbi(reg = a1)]
         counter_mask: u64,
         /// Counter start flags.
+        #[sbi(reg = a2)]
         start_flags: PmuCounterStartFlags,
         /// Counter initial value (used in conjunction with start_flags).
+        #[sbi(reg = a3)]
         initial_value: u64,
     },
     /// Stops the counters selected by counter_index and counter_mask.
     /// See the sbi_pmu_counter_stop documentation for details.
+    #[sbi(fid = 4)]
     StopCounters {
         /// Counter index base.
+        #[sbi(reg = a0)]
         counter_index: u64,
         /// Counter index mask.
+        #[sbi(reg = a1)]
         counter_mask: u64,
         /// Counter stop flags.
+        #[sbi(reg = a2)]
         stop_flags: PmuCounterStopFlags,
     },
     /// Returns the current value firmware counter specified by the inner value.
-    ReadFirmwareCounter(u64),
+    #[sbi(fid = 5)]
+    ReadFirmwareCounter(#[sbi(reg = a0)] u64),
+    /// Returns the upper 32 bits of the firmware counter specified by the inner value on RV32.
+    /// Always returns 0 on RV64.
+    #[sbi(fid = 6)]
+    ReadFirmwareCounterHi(#[sbi(reg = a0)] u64),
+    /// Registers the `PmuSnapshotArea` of the calling hart. Passing all-ones in both
+    /// `shmem_phys_lo` and `shmem_phys_hi` disables the snapshot area.
+    #[sbi(fid = 7)]
+    SnapshotSetShmem {
+        /// Lower XLEN bits of the physical address of the snapshot area.
+        #[sbi(reg = a0)]
+        shmem_phys_lo: u64,
+        /// Upper bits of the physical address of the snapshot area (RV32 only).
+        #[sbi(reg = a1)]
+        shmem_phys_hi: u64,
+        /// Reserved, must be 0.
+        #[sbi(reg = a2)]
+        flags: u64,
+    },
+    /// Queries whether each event in an array of `num_entries` `PmuEventInfo` entries is
+    /// supported, setting the output bit of each entry.
+    #[sbi(fid = 8)]
+    EventGetInfo {
+        /// Lower XLEN bits of the physical address of the entry array.
+        #[sbi(reg = a0)]
+        shmem_phys_lo: u64,
+        /// Upper bits of the physical address of the entry array (RV32 only).
+        #[sbi(reg = a1)]
+        shmem_phys_hi: u64,
+        /// Number of entries in the array.
+        #[sbi(reg = a2)]
+        num_entries: u64,
+        /// Reserved, must be 0.
+        #[sbi(reg = a3)]
+        flags: u64,
+    },
 }
 
 /// This encapsulates the bit-fields for PMU config_flags parameter as described in the SBI documentation
 /// for sbi_pmu_counter_config_matching
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 pub struct PmuCounterConfigFlags(u64);
 
 #[derive(Copy, Clone)]
@@ -184,16 +239,26 @@ impl PmuCounterConfigFlags {
     }
 }
 
+impl SbiReg for PmuCounterConfigFlags {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_raw_value(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.raw()
+    }
+}
+
 /// This encapsulates the bit-fields for PMU start_flags parameter as described in the SBI documentation
 /// for sbi_pmu_counter_start
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct PmuCounterStartFlags(u64);
 
 impl PmuCounterStartFlags {
     /// Constructs a new PmuCounterStartFlags from a valid passed-in value.
     pub fn from_raw_value(value: u64) -> Result<Self> {
         match value {
-            0 | 1 => Ok(PmuCounterStartFlags(value)),
+            0..=3 => Ok(PmuCounterStartFlags(value)),
             _ => Err(Error::InvalidParam),
         }
     }
@@ -212,18 +277,38 @@ impl PmuCounterStartFlags {
     pub fn is_init_value(&self) -> bool {
         self.0 & 1 != 0
     }
+
+    /// Sets the init_snapshot bit-flag (set initial counter values from the snapshot area).
+    pub fn set_init_snapshot(self) -> Self {
+        PmuCounterStartFlags(self.0 | (1 << 1))
+    }
+
+    /// Returns if the init_snapshot bit-flag is set.
+    pub fn is_init_snapshot(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+}
+
+impl SbiReg for PmuCounterStartFlags {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_raw_value(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.raw()
+    }
 }
 
 /// This encapsulates the bit-fields for PMU stop_flags parameter as described in the SBI documentation
 /// for sbi_pmu_counter_stop
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct PmuCounterStopFlags(u64);
 
 impl PmuCounterStopFlags {
     /// Constructs a new PmuCounterStopFlags from a valid passed-in value.
     pub fn from_raw_value(value: u64) -> Result<Self> {
         match value {
-            0 | 1 => Ok(PmuCounterStopFlags(value)),
+            0..=3 => Ok(PmuCounterStopFlags(value)),
             _ => Err(Error::InvalidParam),
         }
     }
@@ -242,6 +327,26 @@ impl PmuCounterStopFlags {
     pub fn is_reset_flag(&self) -> bool {
         self.0 & 1 != 0
     }
+
+    /// Sets the take_snapshot bit-flag (saves the counter values to the snapshot area).
+    pub fn set_take_snapshot(self) -> Self {
+        PmuCounterStopFlags(self.0 | (1 << 1))
+    }
+
+    /// Returns if the take_snapshot bit-flag is set.
+    pub fn is_take_snapshot(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+}
+
+impl SbiReg for PmuCounterStopFlags {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_raw_value(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.raw()
+    }
 }
 
 /// This encapsulates the counter information returned by the call to sbi_pmu_counter_get_info.
@@ -280,15 +385,19 @@ impl PmuCounterInfo {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 /// Enumeration of the event types.
 pub enum PmuEventType {
     /// Represents the hardware general events (type #0) in the SBI documentation.
     Hardware(PmuHardware),
     /// Represents the hardware cache events (type #1) in the SBI documentation.
     Cache(PmuHwCacheParams),
-    /// Represents a raw event (type #2) in the SBI documentation
+    /// Represents a raw event (type #2) in the SBI documentation. The raw event value is passed in
+    /// the lower 48 bits of `event_data`.
     RawEvent,
+    /// Represents a raw event v2 (type #3) in the SBI documentation. The raw event value is passed
+    /// in the lower 56 bits of `event_data`.
+    RawEventV2,
     /// Represents the firmware events (type #15) in the SBI documentation.
     Firmware(PmuFirmware),
 }
@@ -305,12 +414,14 @@ impl PmuEventType {
     pub fn raw(&self) -> u64 {
         const HARDWARE_CACHE_EVENT_TYPE: u64 = 1;
         const HARDWARE_RAW_EVENT_TYPE: u64 = 2;
+        const HARDWARE_RAW_EVENT_V2_TYPE: u64 = 3;
         const FIRMWARE_EVENT_TYPE: u64 = 0xF;
         use PmuEventType::*;
         match self {
             Hardware(p) => *p as u64,
             Cache(p) => p.raw() | (HARDWARE_CACHE_EVENT_TYPE << EVENT_TYPE_SHIFT),
             RawEvent => HARDWARE_RAW_EVENT_TYPE << EVENT_TYPE_SHIFT,
+            RawEventV2 => HARDWARE_RAW_EVENT_V2_TYPE << EVENT_TYPE_SHIFT,
             Firmware(p) => *p as u64 | (FIRMWARE_EVENT_TYPE << EVENT_TYPE_SHIFT),
         }
     }
@@ -327,16 +438,101 @@ impl PmuEventType {
                 value & EVENT_TYPE_INVERSE_MASK,
             )?)),
             2 if (value & EVENT_TYPE_INVERSE_MASK) == 0 => Ok(RawEvent),
+            3 if (value & EVENT_TYPE_INVERSE_MASK) == 0 => Ok(RawEventV2),
             0xF => Ok(Firmware(PmuFirmware::from_raw_value(
                 value & EVENT_TYPE_INVERSE_MASK,
             )?)),
             _ => Err(Error::InvalidParam),
         }
     }
+
+    /// Returns the mask of the bits of `event_data` that are valid for this event type, or
+    /// `u64::MAX` if `event_data` isn't restricted.
+    pub fn event_data_mask(&self) -> u64 {
+        use PmuEventType::*;
+        match self {
+            RawEvent => (1 << 48) - 1,
+            RawEventV2 => (1 << 56) - 1,
+            _ => u64::MAX,
+        }
+    }
+}
+
+impl SbiReg for PmuEventType {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_raw_value(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.raw()
+    }
+}
+
+/// An entry in the array passed to `EventGetInfo`, which the SBI spec requires to be 16-byte
+/// aligned.
+#[repr(C, align(16))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PmuEventInfo {
+    /// The encoded event type, as returned by `PmuEventType::raw()`.
+    pub event_idx: u32,
+    /// Output bits written by `EventGetInfo`. Bit 0 is set if the event is supported.
+    pub output: u32,
+    /// The event data, interpreted according to the event type.
+    pub event_data: u64,
+}
+
+impl PmuEventInfo {
+    /// Creates an entry querying support for `event_type` with `event_data`.
+    pub fn new(event_type: PmuEventType, event_data: u64) -> Self {
+        Self {
+            event_idx: event_type.raw() as u32,
+            output: 0,
+            event_data,
+        }
+    }
+
+    /// Returns if `EventGetInfo` reported the event as supported.
+    pub fn is_supported(&self) -> bool {
+        self.output & 1 != 0
+    }
+}
+
+/// Maximum number of counters in a `PmuSnapshotArea`.
+pub const PMU_SNAPSHOT_COUNTERS: usize = 64;
+
+/// Layout of the counter snapshot area registered with `SnapshotSetShmem`. Updated by
+/// `StopCounters` with the take_snapshot flag and read by `StartCounters` with the init_snapshot
+/// flag.
+#[repr(C, align(4096))]
+pub struct PmuSnapshotArea {
+    /// Bitmap of the counters that overflowed, relative to the counter_index of the last
+    /// `StopCounters` call.
+    pub counter_overflow_bitmap: u64,
+    /// Counter values, relative to the counter_index of the last `StopCounters` or
+    /// `StartCounters` call.
+    pub counter_values: [u64; PMU_SNAPSHOT_COUNTERS],
+    _reserved: [u64; 447],
+}
+
+impl Default for PmuSnapshotArea {
+    fn default() -> Self {
+        Self {
+            counter_overflow_bitmap: 0,
+            counter_values: [0; PMU_SNAPSHOT_COUNTERS],
+            _reserved: [0; 447],
+        }
+    }
+}
+
+fn _assert_pmu_layout() {
+    const_assert!(core::mem::size_of::<PmuEventInfo>() == 16);
+    const_assert!(core::mem::align_of::<PmuEventInfo>() == 16);
+    const_assert!(core::mem::size_of::<PmuSnapshotArea>() == 4096);
+    const_assert!(core::mem::offset_of!(PmuSnapshotArea, counter_values) == 8);
 }
 
 /// Enumeration of the hardware event types.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u64)]
 pub enum PmuHardware {
     /// Identifier for CPU cycle events.
@@ -382,7 +578,7 @@ impl PmuHardware {
 }
 
 /// Enumeration of cache event types (for use with PmuHardware of type CacheReferences/CacheMisses).
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u64)]
 pub enum PmuHwCache {
     /// Identifier for first level data cache.
@@ -419,7 +615,7 @@ impl PmuHwCache {
 }
 
 /// Enumeration of cache op_ids (for use with PmuHardware of type CacheReferences/CacheMisses).
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u64)]
 pub enum PmuHwCacheOpId {
     /// Identifier for a cache read op_id.
@@ -444,7 +640,7 @@ impl PmuHwCacheOpId {
 }
 
 /// Enumeration of results returned by cache counter reads.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u64)]
 pub enum PmuHwCacheResultId {
     /// Cache miss.
@@ -466,7 +662,7 @@ impl PmuHwCacheResultId {
 }
 
 /// Structure to encapsulate parameters for PmuHardware of type CacheReferences/CacheMisses).
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct PmuHwCacheParams {
     cache_id: PmuHwCache,
     op_id: PmuHwCacheOpId,
@@ -498,7 +694,7 @@ impl PmuHwCacheParams {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u64)]
 /// Enumeration of the firmware event types.
 pub enum PmuFirmware {
@@ -580,183 +776,50 @@ impl PmuFirmware {
 }
 
 impl PmuFunction {
-    /// Attempts to parse `Self` from the passed in `a0-a7`.
-    pub(crate) fn from_regs(args: &[u64]) -> Result<Self> {
-        use PmuFunction::*;
-        match args[6] {
-            0 => Ok(GetNumCounters),
-            1 => Ok(GetCounterInfo(args[0])),
-            2 => Ok(ConfigureMatchingCounters {
-                counter_index: args[0],
-                counter_mask: args[1],
-                config_flags: PmuCounterConfigFlags::from_raw_value(args[2])?,
-                event_type: PmuEventType::from_raw_value(args[3])?,
-                event_data: args[4],
-            }),
-            3 => Ok(StartCounters {
-                counter_index: args[0],
-                counter_mask: args[1],
-                start_flags: PmuCounterStartFlags::from_raw_value(args[2])?,
-                initial_value: args[3],
-            }),
-            4 => Ok(StopCounters {
-                counter_index: args[0],
-                counter_mask: args[1],
-                stop_flags: PmuCounterStopFlags::from_raw_value(args[2])?,
-            }),
-            5 => Ok(ReadFirmwareCounter(args[0])),
-            _ => Err(Error::NotSupported),
-        }
-    }
-}
-
-impl SbiFunction for PmuFunction {
-    fn a6(&self) -> u64 {
-        use PmuFunction::*;
+    // Rejects `event_data` with bits set outside of those valid for the event type.
+    fn check_event_data(&self) -> Result<()> {
         match self {
-            GetNumCounters => 0,
-            GetCounterInfo(_) => 1,
-            ConfigureMatchingCounters {
-                counter_index: _,
-                counter_mask: _,
-                config_flags: _,
-                event_type: _,
-                event_data: _,
-            } => 2,
-            StartCounters {
-                counter_index: _,
-                counter_mask: _,
-                start_flags: _,
-                initial_value: _,
-            } => 3,
-            StopCounters {
-                counter_index: _,
-                counter_mask: _,
-                stop_flags: _,
-            } => 4,
-            ReadFirmwareCounter(_) => 5,
-        }
-    }
-
-    fn a5(&self) -> u64 {
-        0
-    }
-
-    fn a4(&self) -> u64 {
-        use PmuFunction::*;
-        match self {
-            ConfigureMatchingCounters {
-                counter_index: _,
-                counter_mask: _,
-                config_flags: _,
-                event_type: _,
-                event_data,
-            } => *event_data,
-            _ => 0,
-        }
-    }
-
-    fn a3(&self) -> u64 {
-        use PmuFunction::*;
-        match self {
-            ConfigureMatchingCounters {
-                counter_index: _,
-                counter_mask: _,
-                config_flags: _,
+            PmuFunction::ConfigureMatchingCounters {
                 event_type,
-                event_data: _,
-            } => event_type.raw(),
-            StartCounters {
-                counter_index: _,
-                counter_mask: _,
-                start_flags: _,
-                initial_value,
-            } => *initial_value,
-            _ => 0,
+                event_data,
+                ..
+            } if event_data & !event_type.event_data_mask() != 0 => Err(Error::InvalidParam),
+            _ => Ok(()),
         }
     }
+}
 
-    fn a2(&self) -> u64 {
-        use PmuFunction::*;
-        match self {
-            ConfigureMatchingCounters {
-                counter_index: _,
-                counter_mask: _,
-                config_flags,
-                event_type: _,
-                event_data: _,
-            } => config_flags.raw(),
-            StartCounters {
-                counter_index: _,
-                counter_mask: _,
-                start_flags,
-                initial_value: _,
-            } => start_flags.raw(),
-            StopCounters {
-                counter_index: _,
-                counter_mask: _,
-                stop_flags,
-            } => stop_flags.raw(),
-            _ => 0,
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn a1(&self) -> u64 {
-        use PmuFunction::*;
-        match self {
-            ConfigureMatchingCounters {
-                counter_index: _,
-                counter_mask,
-                config_flags: _,
-                event_type: _,
-                event_data: _,
-            } => *counter_mask,
-            StartCounters {
-                counter_index: _,
-                counter_mask,
-                start_flags: _,
-                initial_value: _,
-            } => *counter_mask,
-            StopCounters {
-                counter_index: _,
-                counter_mask,
-                stop_flags: _,
-            } => *counter_mask,
-            _ => 0,
-        }
+    // Decodes a ConfigureMatchingCounters call for `event_type` with `event_data`.
+    fn configure(event_type: PmuEventType, event_data: u64) -> Result<PmuFunction> {
+        PmuFunction::from_regs(&[0, 1, 0, event_type.raw(), event_data, 0, 2, 0])
     }
 
-    fn a0(&self) -> u64 {
-        use PmuFunction::*;
-        match self {
-            GetCounterInfo(counter_index) => *counter_index,
-            ConfigureMatchingCounters {
-                counter_index,
-                counter_mask: _,
-                config_flags: _,
-                event_type: _,
-                event_data: _,
-            } => *counter_index,
-            StartCounters {
-                counter_index,
-                counter_mask: _,
-                start_flags: _,
-                initial_value: _,
-            } => *counter_index,
-            StopCounters {
-                counter_index,
-                counter_mask: _,
-                stop_flags: _,
-            } => *counter_index,
-            ReadFirmwareCounter(counter_index) => *counter_index,
-            _ => 0,
+    #[test]
+    fn raw_event_data_mask() {
+        for event_type in [PmuEventType::RawEvent, PmuEventType::RawEventV2] {
+            assert_eq!(
+                PmuEventType::from_raw_value(event_type.raw()),
+                Ok(event_type)
+            );
         }
-    }
+        assert_eq!(PmuEventType::RawEventV2.raw(), 0x3_0000);
 
-    fn result(&self, a0: u64, a1: u64) -> Result<u64> {
-        match a0 {
-            0 => Ok(a1),
-            e => Err(Error::from_code(e as i64)),
-        }
+        assert!(configure(PmuEventType::RawEvent, (1 << 48) - 1).is_ok());
+        assert_eq!(
+            configure(PmuEventType::RawEvent, 1 << 48),
+            Err(Error::InvalidParam)
+        );
+        assert!(configure(PmuEventType::RawEventV2, (1 << 56) - 1).is_ok());
+        assert_eq!(
+            configure(PmuEventType::RawEventV2, 1 << 56),
+            Err(Error::InvalidParam)
+        );
+        // Other event types don't restrict the event data.
+        let cycles = PmuEventType::Hardware(PmuHardware::CpuCycles);
+        assert!(configure(cycles, u64::MAX).is_ok());
     }
 }
@@ -6,13 +6,16 @@ use crate::error::*;
 use crate::function::*;
 
 /// Functions for the Reset extension
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
 pub enum ResetFunction {
     /// Performs a system reset.
+    #[sbi(fid = 0)]
     Reset {
         /// Determines the type of reset to perform.
+        #[sbi(reg = a0)]
         reset_type: ResetType,
         /// Represents the reason for system reset.
+        #[sbi(reg = a1)]
         reason: ResetReason,
     },
 }
@@ -43,6 +46,16 @@ impl ResetType {
     }
 }
 
+impl SbiReg for ResetType {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_reg(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        *self as u64
+    }
+}
+
 /// Reasons why a supervisor requests a reset.
 #[repr(u64)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -66,20 +79,17 @@ impl ResetReason {
     }
 }
 
-impl ResetFunction {
-    /// Attempts to parse `Self` from the passed in `a0-a7`.
-    pub(crate) fn from_regs(args: &[u64]) -> Result<Self> {
-        use ResetFunction::*;
-
-        Ok(match args[6] {
-            0 => Reset {
-                reset_type: ResetType::from_reg(args[0])?,
-                reason: ResetReason::from_reg(args[1])?,
-            },
-            _ => return Err(Error::NotSupported),
-        })
+impl SbiReg for ResetReason {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_reg(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        *self as u64
     }
+}
 
+impl ResetFunction {
     /// Creates an operation to shutdown the machine.
     pub fn shutdown() -> Self {
         ResetFunction::Reset {
@@ -89,26 +99,6 @@ impl ResetFunction {
     }
 }
 
-impl SbiFunction for ResetFunction {
-    fn a0(&self) -> u64 {
-        match self {
-            ResetFunction::Reset {
-                reset_type,
-                reason: _,
-            } => *reset_type as u64,
-        }
-    }
-
-    fn a1(&self) -> u64 {
-        match self {
-            ResetFunction::Reset {
-                reset_type: _,
-                reason,
-            } => *reason as u64,
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,240 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use core::ops::Range;
+
+use crate::error::*;
+use crate::function::*;
+use crate::HartMask;
+
+/// A range of addresses to be fenced by one of the remote fence functions.
+///
+/// A `start_addr` and `size` of 0, or a `size` of -1 (`u64::MAX`), select the entire address space.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FenceRange {
+    start_addr: u64,
+    size: u64,
+}
+
+impl FenceRange {
+    /// Creates a `FenceRange` covering `size` bytes starting at `start_addr`. Returns an error if
+    /// the range wraps around the end of the address space.
+    pub fn new(start_addr: u64, size: u64) -> Result<Self> {
+        let range = Self { start_addr, size };
+        if !range.is_all() && start_addr.checked_add(size).is_none() {
+            return Err(Error::InvalidParam);
+        }
+        Ok(range)
+    }
+
+    /// Returns a `FenceRange` covering the entire address space.
+    pub fn all() -> Self {
+        Self {
+            start_addr: 0,
+            size: u64::MAX,
+        }
+    }
+
+    /// Returns the start address of the range.
+    pub fn start_addr(&self) -> u64 {
+        self.start_addr
+    }
+
+    /// Returns the size of the range in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns true if this range covers the entire address space.
+    pub fn is_all(&self) -> bool {
+        self.size == u64::MAX || (self.start_addr == 0 && self.size == 0)
+    }
+}
+
+impl SbiRegs<2> for FenceRange {
+    fn from_regs(regs: [u64; 2]) -> Result<Self> {
+        Self::new(regs[0], regs[1])
+    }
+
+    fn to_regs(&self) -> [u64; 2] {
+        [self.start_addr, self.size]
+    }
+}
+
+impl TryFrom<Range<u64>> for FenceRange {
+    type Error = Error;
+
+    fn try_from(range: Range<u64>) -> Result<Self> {
+        if range.end < range.start {
+            return Err(Error::InvalidParam);
+        }
+        Self::new(range.start, range.end - range.start)
+    }
+}
+
+/// Functions for the Remote Fence (RFENCE) extension. All functions take a set of harts to fence
+/// in `hart_mask` (a0) and `hart_mask_base` (a1).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
+pub enum RfenceFunction {
+    /// Instructs the remote harts to execute a FENCE.I instruction.
+    ///
+    /// a6 = 0
+    #[sbi(fid = 0)]
+    RemoteFenceI {
+        /// a0, a1 = harts to fence
+        #[sbi(regs(a0, a1))]
+        hart_mask: HartMask,
+    },
+    /// Instructs the remote harts to execute one or more SFENCE.VMA instructions covering the
+    /// range of virtual addresses in `range`.
+    ///
+    /// a6 = 1
+    #[sbi(fid = 1)]
+    RemoteSfenceVma {
+        /// a0, a1 = harts to fence
+        #[sbi(regs(a0, a1))]
+        hart_mask: HartMask,
+        /// a2, a3 = virtual address range to fence
+        #[sbi(regs(a2, a3))]
+        range: FenceRange,
+    },
+    /// Instructs the remote harts to execute one or more SFENCE.VMA instructions covering the
+    /// range of virtual addresses in `range`, for the given ASID only.
+    ///
+    /// a6 = 2
+    #[sbi(fid = 2)]
+    RemoteSfenceVmaAsid {
+        /// a0, a1 = harts to fence
+        #[sbi(regs(a0, a1))]
+        hart_mask: HartMask,
+        /// a2, a3 = virtual address range to fence
+        #[sbi(regs(a2, a3))]
+        range: FenceRange,
+        /// a4 = address space ID
+        #[sbi(reg = a4)]
+        asid: u64,
+    },
+    /// Instructs the remote harts to execute one or more HFENCE.GVMA instructions covering the
+    /// range of guest physical addresses in `range`, for the given VMID only.
+    ///
+    /// a6 = 3
+    #[sbi(fid = 3)]
+    RemoteHfenceGvmaVmid {
+        /// a0, a1 = harts to fence
+        #[sbi(regs(a0, a1))]
+        hart_mask: HartMask,
+        /// a2, a3 = guest physical address range to fence
+        #[sbi(regs(a2, a3))]
+        range: FenceRange,
+        /// a4 = virtual machine ID
+        #[sbi(reg = a4)]
+        vmid: u64,
+    },
+    /// Instructs the remote harts to execute one or more HFENCE.GVMA instructions covering the
+    /// range of guest physical addresses in `range`, for all guests.
+    ///
+    /// a6 = 4
+    #[sbi(fid = 4)]
+    RemoteHfenceGvma {
+        /// a0, a1 = harts to fence
+        #[sbi(regs(a0, a1))]
+        hart_mask: HartMask,
+        /// a2, a3 = guest physical address range to fence
+        #[sbi(regs(a2, a3))]
+        range: FenceRange,
+    },
+    /// Instructs the remote harts to execute one or more HFENCE.VVMA instructions covering the
+    /// range of guest virtual addresses in `range`, for the given ASID and the VMID in the
+    /// current `hgatp`.
+    ///
+    /// a6 = 5
+    #[sbi(fid = 5)]
+    RemoteHfenceVvmaAsid {
+        /// a0, a1 = harts to fence
+        #[sbi(regs(a0, a1))]
+        hart_mask: HartMask,
+        /// a2, a3 = guest virtual address range to fence
+        #[sbi(regs(a2, a3))]
+        range: FenceRange,
+        /// a4 = address space ID
+        #[sbi(reg = a4)]
+        asid: u64,
+    },
+    /// Instructs the remote harts to execute one or more HFENCE.VVMA instructions covering the
+    /// range of guest virtual addresses in `range`, for the VMID in the current `hgatp`.
+    ///
+    /// a6 = 6
+    #[sbi(fid = 6)]
+    RemoteHfenceVvma {
+        /// a0, a1 = harts to fence
+        #[sbi(regs(a0, a1))]
+        hart_mask: HartMask,
+        /// a2, a3 = guest virtual address range to fence
+        #[sbi(regs(a2, a3))]
+        range: FenceRange,
+    },
+}
+
+impl RfenceFunction {
+    /// Returns the set of harts targeted by this fence.
+    pub fn hart_mask(&self) -> HartMask {
+        use RfenceFunction::*;
+        match self {
+            RemoteFenceI { hart_mask }
+            | RemoteSfenceVma { hart_mask, .. }
+            | RemoteSfenceVmaAsid { hart_mask, .. }
+            | RemoteHfenceGvmaVmid { hart_mask, .. }
+            | RemoteHfenceGvma { hart_mask, .. }
+            | RemoteHfenceVvmaAsid { hart_mask, .. }
+            | RemoteHfenceVvma { hart_mask, .. } => *hart_mask,
+        }
+    }
+
+    /// Returns the address range covered by this fence, if any.
+    pub fn range(&self) -> Option<FenceRange> {
+        use RfenceFunction::*;
+        match self {
+            RemoteFenceI { .. } => None,
+            RemoteSfenceVma { range, .. }
+            | RemoteSfenceVmaAsid { range, .. }
+            | RemoteHfenceGvmaVmid { range, .. }
+            | RemoteHfenceGvma { range, .. }
+            | RemoteHfenceVvmaAsid { range, .. }
+            | RemoteHfenceVvma { range, .. } => Some(*range),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_address_space() {
+        assert!(FenceRange::new(0, 0).unwrap().is_all());
+        assert!(FenceRange::new(0x8000_0000, u64::MAX).unwrap().is_all());
+        assert!(FenceRange::all().is_all());
+        assert!(!FenceRange::new(0x1000, 0x1000).unwrap().is_all());
+    }
+
+    #[test]
+    fn wrapping_range_rejected() {
+        let args = [0, u64::MAX, 0xffff_ffff_ffff_f000, 0x2000, 0, 0, 1, 0];
+        assert_eq!(
+            RfenceFunction::from_regs(&args).unwrap_err(),
+            Error::InvalidParam
+        );
+    }
+
+    #[test]
+    fn sfence_vma_asid() {
+        let args = [0b101, 4, 0x1000, 0x3000, 7, 0, 2, 0];
+        let f = RfenceFunction::from_regs(&args).unwrap();
+        assert_eq!(f.hart_mask(), HartMask::new(0b101, 4));
+        assert_eq!(f.a2(), 0x1000);
+        assert_eq!(f.a3(), 0x3000);
+        assert_eq!(f.a4(), 7);
+        assert_eq!(f.a6(), 2);
+    }
+}
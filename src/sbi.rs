@@ -7,6 +7,13 @@
 //! For each extension, a function enum is defined to contain the SBI function data.
 #![no_std]
 
+#[cfg(any(test, feature = "test-utils"))]
+extern crate std;
+
+// Lets `#[derive(SbiFunction)]` refer to this crate as `::sbi_rs` from within it.
+extern crate self as sbi_rs;
+
+mod bytes;
 mod consts;
 pub use consts::*;
 mod debug_console;
@@ -16,12 +23,18 @@ pub mod error;
 pub use error::*;
 mod function;
 pub use function::*;
+// Support for vendor-specific SBI extensions
+mod vendor;
+pub use vendor::*;
 // The Attestation SBI extension
 mod attestation;
 pub use attestation::*;
 // The Base SBI extension
 mod base;
 pub use base::*;
+// The legacy (v0.1) SBI extensions
+mod legacy;
+pub use legacy::*;
 // The Nested Virtualization Acceleration (NACL) SBI extension
 mod nacl;
 pub use nacl::*;
@@ -31,6 +44,21 @@ pub use reset::*;
 // The State SBI extension
 mod state;
 pub use state::*;
+// The Timer SBI extension
+mod time;
+pub use time::*;
+// The IPI SBI extension
+mod ipi;
+pub use ipi::*;
+// Hart masks used by the IPI and RFENCE extensions
+mod hart_mask;
+pub use hart_mask::*;
+// The Remote Fence SBI extension
+mod rfence;
+pub use rfence::*;
+// The System Suspend SBI extension
+mod susp;
+pub use susp::*;
 // The COVE host SBI extension
 mod cove_host;
 pub use cove_host::*;
@@ -43,6 +71,35 @@ pub use cove_guest::*;
 // The PMU SBI extension
 mod pmu;
 pub use pmu::*;
+// The CPPC SBI extension
+mod cppc;
+pub use cppc::*;
+// The Steal-Time Accounting SBI extension
+mod sta;
+pub use sta::*;
+// The Firmware Features SBI extension
+mod fwft;
+pub use fwft::*;
+// The Debug Triggers SBI extension
+mod dbtr;
+pub use dbtr::*;
+// The Supervisor Software Events SBI extension
+mod sse;
+pub use sse::*;
+// The Message Proxy SBI extension
+mod mpxy;
+pub use mpxy::*;
+
+// The transport SBI calls are made through
+mod transport;
+pub use transport::*;
+// Access to guest memory for SBI implementations
+mod guest_memory;
+pub use guest_memory::{read_bytes, GuestMemory};
+
+/// A mock SBI implementation for host tests.
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock;
 
 /// Salus SBI Vendor Extensions.
 pub mod salus;
@@ -50,8 +107,8 @@ pub mod salus;
 /// Interfaces for invoking SBI functionality.
 pub mod api;
 
-#[cfg(all(target_arch = "riscv64", target_os = "none"))]
-use core::arch::asm;
+/// Dispatching of SBI calls to extension handlers, for SBI implementations.
+pub mod server;
 
 /// The values returned from an SBI function call.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -84,7 +141,7 @@ impl From<Result<i64>> for SbiReturn {
 impl From<Error> for SbiReturn {
     fn from(error: Error) -> SbiReturn {
         SbiReturn {
-            error_code: error as i64,
+            error_code: error.code(),
             return_value: 0,
         }
     }
@@ -136,17 +193,68 @@ pub enum SbiReturnType {
     Standard(SbiReturn),
 }
 
+/// A call to an experimental extension, holding its a0-a7 register values. Created with
+/// `SbiMessage::experimental`, which checks that the EID in a7 is in the experimental range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExperimentalCall([u64; 8]);
+
+impl ExperimentalCall {
+    /// Returns the a0-a7 register values, where a7 is the EID.
+    pub fn regs(&self) -> &[u64; 8] {
+        &self.0
+    }
+}
+
+/// A call to a firmware-specific extension, holding the ID of the SBI implementation that defines
+/// it and its a0-a7 register values. Created with `SbiMessage::firmware_specific`, which checks
+/// that the EID in a7 is in the firmware-specific range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FirmwareSpecificCall {
+    impl_id: u64,
+    regs: [u64; 8],
+}
+
+impl FirmwareSpecificCall {
+    /// Returns the ID of the SBI implementation that defines this extension.
+    pub fn impl_id(&self) -> u64 {
+        self.impl_id
+    }
+
+    /// Returns the a0-a7 register values, where a7 is the EID.
+    pub fn regs(&self) -> &[u64; 8] {
+        &self.regs
+    }
+}
+
+// How the registers of an `SbiMessage` are encoded, returned by `SbiMessage::encoding`.
+enum Encoding<'a> {
+    // The registers are those of a function of one of the extensions defined in this crate.
+    Function(&'a dyn SbiFunction),
+    // The message holds its a0-a7 register values.
+    Raw([u64; 8]),
+}
+
 /// SBI Message used to invoke the specified SBI extension in the firmware.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SbiMessage {
     /// The base SBI extension functions.
     Base(BaseFunction),
     /// The legacy PutChar extension.
     PutChar(u64),
+    /// The remaining legacy (v0.1) extensions.
+    Legacy(LegacyFunction),
     /// The extension for getting/setting the state of CPUs.
     HartState(StateFunction),
+    /// Programs the supervisor timer.
+    Time(TimeFunction),
+    /// Sends inter-processor interrupts.
+    Ipi(IpiFunction),
+    /// Requests remote TLB and instruction cache fences.
+    Rfence(RfenceFunction),
     /// Handles system reset.
     Reset(ResetFunction),
+    /// Handles system suspend.
+    Suspend(SuspendFunction),
     /// Handles output to the console for debug.
     DebugConsole(DebugConsoleFunction),
     /// Provides functions for accelerating nested virtualization.
@@ -161,20 +269,48 @@ pub enum SbiMessage {
     Attestation(AttestationFunction),
     /// The extension for getting performance counter state.
     Pmu(PmuFunction),
+    /// The extension for collaborative processor performance control.
+    Cppc(CppcFunction),
+    /// The extension for steal-time accounting.
+    Sta(StaFunction),
+    /// The extension for getting/setting firmware features.
+    Fwft(FwftFunction),
+    /// The extension for installing debug triggers.
+    Dbtr(DbtrFunction),
+    /// The extension for handling supervisor software events.
+    Sse(SseFunction),
+    /// The extension for proxying messages to platform services.
+    Mpxy(MpxyFunction),
     /// Vendor extensions.
     Vendor([u64; 8]),
+    /// Experimental extensions, with an EID in `EXT_EXPERIMENTAL_RANGE_START..=END`.
+    Experimental(ExperimentalCall),
+    /// Firmware-specific extensions, with an EID in `EXT_FIRMWARE_RANGE_START..=END`. The meaning
+    /// of these depends on the SBI implementation, identified by the value `impl_id` returned by
+    /// `GetImplementationID`.
+    FirmwareSpecific(FirmwareSpecificCall),
 }
 
 impl SbiMessage {
     /// Creates an SbiMessage struct from the given GPRs. Intended for use from the ECALL handler
     /// and passed the saved register state from the calling OS. A7 must contain a valid SBI
     /// extension and the other A* registers will be interpreted based on the extension A7 selects.
+    ///
+    /// Firmware-specific extensions aren't recognized since their meaning depends on the SBI
+    /// implementation; use `from_regs_for_impl` to parse them.
     pub fn from_regs(args: &[u64]) -> Result<Self> {
         match args[7] {
             EXT_PUT_CHAR => Ok(SbiMessage::PutChar(args[0])),
+            EXT_LEGACY_SET_TIMER | EXT_LEGACY_GET_CHAR..=EXT_LEGACY_SHUTDOWN => {
+                LegacyFunction::from_regs(args).map(SbiMessage::Legacy)
+            }
             EXT_BASE => BaseFunction::from_regs(args).map(SbiMessage::Base),
             EXT_HART_STATE => StateFunction::from_regs(args).map(SbiMessage::HartState),
+            EXT_TIME => TimeFunction::from_regs(args).map(SbiMessage::Time),
+            EXT_IPI => IpiFunction::from_regs(args).map(SbiMessage::Ipi),
+            EXT_RFENCE => RfenceFunction::from_regs(args).map(SbiMessage::Rfence),
             EXT_RESET => ResetFunction::from_regs(args).map(SbiMessage::Reset),
+            EXT_SUSP => SuspendFunction::from_regs(args).map(SbiMessage::Suspend),
             EXT_DBCN => DebugConsoleFunction::from_regs(args).map(SbiMessage::DebugConsole),
             EXT_NACL => NaclFunction::from_regs(args).map(SbiMessage::Nacl),
             EXT_COVE_HOST => CoveHostFunction::from_regs(args).map(SbiMessage::CoveHost),
@@ -184,21 +320,78 @@ impl SbiMessage {
             EXT_COVE_GUEST => CoveGuestFunction::from_regs(args).map(SbiMessage::CoveGuest),
             EXT_ATTESTATION => AttestationFunction::from_regs(args).map(SbiMessage::Attestation),
             EXT_PMU => PmuFunction::from_regs(args).map(SbiMessage::Pmu),
+            EXT_CPPC => CppcFunction::from_regs(args).map(SbiMessage::Cppc),
+            EXT_STA => StaFunction::from_regs(args).map(SbiMessage::Sta),
+            EXT_FWFT => FwftFunction::from_regs(args).map(SbiMessage::Fwft),
+            EXT_DBTR => DbtrFunction::from_regs(args).map(SbiMessage::Dbtr),
+            EXT_SSE => SseFunction::from_regs(args).map(SbiMessage::Sse),
+            EXT_MPXY => MpxyFunction::from_regs(args).map(SbiMessage::Mpxy),
             EXT_VENDOR_RANGE_START..=EXT_VENDOR_RANGE_END => Ok(SbiMessage::Vendor(
                 args.try_into().map_err(|_| Error::Failed)?,
             )),
+            EXT_EXPERIMENTAL_RANGE_START..=EXT_EXPERIMENTAL_RANGE_END => {
+                Ok(SbiMessage::Experimental(ExperimentalCall(
+                    args.try_into().map_err(|_| Error::Failed)?,
+                )))
+            }
             _ => Err(Error::NotSupported),
         }
     }
 
+    /// Like `from_regs`, but also parses firmware-specific extensions as those of the SBI
+    /// implementation with ID `impl_id`. Intended for use from the ECALL handler of that
+    /// implementation.
+    pub fn from_regs_for_impl(args: &[u64], impl_id: u64) -> Result<Self> {
+        match args[7] {
+            EXT_FIRMWARE_RANGE_START..=EXT_FIRMWARE_RANGE_END => {
+                Ok(SbiMessage::FirmwareSpecific(FirmwareSpecificCall {
+                    impl_id,
+                    regs: args.try_into().map_err(|_| Error::Failed)?,
+                }))
+            }
+            _ => Self::from_regs(args),
+        }
+    }
+
+    /// Creates a message for an experimental extension from its a0-a7 register values. Returns
+    /// `InvalidParam` if the EID in `regs[7]` isn't in the experimental range.
+    pub fn experimental(regs: [u64; 8]) -> Result<Self> {
+        match regs[7] {
+            EXT_EXPERIMENTAL_RANGE_START..=EXT_EXPERIMENTAL_RANGE_END => {
+                Ok(SbiMessage::Experimental(ExperimentalCall(regs)))
+            }
+            _ => Err(Error::InvalidParam),
+        }
+    }
+
+    /// Creates a message for a firmware-specific extension of the SBI implementation with ID
+    /// `impl_id` from its a0-a7 register values. Returns `InvalidParam` if the EID in `regs[7]`
+    /// isn't in the firmware-specific range.
+    pub fn firmware_specific(impl_id: u64, regs: [u64; 8]) -> Result<Self> {
+        match regs[7] {
+            EXT_FIRMWARE_RANGE_START..=EXT_FIRMWARE_RANGE_END => {
+                Ok(SbiMessage::FirmwareSpecific(FirmwareSpecificCall {
+                    impl_id,
+                    regs,
+                }))
+            }
+            _ => Err(Error::InvalidParam),
+        }
+    }
+
     /// Returns the register value for this `SbiMessage`.
     pub fn a7(&self) -> u64 {
         use SbiMessage::*;
         match self {
             PutChar(_) => EXT_PUT_CHAR,
+            Legacy(f) => f.eid(),
             Base(_) => EXT_BASE,
             HartState(_) => EXT_HART_STATE,
+            Time(_) => EXT_TIME,
+            Ipi(_) => EXT_IPI,
+            Rfence(_) => EXT_RFENCE,
             Reset(_) => EXT_RESET,
+            Suspend(_) => EXT_SUSP,
             DebugConsole(_) => EXT_DBCN,
             Nacl(_) => EXT_NACL,
             CoveHost(_) => EXT_COVE_HOST,
@@ -206,145 +399,122 @@ impl SbiMessage {
             CoveGuest(_) => EXT_COVE_GUEST,
             Attestation(_) => EXT_ATTESTATION,
             Pmu(_) => EXT_PMU,
+            Cppc(_) => EXT_CPPC,
+            Sta(_) => EXT_STA,
+            Fwft(_) => EXT_FWFT,
+            Dbtr(_) => EXT_DBTR,
+            Sse(_) => EXT_SSE,
+            Mpxy(_) => EXT_MPXY,
             Vendor(regs) => regs[7],
+            Experimental(call) => call.0[7],
+            FirmwareSpecific(call) => call.regs[7],
         }
     }
 
-    // TODO: Consider using enum_dispatch to avoid the repetition.
+    // Returns how the a0-a6 registers of this message are encoded: by the `SbiFunction` of its
+    // extension, or as raw register values.
+    fn encoding(&self) -> Encoding<'_> {
+        use SbiMessage::*;
+        Encoding::Function(match self {
+            Base(f) => f,
+            Legacy(f) => f,
+            HartState(f) => f,
+            Time(f) => f,
+            Ipi(f) => f,
+            Rfence(f) => f,
+            Reset(f) => f,
+            Suspend(f) => f,
+            DebugConsole(f) => f,
+            Nacl(f) => f,
+            CoveHost(f) => f,
+            CoveInterrupt(f) => f,
+            CoveGuest(f) => f,
+            Attestation(f) => f,
+            Pmu(f) => f,
+            Cppc(f) => f,
+            Sta(f) => f,
+            Fwft(f) => f,
+            Dbtr(f) => f,
+            Sse(f) => f,
+            Mpxy(f) => f,
+            PutChar(c) => return Encoding::Raw([*c, 0, 0, 0, 0, 0, 0, EXT_PUT_CHAR]),
+            Vendor(regs) => return Encoding::Raw(*regs),
+            Experimental(call) => return Encoding::Raw(call.0),
+            FirmwareSpecific(call) => return Encoding::Raw(call.regs),
+        })
+    }
 
     /// Returns the register value for this `SbiMessage`.
     pub fn a6(&self) -> u64 {
-        use SbiMessage::*;
-        match self {
-            PutChar(_) => 0,
-            Base(f) => f.a6(),
-            HartState(f) => f.a6(),
-            Reset(f) => f.a6(),
-            DebugConsole(f) => f.a6(),
-            Nacl(f) => f.a6(),
-            CoveHost(f) => f.a6(),
-            CoveInterrupt(f) => f.a6(),
-            CoveGuest(f) => f.a6(),
-            Attestation(f) => f.a6(),
-            Pmu(f) => f.a6(),
-            Vendor(regs) => regs[6],
+        match self.encoding() {
+            Encoding::Function(f) => f.a6(),
+            Encoding::Raw(regs) => regs[6],
         }
     }
 
     /// Returns the register value for this `SbiMessage`.
     pub fn a5(&self) -> u64 {
-        use SbiMessage::*;
-        match self {
-            PutChar(_) => 0,
-            Base(f) => f.a5(),
-            HartState(f) => f.a5(),
-            Reset(f) => f.a5(),
-            DebugConsole(f) => f.a5(),
-            Nacl(f) => f.a5(),
-            CoveHost(f) => f.a5(),
-            CoveInterrupt(f) => f.a5(),
-            CoveGuest(f) => f.a5(),
-            Attestation(f) => f.a5(),
-            Pmu(f) => f.a5(),
-            Vendor(regs) => regs[5],
+        match self.encoding() {
+            Encoding::Function(f) => f.a5(),
+            Encoding::Raw(regs) => regs[5],
         }
     }
 
     /// Returns the register value for this `SbiMessage`.
     pub fn a4(&self) -> u64 {
-        use SbiMessage::*;
-        match self {
-            PutChar(_) => 0,
-            Base(f) => f.a4(),
-            HartState(f) => f.a4(),
-            Reset(f) => f.a4(),
-            DebugConsole(f) => f.a4(),
-            Nacl(f) => f.a4(),
-            CoveHost(f) => f.a4(),
-            CoveInterrupt(f) => f.a4(),
-            CoveGuest(f) => f.a4(),
-            Attestation(f) => f.a4(),
-            Pmu(f) => f.a4(),
-            Vendor(regs) => regs[4],
+        match self.encoding() {
+            Encoding::Function(f) => f.a4(),
+            Encoding::Raw(regs) => regs[4],
         }
     }
 
     /// Returns the register value for this `SbiMessage`.
     pub fn a3(&self) -> u64 {
-        use SbiMessage::*;
-        match self {
-            PutChar(_) => 0,
-            Base(f) => f.a3(),
-            HartState(f) => f.a3(),
-            Reset(f) => f.a3(),
-            DebugConsole(f) => f.a3(),
-            Nacl(f) => f.a3(),
-            CoveHost(f) => f.a3(),
-            CoveInterrupt(f) => f.a3(),
-            CoveGuest(f) => f.a3(),
-            Attestation(f) => f.a3(),
-            Pmu(f) => f.a3(),
-            Vendor(regs) => regs[3],
+        match self.encoding() {
+            Encoding::Function(f) => f.a3(),
+            Encoding::Raw(regs) => regs[3],
         }
     }
 
     /// Returns the register value for this `SbiMessage`.
     pub fn a2(&self) -> u64 {
-        use SbiMessage::*;
-        match self {
-            PutChar(_) => 0,
-            Base(f) => f.a2(),
-            HartState(f) => f.a2(),
-            Reset(f) => f.a2(),
-            DebugConsole(f) => f.a2(),
-            Nacl(f) => f.a2(),
-            CoveHost(f) => f.a2(),
-            CoveInterrupt(f) => f.a2(),
-            CoveGuest(f) => f.a2(),
-            Attestation(f) => f.a2(),
-            Pmu(f) => f.a2(),
-            Vendor(regs) => regs[2],
+        match self.encoding() {
+            Encoding::Function(f) => f.a2(),
+            Encoding::Raw(regs) => regs[2],
         }
     }
 
     /// Returns the register value for this `SbiMessage`.
     pub fn a1(&self) -> u64 {
-        use SbiMessage::*;
-        match self {
-            PutChar(_) => 0,
-            Base(f) => f.a1(),
-            HartState(f) => f.a1(),
-            Reset(f) => f.a1(),
-            DebugConsole(f) => f.a1(),
-            Nacl(f) => f.a1(),
-            CoveHost(f) => f.a1(),
-            CoveInterrupt(f) => f.a1(),
-            CoveGuest(f) => f.a1(),
-            Attestation(f) => f.a1(),
-            Pmu(f) => f.a1(),
-            Vendor(regs) => regs[1],
+        match self.encoding() {
+            Encoding::Function(f) => f.a1(),
+            Encoding::Raw(regs) => regs[1],
         }
     }
 
     /// Returns the register value for this `SbiMessage`.
     pub fn a0(&self) -> u64 {
-        use SbiMessage::*;
-        match self {
-            PutChar(c) => *c,
-            Base(f) => f.a0(),
-            Reset(f) => f.a0(),
-            DebugConsole(f) => f.a0(),
-            HartState(f) => f.a0(),
-            Nacl(f) => f.a0(),
-            CoveHost(f) => f.a0(),
-            CoveInterrupt(f) => f.a0(),
-            CoveGuest(f) => f.a0(),
-            Attestation(f) => f.a0(),
-            Pmu(f) => f.a0(),
-            Vendor(regs) => regs[0],
+        match self.encoding() {
+            Encoding::Function(f) => f.a0(),
+            Encoding::Raw(regs) => regs[0],
         }
     }
 
+    /// Returns the a0-a7 register values for this `SbiMessage`. Passing them to `from_regs` (or
+    /// `from_regs_for_impl` for firmware-specific extensions) yields a message equal to `self`.
+    pub fn to_regs(&self) -> [u64; 8] {
+        [
+            self.a0(),
+            self.a1(),
+            self.a2(),
+            self.a3(),
+            self.a4(),
+            self.a5(),
+            self.a6(),
+            self.a7(),
+        ]
+    }
+
     /// Returns the result returned in the SbiMessage. Intended for use after an SbiMessage has been
     /// handled by the firmware. Interprets the given registers based on the extension and function
     /// and returns the appropriate result.
@@ -371,26 +541,74 @@ impl SbiMessage {
     where
         Result<T>: From<SbiReturn>,
     {
-        let ret = SbiReturn {
-            error_code: a0,
-            return_value: a1,
-        };
-        match self {
+        match self.return_type(a0, a1) {
+            SbiReturnType::Standard(ret) => ret.into(),
             // For legacy messages, a0 is 0 on success and an implementation-defined error value on
-            // failure. Nothing is returned in a1.
-            SbiMessage::PutChar(_) => match a0 {
-                SBI_SUCCESS => SbiReturn {
-                    error_code: 0,
-                    return_value: 0,
+            // failure, except for GetChar which returns the byte read or -1 if there is none, and
+            // ClearIpi which returns whether an IPI was pending. Both values are passed back as
+            // successful results. Nothing is returned in a1.
+            SbiReturnType::Legacy(a0) => match (self, a0 as i64) {
+                (SbiMessage::Legacy(LegacyFunction::GetChar | LegacyFunction::ClearIpi), value) => {
+                    SbiReturn::success(value).into()
                 }
-                .into(),
+                (_, SBI_SUCCESS) => SbiReturn::success(0).into(),
                 _ => Err(Error::Failed),
             },
-            _ => ret.into(),
+        }
+    }
+
+    /// Returns true if this message belongs to one of the legacy (v0.1) extensions.
+    pub fn is_legacy(&self) -> bool {
+        matches!(self, SbiMessage::PutChar(_) | SbiMessage::Legacy(_))
+    }
+
+    /// Encodes a call to the vendor extension function `func` as an `SbiMessage::Vendor`.
+    pub fn from_vendor<E: VendorExtension>(func: &E) -> Self {
+        assert_vendor_eid::<E>();
+        SbiMessage::Vendor([
+            func.a0(),
+            func.a1(),
+            func.a2(),
+            func.a3(),
+            func.a4(),
+            func.a5(),
+            func.a6(),
+            E::EID,
+        ])
+    }
+
+    /// Decodes this message as a call to the vendor extension `E`. Returns `NotSupported` if this
+    /// isn't a call to `E`.
+    pub fn decode_vendor<E: VendorExtension>(&self) -> Result<E> {
+        assert_vendor_eid::<E>();
+        match self {
+            SbiMessage::Vendor(regs) if regs[7] == E::EID => E::from_regs(regs),
+            _ => Err(Error::NotSupported),
+        }
+    }
+
+    /// Returns the values in `a0` and `a1` after the SBI call for this message, interpreted using
+    /// the return value convention of the message's extension.
+    pub fn return_type(&self, a0: i64, a1: i64) -> SbiReturnType {
+        if self.is_legacy() {
+            SbiReturnType::Legacy(a0 as u64)
+        } else {
+            SbiReturnType::Standard(SbiReturn {
+                error_code: a0,
+                return_value: a1,
+            })
         }
     }
 }
 
+impl TryFrom<[u64; 8]> for SbiMessage {
+    type Error = Error;
+
+    fn try_from(regs: [u64; 8]) -> Result<Self> {
+        Self::from_regs(&regs)
+    }
+}
+
 /// Sends an ecall to the firmware or hypervisor.
 ///
 /// # Safety
@@ -402,32 +620,613 @@ impl SbiMessage {
 ///
 /// In addition the caller is placing trust in the firmware or hypervisor to maintain the promises
 /// of the interface w.r.t. reading and writing only within the provided bounds.
+///
+/// On bare-metal RISC-V the call is made with the `ecall` instruction. On other targets it goes to
+/// the transport set with `override_transport` (with the `test-utils` feature) and panics if there
+/// is none. Use `ecall_send_with` to make a call through another transport.
 #[cfg(all(target_arch = "riscv64", target_os = "none"))]
+#[inline(always)]
 pub unsafe fn ecall_send<T>(msg: &SbiMessage) -> Result<T>
 where
     Result<T>: From<SbiReturn>,
 {
-    // normally error code
-    let mut a0;
-    // normally return value
-    let mut a1;
-    asm!("ecall", inlateout("a0") msg.a0()=>a0, inlateout("a1")msg.a1()=>a1,
-                in("a2")msg.a2(), in("a3") msg.a3(),
-                in("a4")msg.a4(), in("a5") msg.a5(),
-                in("a6")msg.a6(), in("a7") msg.a7(), options(nostack));
-
-    msg.result(a0, a1)
+    ecall_send_with(&Ecall, msg)
 }
 
-#[cfg(not(all(target_arch = "riscv64", target_os = "none")))]
-/// Test Compilation only.
+/// Sends an ecall to the firmware or hypervisor.
 ///
 /// # Safety
 ///
-/// Test-only. Do not call.
-pub unsafe fn ecall_send<T>(_msg: &SbiMessage) -> Result<T>
+/// See the bare-metal RISC-V version of `ecall_send`.
+///
+/// On this target the call goes to the transport set with `override_transport` (with the
+/// `test-utils` feature) and panics if there is none.
+#[cfg(not(all(target_arch = "riscv64", target_os = "none")))]
+pub unsafe fn ecall_send<T>(msg: &SbiMessage) -> Result<T>
 where
     Result<T>: From<SbiReturn>,
 {
-    panic!("ecall_send called");
+    let ret = transport::host_ecall(msg);
+    msg.result(ret.error_code, ret.return_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The standard extensions along with the function IDs they define.
+    pub(crate) const EXTENSIONS: &[(u64, &[(u64, &str)])] = &[
+        (EXT_BASE, BaseFunction::FUNCTIONS),
+        (EXT_HART_STATE, StateFunction::FUNCTIONS),
+        (EXT_TIME, TimeFunction::FUNCTIONS),
+        (EXT_IPI, IpiFunction::FUNCTIONS),
+        (EXT_RFENCE, RfenceFunction::FUNCTIONS),
+        (EXT_RESET, ResetFunction::FUNCTIONS),
+        (EXT_SUSP, SuspendFunction::FUNCTIONS),
+        (EXT_DBCN, DebugConsoleFunction::FUNCTIONS),
+        (EXT_NACL, NaclFunction::FUNCTIONS),
+        (EXT_COVE_HOST, CoveHostFunction::FUNCTIONS),
+        (EXT_COVE_INTERRUPT, CoveInterruptFunction::FUNCTIONS),
+        (EXT_COVE_GUEST, CoveGuestFunction::FUNCTIONS),
+        (EXT_ATTESTATION, AttestationFunction::FUNCTIONS),
+        (EXT_PMU, PmuFunction::FUNCTIONS),
+        (EXT_CPPC, CppcFunction::FUNCTIONS),
+        (EXT_STA, StaFunction::FUNCTIONS),
+        (EXT_FWFT, FwftFunction::FUNCTIONS),
+        (EXT_DBTR, DbtrFunction::FUNCTIONS),
+        (EXT_SSE, SseFunction::FUNCTIONS),
+        (EXT_MPXY, MpxyFunction::FUNCTIONS),
+    ];
+
+    // Returns the a0-a5 argument patterns to decode every function with: a few fixed patterns
+    // that decode for functions with typed arguments, followed by pseudo-random ones.
+    pub(crate) fn arg_patterns() -> impl Iterator<Item = [u64; 6]> {
+        let fixed = [[0; 6], [1, 2, 3, 4, 5, 6], [u64::MAX; 6]];
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let random = (0..256).map(move |i| {
+            let mut args = [0; 6];
+            for arg in args.iter_mut() {
+                // xorshift64
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                // Alternate between full-width, 32-bit and small values.
+                *arg = match i % 3 {
+                    0 => state,
+                    1 => state & 0xffff_ffff,
+                    _ => state & 0xf,
+                };
+            }
+            args
+        });
+        fixed.into_iter().chain(random)
+    }
+
+    pub(crate) fn regs(args: [u64; 6], a6: u64, a7: u64) -> [u64; 8] {
+        [args[0], args[1], args[2], args[3], args[4], args[5], a6, a7]
+    }
+
+    // Decodes `regs` and, if that succeeds, checks that the message survives a round trip through
+    // `to_regs`. Returns true if `regs` decoded.
+    fn check_round_trip(regs: [u64; 8]) -> bool {
+        let Ok(msg) = SbiMessage::try_from(regs) else {
+            return false;
+        };
+        let encoded = msg.to_regs();
+        assert_eq!(
+            SbiMessage::try_from(encoded),
+            Ok(msg),
+            "{regs:x?} -> {msg:?}"
+        );
+        assert_eq!(msg.a7(), regs[7], "{msg:?}");
+        true
+    }
+
+    // Checks that a message built by a caller encodes to registers that decode back to the same
+    // message.
+    fn check_constructed(msg: SbiMessage) {
+        assert_eq!(SbiMessage::try_from(msg.to_regs()), Ok(msg), "{msg:?}");
+    }
+
+    // `round_trip_standard` only covers messages that were decoded in the first place. These check
+    // messages built from typed values, including every variant of each typed argument and IDs at
+    // the edges of the reserved and platform-specific ranges, which decoding alone never
+    // constructs. Functions whose arguments are all plain u64s accept any value, so decoding
+    // already covers them.
+    #[test]
+    fn round_trip_constructed_arguments() {
+        for sleep_type in [
+            SleepType::SUSPEND_TO_RAM,
+            SleepType::reserved(0x0000_0001).unwrap(),
+            SleepType::reserved(0x7fff_ffff).unwrap(),
+            SleepType::platform_specific(0x8000_0000).unwrap(),
+            SleepType::platform_specific(0xffff_ffff).unwrap(),
+        ] {
+            check_constructed(SbiMessage::Suspend(SuspendFunction::SystemSuspend {
+                sleep_type,
+                resume_addr: 0x8020_0000,
+                opaque: 1,
+            }));
+        }
+
+        for suspend_type in [
+            SuspendType::DEFAULT_RETENTIVE,
+            SuspendType::DEFAULT_NON_RETENTIVE,
+            SuspendType::platform_retentive(0x1000_0000).unwrap(),
+            SuspendType::platform_retentive(0x7fff_ffff).unwrap(),
+            SuspendType::platform_non_retentive(0x9000_0000).unwrap(),
+            SuspendType::platform_non_retentive(0xffff_ffff).unwrap(),
+        ] {
+            check_constructed(SbiMessage::HartState(StateFunction::HartSuspend {
+                suspend_type,
+                resume_addr: 0x8020_0000,
+                opaque: 1,
+            }));
+        }
+
+        {
+            use CppcRegister::*;
+            for reg_id in [
+                HighestPerformance,
+                NominalPerformance,
+                LowestNonlinearPerformance,
+                LowestPerformance,
+                GuaranteedPerformance,
+                DesiredPerformance,
+                MinimumPerformance,
+                MaximumPerformance,
+                PerformanceReductionTolerance,
+                TimeWindow,
+                CounterWraparoundTime,
+                ReferencePerformanceCounter,
+                DeliveredPerformanceCounter,
+                PerformanceLimited,
+                CppcEnable,
+                AutonomousSelectionEnable,
+                AutonomousActivityWindow,
+                EnergyPerformancePreference,
+                ReferencePerformance,
+                LowestFrequency,
+                NominalFrequency,
+                TransitionLatency,
+                CppcRegister::platform_specific(0x8000_1000).unwrap(),
+                CppcRegister::platform_specific(0x8000_ffff).unwrap(),
+                CppcRegister::reserved(0x0000_0015).unwrap(),
+                CppcRegister::reserved(0x7fff_ffff).unwrap(),
+                CppcRegister::reserved(0x8000_0001).unwrap(),
+                CppcRegister::reserved(0x8000_0fff).unwrap(),
+                CppcRegister::reserved(0x8001_0000).unwrap(),
+                CppcRegister::reserved(0xffff_ffff).unwrap(),
+            ] {
+                check_constructed(SbiMessage::Cppc(CppcFunction::Probe { reg_id }));
+                check_constructed(SbiMessage::Cppc(CppcFunction::Read { reg_id }));
+                check_constructed(SbiMessage::Cppc(CppcFunction::ReadHi { reg_id }));
+                check_constructed(SbiMessage::Cppc(CppcFunction::Write { reg_id, value: 7 }));
+            }
+        }
+
+        {
+            use FwftFeature::*;
+            for feature in [
+                MisalignedExceptionDelegation,
+                LandingPad,
+                ShadowStack,
+                DoubleTrap,
+                PteAdHardwareUpdating,
+                PointerMaskingPmlen,
+                FwftFeature::reserved(0x0000_0006).unwrap(),
+                FwftFeature::reserved(0x3fff_ffff).unwrap(),
+                FwftFeature::reserved(0x8000_0000).unwrap(),
+                FwftFeature::reserved(0xbfff_ffff).unwrap(),
+                FwftFeature::platform_specific(0x4000_0000).unwrap(),
+                FwftFeature::platform_specific(0x7fff_ffff).unwrap(),
+                FwftFeature::platform_specific(0xc000_0000).unwrap(),
+                FwftFeature::platform_specific(0xffff_ffff).unwrap(),
+            ] {
+                for flags in [flagset::FlagSet::default(), FwftFlag::Lock.into()] {
+                    check_constructed(SbiMessage::Fwft(FwftFunction::Set {
+                        feature,
+                        value: 1,
+                        flags,
+                    }));
+                }
+                check_constructed(SbiMessage::Fwft(FwftFunction::Get { feature }));
+            }
+        }
+
+        {
+            use SseEventId::*;
+            for event_id in [
+                LocalHighPrioRas,
+                LocalDoubleTrap,
+                GlobalHighPrioRas,
+                LocalPmuOverflow,
+                LocalLowPrioRas,
+                GlobalLowPrioRas,
+                LocalSoftwareInjected,
+                GlobalSoftwareInjected,
+                SseEventId::reserved(0x0000_0002).unwrap(),
+                SseEventId::reserved(0x0002_4000).unwrap(),
+                SseEventId::platform_specific(0x0000_4000).unwrap(),
+                SseEventId::platform_specific(0xffff_ffff).unwrap(),
+            ] {
+                check_constructed(SbiMessage::Sse(SseFunction::Register {
+                    event_id,
+                    handler_entry_pc: 0x8020_0000,
+                    handler_entry_arg: 1,
+                }));
+                check_constructed(SbiMessage::Sse(SseFunction::Unregister { event_id }));
+                check_constructed(SbiMessage::Sse(SseFunction::Enable { event_id }));
+                check_constructed(SbiMessage::Sse(SseFunction::Disable { event_id }));
+                check_constructed(SbiMessage::Sse(SseFunction::Inject {
+                    event_id,
+                    hart_id: 1,
+                }));
+            }
+        }
+
+        {
+            use SseAttrId::*;
+            for base_attr_id in [
+                Status,
+                Priority,
+                Config,
+                PreferredHart,
+                EntryPc,
+                EntryArg,
+                InterruptedSepc,
+                InterruptedFlags,
+                InterruptedA6,
+                InterruptedA7,
+                SseAttrId::reserved(10).unwrap(),
+                SseAttrId::reserved(u32::MAX).unwrap(),
+            ] {
+                let event_id = SseEventId::LocalSoftwareInjected;
+                check_constructed(SbiMessage::Sse(SseFunction::ReadAttrs {
+                    event_id,
+                    base_attr_id,
+                    attr_count: 1,
+                    output_phys_lo: 0x1000,
+                    output_phys_hi: 0,
+                }));
+                check_constructed(SbiMessage::Sse(SseFunction::WriteAttrs {
+                    event_id,
+                    base_attr_id,
+                    attr_count: 1,
+                    input_phys_lo: 0x1000,
+                    input_phys_hi: 0,
+                }));
+            }
+        }
+
+        {
+            use MpxyAttrId::*;
+            for base_attr_id in [
+                MsgProtId,
+                MsgProtVersion,
+                MsgMaxLen,
+                MsgSendTimeout,
+                MsgCompletionTimeout,
+                ChannelCapability,
+                SseEventId,
+                MsiControl,
+                MsiAddrLo,
+                MsiAddrHi,
+                MsiData,
+                EventsStateControl,
+                MpxyAttrId::reserved(0x0000_000c).unwrap(),
+                MpxyAttrId::reserved(0x7fff_ffff).unwrap(),
+                MpxyAttrId::protocol_specific(0x8000_0000).unwrap(),
+                MpxyAttrId::protocol_specific(0xffff_ffff).unwrap(),
+            ] {
+                check_constructed(SbiMessage::Mpxy(MpxyFunction::ReadAttributes {
+                    channel_id: 1,
+                    base_attr_id,
+                    attr_count: 1,
+                }));
+                check_constructed(SbiMessage::Mpxy(MpxyFunction::WriteAttributes {
+                    channel_id: 1,
+                    base_attr_id,
+                    attr_count: 1,
+                }));
+            }
+        }
+
+        for page_type in [
+            TsmPageType::Page4k,
+            TsmPageType::Page2M,
+            TsmPageType::Page1G,
+            TsmPageType::Page512G,
+        ] {
+            check_constructed(SbiMessage::CoveHost(CoveHostFunction::TvmPromotePage {
+                guest_id: 1,
+                guest_addr: 0x4000_0000,
+                page_type,
+            }));
+            check_constructed(SbiMessage::CoveHost(CoveHostFunction::TvmAddZeroPages {
+                guest_id: 1,
+                page_addr: 0x8000_0000,
+                page_type,
+                num_pages: 2,
+                guest_addr: 0x4000_0000,
+            }));
+        }
+
+        for reset_type in [
+            ResetType::Shutdown,
+            ResetType::ColdReset,
+            ResetType::WarmReset,
+        ] {
+            for reason in [ResetReason::NoReason, ResetReason::SystemFailure] {
+                check_constructed(SbiMessage::Reset(ResetFunction::Reset {
+                    reset_type,
+                    reason,
+                }));
+            }
+        }
+
+        for hart_mask in [
+            HartMask::all(),
+            HartMask::single(0),
+            HartMask::single(u64::MAX),
+            HartMask::new(0b101, 64),
+        ] {
+            check_constructed(SbiMessage::Rfence(RfenceFunction::RemoteFenceI {
+                hart_mask,
+            }));
+            for range in [
+                FenceRange::all(),
+                FenceRange::new(0x1000, 0x2000).unwrap(),
+                FenceRange::new(u64::MAX - 1, 1).unwrap(),
+            ] {
+                check_constructed(SbiMessage::Rfence(RfenceFunction::RemoteHfenceGvmaVmid {
+                    hart_mask,
+                    range,
+                    vmid: 1,
+                }));
+            }
+        }
+
+        let cache = PmuHwCacheParams::new(
+            PmuHwCache::DataTlbCache,
+            PmuHwCacheOpId::Prefetch,
+            PmuHwCacheResultId::CacheHit,
+        );
+        for (event_type, event_data) in [
+            (PmuEventType::Hardware(PmuHardware::CpuCycles), u64::MAX),
+            (PmuEventType::Cache(cache), 0),
+            (PmuEventType::RawEvent, (1 << 48) - 1),
+            (PmuEventType::RawEventV2, (1 << 56) - 1),
+            (PmuEventType::Firmware(PmuFirmware::MisalignedStore), 0),
+        ] {
+            check_constructed(SbiMessage::Pmu(PmuFunction::ConfigureMatchingCounters {
+                counter_index: 3,
+                counter_mask: 1,
+                config_flags: PmuCounterConfigFlags::default().set_skip_match(),
+                event_type,
+                event_data,
+            }));
+        }
+        // Event data the event type can't carry is rejected rather than decoded as another event.
+        let msg = SbiMessage::Pmu(PmuFunction::ConfigureMatchingCounters {
+            counter_index: 3,
+            counter_mask: 1,
+            config_flags: PmuCounterConfigFlags::default(),
+            event_type: PmuEventType::RawEvent,
+            event_data: 1 << 48,
+        });
+        assert_eq!(
+            SbiMessage::try_from(msg.to_regs()),
+            Err(Error::InvalidParam)
+        );
+        check_constructed(SbiMessage::Pmu(PmuFunction::StartCounters {
+            counter_index: 3,
+            counter_mask: 1,
+            start_flags: PmuCounterStartFlags::default()
+                .set_init_value()
+                .set_init_snapshot(),
+            initial_value: 5,
+        }));
+        check_constructed(SbiMessage::Pmu(PmuFunction::StopCounters {
+            counter_index: 3,
+            counter_mask: 1,
+            stop_flags: PmuCounterStopFlags::default().set_reset_flag(),
+        }));
+    }
+
+    // One message built by a caller for each kind of `SbiMessage`.
+    #[test]
+    fn round_trip_constructed_messages() {
+        for msg in [
+            SbiMessage::Base(BaseFunction::ProbeSbiExtension(EXT_MPXY)),
+            SbiMessage::PutChar(b'x' as u64),
+            SbiMessage::Legacy(LegacyFunction::RemoteSfenceVmaAsid {
+                hart_mask_addr: 0x1000,
+                start_addr: 0x2000,
+                size: 0x3000,
+                asid: 4,
+            }),
+            SbiMessage::HartState(StateFunction::HartStart {
+                hart_id: 1,
+                start_addr: 0x8020_0000,
+                opaque: 2,
+            }),
+            SbiMessage::Time(TimeFunction::SetTimer { stime_value: 1 }),
+            SbiMessage::Ipi(IpiFunction::SendIpi {
+                hart_mask: 1,
+                hart_mask_base: 2,
+            }),
+            SbiMessage::Rfence(RfenceFunction::RemoteFenceI {
+                hart_mask: HartMask::all(),
+            }),
+            SbiMessage::Reset(ResetFunction::shutdown()),
+            SbiMessage::Suspend(SuspendFunction::SystemSuspend {
+                sleep_type: SleepType::SUSPEND_TO_RAM,
+                resume_addr: 0x8020_0000,
+                opaque: 0,
+            }),
+            SbiMessage::DebugConsole(DebugConsoleFunction::WriteByte { byte: b'x' as u64 }),
+            SbiMessage::Nacl(NaclFunction::SyncHfence { entry_index: 3 }),
+            SbiMessage::CoveHost(CoveHostFunction::TsmInitiateFence),
+            SbiMessage::CoveInterrupt(CoveInterruptFunction::TsmConvertImsic {
+                imsic_addr: 0x2800_0000,
+            }),
+            SbiMessage::CoveGuest(CoveGuestFunction::AllowExternalInterrupt { id: -1 }),
+            SbiMessage::Attestation(AttestationFunction::GetCapabilities {
+                caps_addr_out: 0x1000,
+                caps_size: 0x100,
+            }),
+            SbiMessage::Pmu(PmuFunction::GetNumCounters),
+            SbiMessage::Cppc(CppcFunction::Read {
+                reg_id: CppcRegister::DesiredPerformance,
+            }),
+            SbiMessage::Sta(StaFunction::SetShmem {
+                shmem_lo: 0x1000,
+                shmem_hi: 0,
+                flags: 0,
+            }),
+            SbiMessage::Fwft(FwftFunction::Get {
+                feature: FwftFeature::ShadowStack,
+            }),
+            SbiMessage::Dbtr(DbtrFunction::NumTriggers { trig_tdata1: 2 }),
+            SbiMessage::Sse(SseFunction::Complete),
+            SbiMessage::Mpxy(MpxyFunction::SendMessageWithResponse {
+                channel_id: 1,
+                msg_id: 2,
+                msg_data_len: 3,
+            }),
+            SbiMessage::from_vendor(&salus::SalusTestFunction::MemCopy(salus::MemCopyArgs {
+                to: 0x1000,
+                from: 0x2000,
+                len: 0x30,
+            })),
+            SbiMessage::experimental([1, 2, 3, 4, 5, 6, 7, EXT_EXPERIMENTAL_RANGE_END]).unwrap(),
+        ] {
+            check_constructed(msg);
+        }
+
+        // Firmware-specific messages only decode for the implementation that defines them.
+        let msg = SbiMessage::firmware_specific(7, [1, 2, 3, 4, 5, 6, 7, EXT_FIRMWARE_RANGE_END])
+            .unwrap();
+        assert_eq!(SbiMessage::from_regs_for_impl(&msg.to_regs(), 7), Ok(msg));
+    }
+
+    #[test]
+    fn round_trip_standard() {
+        for (eid, functions) in EXTENSIONS {
+            for (fid, name) in functions.iter() {
+                let mut decoded = 0;
+                for args in arg_patterns() {
+                    if check_round_trip(regs(args, *fid, *eid)) {
+                        decoded += 1;
+                    }
+                }
+                assert!(
+                    decoded > 0,
+                    "{name} (EID {eid:#x}, FID {fid}) never decoded"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_legacy() {
+        for (eid, name) in LegacyFunction::FUNCTIONS
+            .iter()
+            .chain(&[(EXT_PUT_CHAR, "PutChar")])
+        {
+            let mut decoded = 0;
+            for args in arg_patterns() {
+                // a6 is ignored by the legacy extensions.
+                if check_round_trip(regs(args, args[0], *eid)) {
+                    decoded += 1;
+                }
+            }
+            assert!(decoded > 0, "{name} (EID {eid:#x}) never decoded");
+        }
+    }
+
+    #[test]
+    fn legacy_results() {
+        let getchar = SbiMessage::Legacy(LegacyFunction::GetChar);
+        assert_eq!(getchar.result::<i64>(b'x' as i64, 0), Ok(b'x' as i64));
+        // No byte available isn't an error.
+        assert_eq!(getchar.result::<i64>(-1, 0), Ok(-1));
+        let clear_ipi = SbiMessage::Legacy(LegacyFunction::ClearIpi);
+        assert_eq!(clear_ipi.result::<i64>(1, 0), Ok(1));
+        let putchar = SbiMessage::PutChar(b'x' as u64);
+        assert_eq!(putchar.result::<()>(0, 7), Ok(()));
+        assert_eq!(putchar.result::<()>(-5, 0), Err(Error::Failed));
+    }
+
+    #[test]
+    fn round_trip_ranges() {
+        for eid in [
+            EXT_EXPERIMENTAL_RANGE_START,
+            EXT_EXPERIMENTAL_RANGE_END,
+            EXT_VENDOR_RANGE_START,
+            EXT_VENDOR_RANGE_END,
+        ] {
+            for args in arg_patterns() {
+                assert!(check_round_trip(regs(args, args[1], eid)));
+            }
+        }
+
+        let msg = SbiMessage::firmware_specific(7, [1, 2, 3, 4, 5, 6, 7, EXT_FIRMWARE_RANGE_START])
+            .unwrap();
+        assert_eq!(SbiMessage::from_regs_for_impl(&msg.to_regs(), 7), Ok(msg));
+        assert_eq!(
+            SbiMessage::try_from(msg.to_regs()),
+            Err(Error::NotSupported)
+        );
+    }
+
+    #[test]
+    fn opaque_payloads() {
+        let regs = [1, 2, 3, 4, 5, 6, 7, EXT_EXPERIMENTAL_RANGE_START];
+        let Ok(SbiMessage::Experimental(call)) = SbiMessage::experimental(regs) else {
+            panic!("experimental message not created");
+        };
+        assert_eq!(call.regs(), &regs);
+        for eid in [EXT_BASE, EXT_VENDOR_RANGE_START, EXT_FIRMWARE_RANGE_START] {
+            let regs = [0, 0, 0, 0, 0, 0, 0, eid];
+            assert_eq!(SbiMessage::experimental(regs), Err(Error::InvalidParam));
+        }
+
+        let regs = [1, 2, 3, 4, 5, 6, 7, EXT_FIRMWARE_RANGE_END];
+        let Ok(SbiMessage::FirmwareSpecific(call)) = SbiMessage::firmware_specific(9, regs) else {
+            panic!("firmware-specific message not created");
+        };
+        assert_eq!(call.impl_id(), 9);
+        assert_eq!(call.regs(), &regs);
+        for eid in [EXT_BASE, EXT_VENDOR_RANGE_END, EXT_EXPERIMENTAL_RANGE_END] {
+            let regs = [0, 0, 0, 0, 0, 0, 0, eid];
+            assert_eq!(
+                SbiMessage::firmware_specific(9, regs),
+                Err(Error::InvalidParam)
+            );
+        }
+    }
+
+    #[test]
+    fn error_round_trip() {
+        for error in [
+            Error::Io,
+            Error::DeniedLocked,
+            Error::other(-0x1234).unwrap(),
+            Error::other(7).unwrap(),
+        ] {
+            let ret = SbiReturn::from(error);
+            assert_eq!(ret.error_code, error.code());
+            assert_eq!(Result::<i64>::from(ret), Err(error));
+        }
+    }
+
+    #[test]
+    fn unknown_extension() {
+        assert_eq!(
+            SbiMessage::try_from([0, 0, 0, 0, 0, 0, 0, 0x1234_5678]),
+            Err(Error::NotSupported)
+        );
+    }
 }
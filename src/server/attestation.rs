@@ -0,0 +1,194 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{AttestationFunction, Error, Result};
+
+/// Handles calls to the Attestation extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait AttestationHandler {
+    /// Get the SBI implementation attestation capabilities.
+    /// The attestation capabilities let the SBI implementations expose which
+    /// hash algorithm is being used for measurements, which evidence formats
+    /// are supported. The attestation capabilities structure also contains a
+    /// map of all  measurement registers.
+    fn get_capabilities(&mut self, _caps_addr_out: u64, _caps_size: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Get an attestation evidence from a Certificate Signing Request (CSR)
+    /// <https://datatracker.ietf.org/doc/html/rfc2986>.
+    /// The caller passes the CSR and its length through the first 2 arguments.
+    /// The third argument is the address where the caller places a data blob
+    /// that will be included in the generated certificate. Typically, this is a
+    /// cryptographic nonce.
+    /// The fourth argument is the evidence format: DiceTcbInfo (0),
+    /// DiceMultiTcbInfo (1) or OpenDice (2).
+    /// The fifth argument is the address where the generated certificate will be placed.
+    /// The evidence is formatted an x.509 DiceTcbInfo certificate extension
+    ///
+    /// `crate::read_bytes` reads the CSR with a bound on `cert_request_size`.
+    fn get_evidence(
+        &mut self,
+        _cert_request_addr: u64,
+        _cert_request_size: u64,
+        _request_data_addr: u64,
+        _evidence_format: u64,
+        _cert_addr_out: u64,
+        _cert_size: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Extend a measurement register with an additional measurement.
+    /// The first parameter is the address of the measurement buffer.
+    /// The second argument is the length of the measurement buffer, which must
+    /// be the same as the hash algorithm size reported by `GetCapabilities`.
+    /// The third parameter is the measurement register index, and it must be
+    /// one of the reported 'TCG_PCR_INDEX` from the runtime measurement
+    /// registers array.
+    fn extend_measurement(
+        &mut self,
+        _measurement_data_addr: u64,
+        _measurement_data_size: u64,
+        _measurement_index: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Read a measurement register data back.
+    /// The first parameter is the address of the measurement buffer allocated
+    /// by the caller, for the SBI implementation to write the measurement data
+    /// into.
+    /// The second argument is the length of the measurement buffer, which must
+    /// be at least as large as the hash algorithm size reported by
+    /// `GetCapabilities`.
+    /// The third parameter is the measurement register index, and it must be
+    /// one of the reported 'TCG_PCR_INDEX` from the runtime measurement
+    /// registers array.
+    /// The returned value is the length of the measurement data.
+    fn read_measurement(
+        &mut self,
+        _measurement_data_addr_out: u64,
+        _measurement_data_size: u64,
+        _measurement_index: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(
+    handler: &mut dyn AttestationHandler,
+    function: AttestationFunction,
+) -> Result<u64> {
+    use AttestationFunction::*;
+    match function {
+        GetCapabilities {
+            caps_addr_out,
+            caps_size,
+        } => handler.get_capabilities(caps_addr_out, caps_size),
+        GetEvidence {
+            cert_request_addr,
+            cert_request_size,
+            request_data_addr,
+            evidence_format,
+            cert_addr_out,
+            cert_size,
+        } => handler.get_evidence(
+            cert_request_addr,
+            cert_request_size,
+            request_data_addr,
+            evidence_format,
+            cert_addr_out,
+            cert_size,
+        ),
+        ExtendMeasurement {
+            measurement_data_addr,
+            measurement_data_size,
+            measurement_index,
+        } => handler.extend_measurement(
+            measurement_data_addr,
+            measurement_data_size,
+            measurement_index,
+        ),
+        ReadMeasurement {
+            measurement_data_addr_out,
+            measurement_data_size,
+            measurement_index,
+        } => handler.read_measurement(
+            measurement_data_addr_out,
+            measurement_data_size,
+            measurement_index,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use AttestationFunction::*;
+
+    impl AttestationHandler for Recorder {
+        fn get_capabilities(&mut self, caps_addr_out: u64, caps_size: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Attestation(GetCapabilities {
+                caps_addr_out,
+                caps_size,
+            }));
+            Ok(0)
+        }
+
+        fn get_evidence(
+            &mut self,
+            cert_request_addr: u64,
+            cert_request_size: u64,
+            request_data_addr: u64,
+            evidence_format: u64,
+            cert_addr_out: u64,
+            cert_size: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Attestation(GetEvidence {
+                cert_request_addr,
+                cert_request_size,
+                request_data_addr,
+                evidence_format,
+                cert_addr_out,
+                cert_size,
+            }));
+            Ok(0)
+        }
+
+        fn extend_measurement(
+            &mut self,
+            measurement_data_addr: u64,
+            measurement_data_size: u64,
+            measurement_index: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Attestation(ExtendMeasurement {
+                measurement_data_addr,
+                measurement_data_size,
+                measurement_index,
+            }));
+            Ok(0)
+        }
+
+        fn read_measurement(
+            &mut self,
+            measurement_data_addr_out: u64,
+            measurement_data_size: u64,
+            measurement_index: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Attestation(ReadMeasurement {
+                measurement_data_addr_out,
+                measurement_data_size,
+                measurement_index,
+            }));
+            Ok(0)
+        }
+    }
+}
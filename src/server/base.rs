@@ -0,0 +1,96 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{BaseFunction, Error, Result};
+
+/// Handles calls to the Base extension. `ProbeSbiExtension` is answered by `SbiServer`.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait BaseHandler {
+    /// Returns the implemented version of the SBI standard.
+    fn get_specification_version(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Returns the ID of the SBI implementation.
+    fn get_implementation_id(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Returns the version of this SBI implementation.
+    fn get_implementation_version(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Returns the vendor that produced this machine(`mvendorid`).
+    fn get_machine_vendor_id(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Returns the architecture implementation ID of this machine(`marchid`).
+    fn get_machine_architecture_id(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Returns the implementation ID of this machine(`mimpid`).
+    fn get_machine_implementation_id(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn BaseHandler, function: BaseFunction) -> Result<u64> {
+    use BaseFunction::*;
+    match function {
+        GetSpecificationVersion => handler.get_specification_version(),
+        GetImplementationID => handler.get_implementation_id(),
+        GetImplementationVersion => handler.get_implementation_version(),
+        GetMachineVendorID => handler.get_machine_vendor_id(),
+        GetMachineArchitectureID => handler.get_machine_architecture_id(),
+        GetMachineImplementationID => handler.get_machine_implementation_id(),
+        _ => Err(Error::NotSupported),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use BaseFunction::*;
+
+    impl BaseHandler for Recorder {
+        fn get_specification_version(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Base(GetSpecificationVersion));
+            Ok(0)
+        }
+
+        fn get_implementation_id(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Base(GetImplementationID));
+            Ok(0)
+        }
+
+        fn get_implementation_version(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Base(GetImplementationVersion));
+            Ok(0)
+        }
+
+        fn get_machine_vendor_id(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Base(GetMachineVendorID));
+            Ok(0)
+        }
+
+        fn get_machine_architecture_id(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Base(GetMachineArchitectureID));
+            Ok(0)
+        }
+
+        fn get_machine_implementation_id(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Base(GetMachineImplementationID));
+            Ok(0)
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{CoveGuestFunction, Error, Result};
+
+/// Handles calls to the COVE Guest extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait CoveGuestHandler {
+    /// Marks the specified range of guest physical address space as used for emulated MMIO. Upon
+    /// return, all accesses by the TVM within the range are trapped and may be emulated by the
+    /// host.
+    fn add_mmio_region(&mut self, _addr: u64, _len: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Removes the specified range of guest physical address space from the emulated MMIO regions. Upon
+    /// return, all accesses by the TVM within the range will result in a page fault.
+    fn remove_mmio_region(&mut self, _addr: u64, _len: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Requests conversion of the specified range of guest physical address space from confidential
+    /// to shared.
+    fn share_memory(&mut self, _addr: u64, _len: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Requests conversion of the specified range of guest physical address space from shared to
+    /// confidential.
+    fn unshare_memory(&mut self, _addr: u64, _len: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Allows injection of the specified external interrupt ID into the calling TVM vCPU. Passing
+    /// an ID of -1 allows injection of all external interrupts. TVM vCPUs are started with
+    /// injection of external interrupts completely disabled by default.
+    fn allow_external_interrupt(&mut self, _id: i64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Denies injection of the specified external interrupt ID into the calling TVM vCPU. Passing
+    /// an ID of -1 denies injection of all external interrupts.
+    fn deny_external_interrupt(&mut self, _id: i64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(
+    handler: &mut dyn CoveGuestHandler,
+    function: CoveGuestFunction,
+) -> Result<u64> {
+    use CoveGuestFunction::*;
+    match function {
+        AddMmioRegion { addr, len } => handler.add_mmio_region(addr, len),
+        RemoveMmioRegion { addr, len } => handler.remove_mmio_region(addr, len),
+        ShareMemory { addr, len } => handler.share_memory(addr, len),
+        UnshareMemory { addr, len } => handler.unshare_memory(addr, len),
+        AllowExternalInterrupt { id } => handler.allow_external_interrupt(id),
+        DenyExternalInterrupt { id } => handler.deny_external_interrupt(id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use CoveGuestFunction::*;
+
+    impl CoveGuestHandler for Recorder {
+        fn add_mmio_region(&mut self, addr: u64, len: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveGuest(AddMmioRegion { addr, len }));
+            Ok(0)
+        }
+
+        fn remove_mmio_region(&mut self, addr: u64, len: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveGuest(RemoveMmioRegion { addr, len }));
+            Ok(0)
+        }
+
+        fn share_memory(&mut self, addr: u64, len: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveGuest(ShareMemory { addr, len }));
+            Ok(0)
+        }
+
+        fn unshare_memory(&mut self, addr: u64, len: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveGuest(UnshareMemory { addr, len }));
+            Ok(0)
+        }
+
+        fn allow_external_interrupt(&mut self, id: i64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveGuest(AllowExternalInterrupt { id }));
+            Ok(0)
+        }
+
+        fn deny_external_interrupt(&mut self, id: i64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveGuest(DenyExternalInterrupt { id }));
+            Ok(0)
+        }
+    }
+}
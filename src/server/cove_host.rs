@@ -0,0 +1,515 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{CoveHostFunction, Error, Result, TsmPageType};
+
+/// Handles calls to the COVE Host extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait CoveHostHandler {
+    /// Writes up to `len` bytes of the `TsmInfo` structure to the non-confidential physical address
+    /// `dest_addr`. Returns the number of bytes written.
+    fn tsm_get_info(&mut self, _dest_addr: u64, _len: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Converts `num_pages` of 4kB page-size non-confidential memory starting at `page_addr`. The converted pages
+    /// remain non-confidential, and thus may not be assigned for use by a child TVM, until the
+    /// fence procedure, described below, has been completed.
+    fn tsm_convert_pages(&mut self, _page_addr: u64, _num_pages: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Reclaims `num_pages` of 4kB page-size confidential memory starting at `page_addr`. The pages must not
+    /// be currently assigned to an active TVM.
+    fn tsm_reclaim_pages(&mut self, _page_addr: u64, _num_pages: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Initiates a TLB invalidation sequence for all pages marked for conversion via calls to
+    /// `TsmConvertPages` between the previous `TsmInitiateFence` and now. The TLB invalidation
+    /// sequence is completed when `TsmLocalFence` has been invoked on all other CPUs, after which
+    /// the pages covered by the invalidation sequence are considered to be fully converted &
+    /// confidential, and may be assigned for use by child TVMs. An error is returned if a TLB
+    /// invalidation sequence is already in progress.
+    fn tsm_initiate_fence(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Invalidates TLB entries for all pages pending conversion by an in-progress TLB invalidation
+    /// operation on the local CPU.
+    fn tsm_local_fence(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Creates a TVM from the parameters in the `TvmCreateParams` structure at the non-confidential
+    /// physical address `params_addr`. Returns a guest ID that can be used to refer to the TVM in
+    /// TVM management TEECALLs.
+    fn tvm_create(&mut self, _params_addr: u64, _len: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Moves a VM from the "Initializing" state to the "Runnable" state, finalizing the
+    /// measurement of the TVM's configuration and initial memory contents. Sets the initial
+    /// entry point (SEPC and opaque argument passed in A1) for the boot vCPU of the TVM.
+    fn finalize(&mut self, _guest_id: u64, _entry_sepc: u64, _entry_arg: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Message to destroy a TVM created with `TvmCreate`.
+    fn tvm_destroy(&mut self, _guest_id: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Adds a memory region to the TVM identified by `guest_id` at the specified range of guest
+    /// physical address space. The memory range is confidential to the guest and may only be
+    /// populated with confidential pages. The guest may later convert parts of this region to
+    /// shared memory with the `ShareMemory` COVE-Guest call.
+    fn tvm_add_memory_region(
+        &mut self,
+        _guest_id: u64,
+        _guest_addr: u64,
+        _len: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Adds `num_pages` 4kB pages of confidential memory starting at `page_addr` to the page-table
+    /// page pool for the specified guest.
+    fn add_page_table_pages(
+        &mut self,
+        _guest_id: u64,
+        _page_addr: u64,
+        _num_pages: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Copies `num_pages` pages from non-confidential memory at `src_addr` to confidential
+    /// memory at `dest_addr`, then measures and maps the pages at `dest_addr` into the specified
+    /// guest's address space at `guest_addr`. The mapping must lie within a region of confidential
+    /// memory created with `TvmAddMemoryRegion`. Measured pages may only be added prior to TVM
+    /// finalization.
+    fn tvm_add_measured_pages(
+        &mut self,
+        _guest_id: u64,
+        _src_addr: u64,
+        _dest_addr: u64,
+        _page_type: TsmPageType,
+        _num_pages: u64,
+        _guest_addr: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Maps `num_pages` zero-filled pages of confidential memory starting at `page_addr` into the
+    /// specified guest's address space at `guest_addr`. The mapping must lie within a region of
+    /// confidential memory created with `TvmAddMemoryRegion`. Zero pages may only be added after
+    /// the TVM has been finalized.
+    fn tvm_add_zero_pages(
+        &mut self,
+        _guest_id: u64,
+        _page_addr: u64,
+        _page_type: TsmPageType,
+        _num_pages: u64,
+        _guest_addr: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Maps non-confidential shared pages in a region of shared memory previously registered by
+    /// the guest via `ShareMemory` in the COVE-Guest API.
+    fn tvm_add_shared_pages(
+        &mut self,
+        _guest_id: u64,
+        _page_addr: u64,
+        _page_type: TsmPageType,
+        _num_pages: u64,
+        _guest_addr: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Adds a vCPU with ID `vcpu_id` to the guest `guest_id`, using the memory at `stage_page_addr`
+    /// for internal storage of the vCPU's state.
+    fn tvm_cpu_create(
+        &mut self,
+        _guest_id: u64,
+        _vcpu_id: u64,
+        _state_page_addr: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Runs the given vCPU in the TVM
+    fn tvm_cpu_run(&mut self, _guest_id: u64, _vcpu_id: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Initiates a TLB invalidation sequence for all pages that have been invalidated in the
+    /// given TVM's address space since the previous call to `TvmInitiateFence`. The TLB
+    /// invalidation sequence is completed when all vCPUs in the TVM that were running prior to
+    /// to the call to `TvmInitiateFence` have taken a trap into the TSM, which the host can
+    /// cause by IPI'ing the physical CPUs on which the TVM's vCPUs are running. An error is
+    /// returned if a TLB invalidation sequence is already in progress for the TVM.
+    fn tvm_initiate_fence(&mut self, _guest_id: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Invalidates the pages in the specified range of guest physical address space.
+    fn tvm_block_pages(&mut self, _guest_id: u64, _guest_addr: u64, _len: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Marks the invalidated pages in the specified range of guest physical address space
+    /// as present.
+    fn tvm_unblock_pages(&mut self, _guest_id: u64, _guest_addr: u64, _len: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Promotes a set of contiguous mappings to the requested page size.
+    fn tvm_promote_page(
+        &mut self,
+        _guest_id: u64,
+        _guest_addr: u64,
+        _page_type: TsmPageType,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Demotes a huge page mapping to a set of contiguous mappings at the target size.
+    fn tvm_demote_page(
+        &mut self,
+        _guest_id: u64,
+        _guest_addr: u64,
+        _page_type: TsmPageType,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Removes mappings from a TVM. The range to be unmapped must already have been invalidated
+    /// and fenced, and must lie within a removable region of guest physical address space.
+    fn tvm_remove_pages(&mut self, _guest_id: u64, _guest_addr: u64, _len: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(
+    handler: &mut dyn CoveHostHandler,
+    function: CoveHostFunction,
+) -> Result<u64> {
+    use CoveHostFunction::*;
+    match function {
+        TsmGetInfo { dest_addr, len } => handler.tsm_get_info(dest_addr, len),
+        TsmConvertPages {
+            page_addr,
+            num_pages,
+        } => handler.tsm_convert_pages(page_addr, num_pages),
+        TsmReclaimPages {
+            page_addr,
+            num_pages,
+        } => handler.tsm_reclaim_pages(page_addr, num_pages),
+        TsmInitiateFence => handler.tsm_initiate_fence(),
+        TsmLocalFence => handler.tsm_local_fence(),
+        TvmCreate { params_addr, len } => handler.tvm_create(params_addr, len),
+        Finalize {
+            guest_id,
+            entry_sepc,
+            entry_arg,
+        } => handler.finalize(guest_id, entry_sepc, entry_arg),
+        TvmDestroy { guest_id } => handler.tvm_destroy(guest_id),
+        TvmAddMemoryRegion {
+            guest_id,
+            guest_addr,
+            len,
+        } => handler.tvm_add_memory_region(guest_id, guest_addr, len),
+        AddPageTablePages {
+            guest_id,
+            page_addr,
+            num_pages,
+        } => handler.add_page_table_pages(guest_id, page_addr, num_pages),
+        TvmAddMeasuredPages {
+            guest_id,
+            src_addr,
+            dest_addr,
+            page_type,
+            num_pages,
+            guest_addr,
+        } => handler.tvm_add_measured_pages(
+            guest_id, src_addr, dest_addr, page_type, num_pages, guest_addr,
+        ),
+        TvmAddZeroPages {
+            guest_id,
+            page_addr,
+            page_type,
+            num_pages,
+            guest_addr,
+        } => handler.tvm_add_zero_pages(guest_id, page_addr, page_type, num_pages, guest_addr),
+        TvmAddSharedPages {
+            guest_id,
+            page_addr,
+            page_type,
+            num_pages,
+            guest_addr,
+        } => handler.tvm_add_shared_pages(guest_id, page_addr, page_type, num_pages, guest_addr),
+        TvmCpuCreate {
+            guest_id,
+            vcpu_id,
+            state_page_addr,
+        } => handler.tvm_cpu_create(guest_id, vcpu_id, state_page_addr),
+        TvmCpuRun { guest_id, vcpu_id } => handler.tvm_cpu_run(guest_id, vcpu_id),
+        TvmInitiateFence { guest_id } => handler.tvm_initiate_fence(guest_id),
+        TvmBlockPages {
+            guest_id,
+            guest_addr,
+            len,
+        } => handler.tvm_block_pages(guest_id, guest_addr, len),
+        TvmUnblockPages {
+            guest_id,
+            guest_addr,
+            len,
+        } => handler.tvm_unblock_pages(guest_id, guest_addr, len),
+        TvmPromotePage {
+            guest_id,
+            guest_addr,
+            page_type,
+        } => handler.tvm_promote_page(guest_id, guest_addr, page_type),
+        TvmDemotePage {
+            guest_id,
+            guest_addr,
+            page_type,
+        } => handler.tvm_demote_page(guest_id, guest_addr, page_type),
+        TvmRemovePages {
+            guest_id,
+            guest_addr,
+            len,
+        } => handler.tvm_remove_pages(guest_id, guest_addr, len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use CoveHostFunction::*;
+
+    impl CoveHostHandler for Recorder {
+        fn tsm_get_info(&mut self, dest_addr: u64, len: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TsmGetInfo { dest_addr, len }));
+            Ok(0)
+        }
+
+        fn tsm_convert_pages(&mut self, page_addr: u64, num_pages: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TsmConvertPages {
+                page_addr,
+                num_pages,
+            }));
+            Ok(0)
+        }
+
+        fn tsm_reclaim_pages(&mut self, page_addr: u64, num_pages: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TsmReclaimPages {
+                page_addr,
+                num_pages,
+            }));
+            Ok(0)
+        }
+
+        fn tsm_initiate_fence(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TsmInitiateFence));
+            Ok(0)
+        }
+
+        fn tsm_local_fence(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TsmLocalFence));
+            Ok(0)
+        }
+
+        fn tvm_create(&mut self, params_addr: u64, len: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmCreate { params_addr, len }));
+            Ok(0)
+        }
+
+        fn finalize(&mut self, guest_id: u64, entry_sepc: u64, entry_arg: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(Finalize {
+                guest_id,
+                entry_sepc,
+                entry_arg,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_destroy(&mut self, guest_id: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmDestroy { guest_id }));
+            Ok(0)
+        }
+
+        fn tvm_add_memory_region(
+            &mut self,
+            guest_id: u64,
+            guest_addr: u64,
+            len: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmAddMemoryRegion {
+                guest_id,
+                guest_addr,
+                len,
+            }));
+            Ok(0)
+        }
+
+        fn add_page_table_pages(
+            &mut self,
+            guest_id: u64,
+            page_addr: u64,
+            num_pages: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(AddPageTablePages {
+                guest_id,
+                page_addr,
+                num_pages,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_add_measured_pages(
+            &mut self,
+            guest_id: u64,
+            src_addr: u64,
+            dest_addr: u64,
+            page_type: TsmPageType,
+            num_pages: u64,
+            guest_addr: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmAddMeasuredPages {
+                guest_id,
+                src_addr,
+                dest_addr,
+                page_type,
+                num_pages,
+                guest_addr,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_add_zero_pages(
+            &mut self,
+            guest_id: u64,
+            page_addr: u64,
+            page_type: TsmPageType,
+            num_pages: u64,
+            guest_addr: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmAddZeroPages {
+                guest_id,
+                page_addr,
+                page_type,
+                num_pages,
+                guest_addr,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_add_shared_pages(
+            &mut self,
+            guest_id: u64,
+            page_addr: u64,
+            page_type: TsmPageType,
+            num_pages: u64,
+            guest_addr: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmAddSharedPages {
+                guest_id,
+                page_addr,
+                page_type,
+                num_pages,
+                guest_addr,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_cpu_create(
+            &mut self,
+            guest_id: u64,
+            vcpu_id: u64,
+            state_page_addr: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmCpuCreate {
+                guest_id,
+                vcpu_id,
+                state_page_addr,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_cpu_run(&mut self, guest_id: u64, vcpu_id: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmCpuRun { guest_id, vcpu_id }));
+            Ok(0)
+        }
+
+        fn tvm_initiate_fence(&mut self, guest_id: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmInitiateFence { guest_id }));
+            Ok(0)
+        }
+
+        fn tvm_block_pages(&mut self, guest_id: u64, guest_addr: u64, len: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmBlockPages {
+                guest_id,
+                guest_addr,
+                len,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_unblock_pages(&mut self, guest_id: u64, guest_addr: u64, len: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmUnblockPages {
+                guest_id,
+                guest_addr,
+                len,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_promote_page(
+            &mut self,
+            guest_id: u64,
+            guest_addr: u64,
+            page_type: TsmPageType,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmPromotePage {
+                guest_id,
+                guest_addr,
+                page_type,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_demote_page(
+            &mut self,
+            guest_id: u64,
+            guest_addr: u64,
+            page_type: TsmPageType,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmDemotePage {
+                guest_id,
+                guest_addr,
+                page_type,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_remove_pages(&mut self, guest_id: u64, guest_addr: u64, len: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveHost(TvmRemovePages {
+                guest_id,
+                guest_addr,
+                len,
+            }));
+            Ok(0)
+        }
+    }
+}
@@ -0,0 +1,277 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{CoveInterruptFunction, Error, Result};
+
+/// Handles calls to the COVE Interrupt extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait CoveInterruptHandler {
+    /// Configures AIA virtualization for the TVM identified by `tvm_id` from the parameters in
+    /// the `TvmAiaParams` structure at the non-confidential physical address `params_addr`.
+    fn tvm_aia_init(&mut self, _tvm_id: u64, _params_addr: u64, _len: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Sets the guest physical address of the specified vCPU's virtualized IMSIC to `imsic_addr`.
+    /// `imsic_addr` must be valid for the AIA configuration that was set in `TvmAiaInit` and no
+    /// two vCPUs may share the same `imsic_addr`.
+    fn tvm_cpu_set_imsic_addr(
+        &mut self,
+        _tvm_id: u64,
+        _vcpu_id: u64,
+        _imsic_addr: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Converts the non-confidential guest interrupt file at `imsic_addr` for use with a TVM. A TSM
+    /// fence sequence must be completed before the interrupt file may be assigned to a child TVM.
+    fn tsm_convert_imsic(&mut self, _imsic_addr: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Reclaims the confidential guest interrupt file at `imsic_addr`. The interrupt file must not
+    /// currently be assigned to a TVM.
+    fn tsm_reclaim_imsic(&mut self, _imsic_addr: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Binds a TVM vCPU to the current physical CPU using the confidential guest interrupt files
+    /// specified in `imsic_mask`, restoring interrupt state from the vCPU's software interrupt
+    /// file if necessary. Note that `imsic_mask` is in the same format as the `hgeie` and `hgeip`
+    /// CSRs, that is bit N corresponds to guest interrupt file N-1 and bit 0 is always 0. The
+    /// number of bits set in `imsic_mask` must be equal to the number of interrupt files in the
+    /// vCPU's virtualized IMSIC (i.e. 1 + `guests_per_hart`). The vCPU must currently be unbound.
+    /// Upon completion,  the vCPU is eligible to be run on this CPU with `TvmCpuRun`.
+    fn tvm_cpu_bind_imsic(&mut self, _tvm_id: u64, _vcpu_id: u64, _imsic_mask: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Begins the unbind process for the specified vCPU from its guest interrupt files. The
+    /// translations for the vCPU's virtualized IMSIC are invalidated, and a TLB flush sequence
+    /// for the TVM must be completed before calling `TvmCpuUnbindImsicEnd` to complete the unbind
+    /// process. Must be called on the physical CPU to which the vCPU is bound.
+    fn tvm_cpu_unbind_imsic_begin(&mut self, _tvm_id: u64, _vcpu_id: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Completes the unbind process for the specified vCPU from its guest interrupt files after
+    /// a TLB flush sequence for the TVM has been completed. Interrupt state is saved to the vCPU's
+    /// software interrupt file and the guest interrupt files are free to be reclaimed via
+    /// `TsmReclaimImsic` or bound to another vCPU via `TvmCpuBindImsic`. Must be called on
+    /// the physical CPU to which the vCPU is bound. Upon success, the vCPU is free to be bound
+    /// to another physical CPU.
+    fn tvm_cpu_unbind_imsic_end(&mut self, _tvm_id: u64, _vcpu_id: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Injects an external interrupt with the given `interrupt_id` into the specified vCPU. If the
+    /// vCPU is presently bound to an IMSIC guest interrupt file, the interrupt is immediately
+    /// injected by writing to the interrupt file. If it is not bound, the interrupt is recorded
+    /// in software and will be injected once the vCPU becomes bound. The specified interrupt ID
+    /// must be valid and must have been allowed by the guest with `allow_external_interrupt()`.
+    fn tvm_cpu_inject_external_interrupt(
+        &mut self,
+        _tvm_id: u64,
+        _vcpu_id: u64,
+        _interrupt_id: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Begins the rebinding process for the specified vCPU to this physical CPU and the specified
+    /// confidential guest interrupt file. The host must complete a TLB invalidation sequence
+    /// for the TVM before cloning old interrupt file state using `rebind_vcpu_imsic_clone`. Once cloned
+    /// the old file will be restored to new guest interrupt file on `rebind_vcpu_imsic_end` invocation.
+    fn tvm_cpu_rebind_imsic_begin(
+        &mut self,
+        _tvm_id: u64,
+        _vcpu_id: u64,
+        _imsic_mask: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Clones the old guest interrupt file of the specified vCPU. Caller must make sure to invoke this from
+    /// old physical CPU. The guest interrupt file after this is free to be reclaimed or bound to another
+    /// vCPU.
+    fn tvm_cpu_rebind_imsic_clone(&mut self, _tvm_id: u64, _vcpu_id: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Completes the rebind process for the specified vCPU from this physical CPU and its guest
+    /// interrupt files. Must be called from the same physical CPU as `rebind_vcpu_imsic_begin`.
+    fn tvm_cpu_rebind_imsic_end(&mut self, _tvm_id: u64, _vcpu_id: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(
+    handler: &mut dyn CoveInterruptHandler,
+    function: CoveInterruptFunction,
+) -> Result<u64> {
+    use CoveInterruptFunction::*;
+    match function {
+        TvmAiaInit {
+            tvm_id,
+            params_addr,
+            len,
+        } => handler.tvm_aia_init(tvm_id, params_addr, len),
+        TvmCpuSetImsicAddr {
+            tvm_id,
+            vcpu_id,
+            imsic_addr,
+        } => handler.tvm_cpu_set_imsic_addr(tvm_id, vcpu_id, imsic_addr),
+        TsmConvertImsic { imsic_addr } => handler.tsm_convert_imsic(imsic_addr),
+        TsmReclaimImsic { imsic_addr } => handler.tsm_reclaim_imsic(imsic_addr),
+        TvmCpuBindImsic {
+            tvm_id,
+            vcpu_id,
+            imsic_mask,
+        } => handler.tvm_cpu_bind_imsic(tvm_id, vcpu_id, imsic_mask),
+        TvmCpuUnbindImsicBegin { tvm_id, vcpu_id } => {
+            handler.tvm_cpu_unbind_imsic_begin(tvm_id, vcpu_id)
+        }
+        TvmCpuUnbindImsicEnd { tvm_id, vcpu_id } => {
+            handler.tvm_cpu_unbind_imsic_end(tvm_id, vcpu_id)
+        }
+        TvmCpuInjectExternalInterrupt {
+            tvm_id,
+            vcpu_id,
+            interrupt_id,
+        } => handler.tvm_cpu_inject_external_interrupt(tvm_id, vcpu_id, interrupt_id),
+        TvmCpuRebindImsicBegin {
+            tvm_id,
+            vcpu_id,
+            imsic_mask,
+        } => handler.tvm_cpu_rebind_imsic_begin(tvm_id, vcpu_id, imsic_mask),
+        TvmCpuRebindImsicClone { tvm_id, vcpu_id } => {
+            handler.tvm_cpu_rebind_imsic_clone(tvm_id, vcpu_id)
+        }
+        TvmCpuRebindImsicEnd { tvm_id, vcpu_id } => {
+            handler.tvm_cpu_rebind_imsic_end(tvm_id, vcpu_id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use CoveInterruptFunction::*;
+
+    impl CoveInterruptHandler for Recorder {
+        fn tvm_aia_init(&mut self, tvm_id: u64, params_addr: u64, len: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveInterrupt(TvmAiaInit {
+                tvm_id,
+                params_addr,
+                len,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_cpu_set_imsic_addr(
+            &mut self,
+            tvm_id: u64,
+            vcpu_id: u64,
+            imsic_addr: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveInterrupt(TvmCpuSetImsicAddr {
+                tvm_id,
+                vcpu_id,
+                imsic_addr,
+            }));
+            Ok(0)
+        }
+
+        fn tsm_convert_imsic(&mut self, imsic_addr: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveInterrupt(TsmConvertImsic { imsic_addr }));
+            Ok(0)
+        }
+
+        fn tsm_reclaim_imsic(&mut self, imsic_addr: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveInterrupt(TsmReclaimImsic { imsic_addr }));
+            Ok(0)
+        }
+
+        fn tvm_cpu_bind_imsic(
+            &mut self,
+            tvm_id: u64,
+            vcpu_id: u64,
+            imsic_mask: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveInterrupt(TvmCpuBindImsic {
+                tvm_id,
+                vcpu_id,
+                imsic_mask,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_cpu_unbind_imsic_begin(&mut self, tvm_id: u64, vcpu_id: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveInterrupt(TvmCpuUnbindImsicBegin {
+                tvm_id,
+                vcpu_id,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_cpu_unbind_imsic_end(&mut self, tvm_id: u64, vcpu_id: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveInterrupt(TvmCpuUnbindImsicEnd {
+                tvm_id,
+                vcpu_id,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_cpu_inject_external_interrupt(
+            &mut self,
+            tvm_id: u64,
+            vcpu_id: u64,
+            interrupt_id: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveInterrupt(TvmCpuInjectExternalInterrupt {
+                tvm_id,
+                vcpu_id,
+                interrupt_id,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_cpu_rebind_imsic_begin(
+            &mut self,
+            tvm_id: u64,
+            vcpu_id: u64,
+            imsic_mask: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveInterrupt(TvmCpuRebindImsicBegin {
+                tvm_id,
+                vcpu_id,
+                imsic_mask,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_cpu_rebind_imsic_clone(&mut self, tvm_id: u64, vcpu_id: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveInterrupt(TvmCpuRebindImsicClone {
+                tvm_id,
+                vcpu_id,
+            }));
+            Ok(0)
+        }
+
+        fn tvm_cpu_rebind_imsic_end(&mut self, tvm_id: u64, vcpu_id: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::CoveInterrupt(TvmCpuRebindImsicEnd {
+                tvm_id,
+                vcpu_id,
+            }));
+            Ok(0)
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{CppcFunction, CppcRegister, Error, Result};
+
+/// Handles calls to the CPPC extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait CppcHandler {
+    /// Checks whether the given CPPC register is implemented. Returns the width of the register
+    /// in bits, or 0 if it isn't implemented.
+    fn probe(&mut self, _reg_id: CppcRegister) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Reads the given CPPC register. On RV32 only the lower 32 bits are returned.
+    fn read(&mut self, _reg_id: CppcRegister) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Reads the upper 32 bits of the given CPPC register on RV32. Always returns 0 on RV64.
+    fn read_hi(&mut self, _reg_id: CppcRegister) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Writes `value` to the given CPPC register.
+    fn write(&mut self, _reg_id: CppcRegister, _value: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn CppcHandler, function: CppcFunction) -> Result<u64> {
+    use CppcFunction::*;
+    match function {
+        Probe { reg_id } => handler.probe(reg_id),
+        Read { reg_id } => handler.read(reg_id),
+        ReadHi { reg_id } => handler.read_hi(reg_id),
+        Write { reg_id, value } => handler.write(reg_id, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use CppcFunction::*;
+
+    impl CppcHandler for Recorder {
+        fn probe(&mut self, reg_id: CppcRegister) -> Result<u64> {
+            self.0 = Some(SbiMessage::Cppc(Probe { reg_id }));
+            Ok(0)
+        }
+
+        fn read(&mut self, reg_id: CppcRegister) -> Result<u64> {
+            self.0 = Some(SbiMessage::Cppc(Read { reg_id }));
+            Ok(0)
+        }
+
+        fn read_hi(&mut self, reg_id: CppcRegister) -> Result<u64> {
+            self.0 = Some(SbiMessage::Cppc(ReadHi { reg_id }));
+            Ok(0)
+        }
+
+        fn write(&mut self, reg_id: CppcRegister, value: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Cppc(Write { reg_id, value }));
+            Ok(0)
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{DbtrFunction, Error, Result};
+
+/// Handles calls to the Debug Triggers extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait DbtrHandler {
+    /// Returns the number of debug triggers on the calling hart that can support the trigger
+    /// configuration in `trig_tdata1`, or the total number of triggers if `trig_tdata1` is 0.
+    fn num_triggers(&mut self, _trig_tdata1: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Registers the array of `DbtrShmemEntry` used by the calling hart. Passing all-ones in both
+    /// `shmem_lo` and `shmem_hi` unregisters the shared memory.
+    fn set_shmem(&mut self, _shmem_lo: u64, _shmem_hi: u64, _flags: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Reads the state and configuration of `trig_count` triggers starting at `trig_idx_base` into
+    /// the shared memory.
+    fn read_triggers(&mut self, _trig_idx_base: u64, _trig_count: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Installs `trig_count` triggers configured in the shared memory, writing the index of each
+    /// installed trigger back to its entry.
+    fn install_triggers(&mut self, _trig_count: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Updates the configuration of `trig_count` already-installed triggers using the indices and
+    /// configurations in the shared memory.
+    fn update_triggers(&mut self, _trig_count: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Uninstalls the triggers selected by `trig_idx_base` and `trig_idx_mask`.
+    fn uninstall_triggers(&mut self, _trig_idx_base: u64, _trig_idx_mask: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Enables the triggers selected by `trig_idx_base` and `trig_idx_mask`.
+    fn enable_triggers(&mut self, _trig_idx_base: u64, _trig_idx_mask: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Disables the triggers selected by `trig_idx_base` and `trig_idx_mask`.
+    fn disable_triggers(&mut self, _trig_idx_base: u64, _trig_idx_mask: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn DbtrHandler, function: DbtrFunction) -> Result<u64> {
+    use DbtrFunction::*;
+    match function {
+        NumTriggers { trig_tdata1 } => handler.num_triggers(trig_tdata1),
+        SetShmem {
+            shmem_lo,
+            shmem_hi,
+            flags,
+        } => handler.set_shmem(shmem_lo, shmem_hi, flags),
+        ReadTriggers {
+            trig_idx_base,
+            trig_count,
+        } => handler.read_triggers(trig_idx_base, trig_count),
+        InstallTriggers { trig_count } => handler.install_triggers(trig_count),
+        UpdateTriggers { trig_count } => handler.update_triggers(trig_count),
+        UninstallTriggers {
+            trig_idx_base,
+            trig_idx_mask,
+        } => handler.uninstall_triggers(trig_idx_base, trig_idx_mask),
+        EnableTriggers {
+            trig_idx_base,
+            trig_idx_mask,
+        } => handler.enable_triggers(trig_idx_base, trig_idx_mask),
+        DisableTriggers {
+            trig_idx_base,
+            trig_idx_mask,
+        } => handler.disable_triggers(trig_idx_base, trig_idx_mask),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use DbtrFunction::*;
+
+    impl DbtrHandler for Recorder {
+        fn num_triggers(&mut self, trig_tdata1: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Dbtr(NumTriggers { trig_tdata1 }));
+            Ok(0)
+        }
+
+        fn set_shmem(&mut self, shmem_lo: u64, shmem_hi: u64, flags: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Dbtr(SetShmem {
+                shmem_lo,
+                shmem_hi,
+                flags,
+            }));
+            Ok(0)
+        }
+
+        fn read_triggers(&mut self, trig_idx_base: u64, trig_count: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Dbtr(ReadTriggers {
+                trig_idx_base,
+                trig_count,
+            }));
+            Ok(0)
+        }
+
+        fn install_triggers(&mut self, trig_count: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Dbtr(InstallTriggers { trig_count }));
+            Ok(0)
+        }
+
+        fn update_triggers(&mut self, trig_count: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Dbtr(UpdateTriggers { trig_count }));
+            Ok(0)
+        }
+
+        fn uninstall_triggers(&mut self, trig_idx_base: u64, trig_idx_mask: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Dbtr(UninstallTriggers {
+                trig_idx_base,
+                trig_idx_mask,
+            }));
+            Ok(0)
+        }
+
+        fn enable_triggers(&mut self, trig_idx_base: u64, trig_idx_mask: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Dbtr(EnableTriggers {
+                trig_idx_base,
+                trig_idx_mask,
+            }));
+            Ok(0)
+        }
+
+        fn disable_triggers(&mut self, trig_idx_base: u64, trig_idx_mask: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Dbtr(DisableTriggers {
+                trig_idx_base,
+                trig_idx_mask,
+            }));
+            Ok(0)
+        }
+    }
+}
@@ -0,0 +1,66 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{DebugConsoleFunction, Error, Result};
+
+/// Handles calls to the Debug Console extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait DebugConsoleHandler {
+    /// Prints the given string to the system console. `crate::read_bytes` reads the string with a
+    /// bound on `len`.
+    fn write(&mut self, _len: u64, _addr: u64, _addr_hi: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Reads from the console.
+    fn read(&mut self, _len: u64, _addr: u64, _addr_hi: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Writes a single byte to the console.
+    fn write_byte(&mut self, _byte: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(
+    handler: &mut dyn DebugConsoleHandler,
+    function: DebugConsoleFunction,
+) -> Result<u64> {
+    use DebugConsoleFunction::*;
+    match function {
+        Write { len, addr, addr_hi } => handler.write(len, addr, addr_hi),
+        Read { len, addr, addr_hi } => handler.read(len, addr, addr_hi),
+        WriteByte { byte } => handler.write_byte(byte),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use DebugConsoleFunction::*;
+
+    impl DebugConsoleHandler for Recorder {
+        fn write(&mut self, len: u64, addr: u64, addr_hi: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::DebugConsole(Write { len, addr, addr_hi }));
+            Ok(0)
+        }
+
+        fn read(&mut self, len: u64, addr: u64, addr_hi: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::DebugConsole(Read { len, addr, addr_hi }));
+            Ok(0)
+        }
+
+        fn write_byte(&mut self, byte: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::DebugConsole(WriteByte { byte }));
+            Ok(0)
+        }
+    }
+}
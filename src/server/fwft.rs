@@ -0,0 +1,72 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use flagset::FlagSet;
+
+use crate::{Error, FwftFeature, FwftFlag, FwftFunction, Result};
+
+/// Handles calls to the Firmware Features extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait FwftHandler {
+    /// Sets the value of the given firmware feature.
+    fn set(
+        &mut self,
+        _feature: FwftFeature,
+        _value: u64,
+        _flags: FlagSet<FwftFlag>,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Returns the current value of the given firmware feature.
+    fn get(&mut self, _feature: FwftFeature) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn FwftHandler, function: FwftFunction) -> Result<u64> {
+    use FwftFunction::*;
+    match function {
+        Set {
+            feature,
+            value,
+            flags,
+        } => handler.set(feature, value, flags),
+        Get { feature } => handler.get(feature),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+    use flagset::FlagSet;
+
+    use FwftFunction::*;
+
+    impl FwftHandler for Recorder {
+        fn set(
+            &mut self,
+            feature: FwftFeature,
+            value: u64,
+            flags: FlagSet<FwftFlag>,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Fwft(Set {
+                feature,
+                value,
+                flags,
+            }));
+            Ok(0)
+        }
+
+        fn get(&mut self, feature: FwftFeature) -> Result<u64> {
+            self.0 = Some(SbiMessage::Fwft(Get { feature }));
+            Ok(0)
+        }
+    }
+}
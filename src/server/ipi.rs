@@ -0,0 +1,48 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, IpiFunction, Result};
+
+/// Handles calls to the IPI extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait IpiHandler {
+    /// Sends an inter-processor interrupt to all the harts defined in `hart_mask` and
+    /// `hart_mask_base`. Interprocessor interrupts manifest at the receiving harts as Supervisor
+    /// Software Interrupts.
+    fn send_ipi(&mut self, _hart_mask: u64, _hart_mask_base: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn IpiHandler, function: IpiFunction) -> Result<u64> {
+    use IpiFunction::*;
+    match function {
+        SendIpi {
+            hart_mask,
+            hart_mask_base,
+        } => handler.send_ipi(hart_mask, hart_mask_base),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use IpiFunction::*;
+
+    impl IpiHandler for Recorder {
+        fn send_ipi(&mut self, hart_mask: u64, hart_mask_base: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Ipi(SendIpi {
+                hart_mask,
+                hart_mask_base,
+            }));
+            Ok(0)
+        }
+    }
+}
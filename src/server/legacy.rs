@@ -0,0 +1,94 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, LegacyFunction, Result};
+
+/// Handles calls to the legacy (v0.1) extensions, including console_putchar.
+///
+/// Legacy functions return a single value in a0. Each method returns that value on success; an
+/// error is passed back as its error code. Methods that aren't implemented return `NotSupported`.
+pub trait LegacyHandler {
+    /// Programs the clock for the next event after `stime_value` time.
+    fn set_timer(&mut self, _stime_value: u64) -> Result<i64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Writes the byte `ch` to the debug console.
+    fn console_putchar(&mut self, _ch: u8) -> Result<i64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Reads a byte from the debug console, or returns `None` (passed back as -1) if there is none.
+    fn console_getchar(&mut self) -> Result<Option<u8>> {
+        Err(Error::NotSupported)
+    }
+
+    /// Clears the pending IPI. Returns 0 if no IPI was pending, a positive value otherwise.
+    fn clear_ipi(&mut self) -> Result<i64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Sends an inter-processor interrupt to the harts in the hart mask at `hart_mask_addr`.
+    fn send_ipi(&mut self, _hart_mask_addr: u64) -> Result<i64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Instructs the harts in the hart mask at `hart_mask_addr` to execute a FENCE.I instruction.
+    fn remote_fence_i(&mut self, _hart_mask_addr: u64) -> Result<i64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Instructs the harts in the hart mask at `hart_mask_addr` to execute one or more SFENCE.VMA
+    /// instructions covering `size` bytes of virtual addresses from `start_addr`.
+    fn remote_sfence_vma(
+        &mut self,
+        _hart_mask_addr: u64,
+        _start_addr: u64,
+        _size: u64,
+    ) -> Result<i64> {
+        Err(Error::NotSupported)
+    }
+
+    /// As `remote_sfence_vma`, for the address space `asid` only.
+    fn remote_sfence_vma_asid(
+        &mut self,
+        _hart_mask_addr: u64,
+        _start_addr: u64,
+        _size: u64,
+        _asid: u64,
+    ) -> Result<i64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Puts all the harts to shutdown state. Only returns on failure.
+    fn shutdown(&mut self) -> Result<i64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn LegacyHandler, function: LegacyFunction) -> Result<i64> {
+    use LegacyFunction::*;
+    match function {
+        SetTimer { stime_value } => handler.set_timer(stime_value),
+        GetChar => handler
+            .console_getchar()
+            .map(|c| c.map_or(-1, |c| c as i64)),
+        ClearIpi => handler.clear_ipi(),
+        SendIpi { hart_mask_addr } => handler.send_ipi(hart_mask_addr),
+        RemoteFenceI { hart_mask_addr } => handler.remote_fence_i(hart_mask_addr),
+        RemoteSfenceVma {
+            hart_mask_addr,
+            start_addr,
+            size,
+        } => handler.remote_sfence_vma(hart_mask_addr, start_addr, size),
+        RemoteSfenceVmaAsid {
+            hart_mask_addr,
+            start_addr,
+            size,
+            asid,
+        } => handler.remote_sfence_vma_asid(hart_mask_addr, start_addr, size, asid),
+        Shutdown => handler.shutdown(),
+    }
+}
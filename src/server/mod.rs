@@ -0,0 +1,758 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    consts::*, BaseFunction, Error, ExperimentalCall, FirmwareSpecificCall, Result, SbiMessage,
+    SbiReturnType,
+};
+
+mod attestation;
+mod base;
+mod cove_guest;
+mod cove_host;
+mod cove_interrupt;
+mod cppc;
+mod dbtr;
+mod debug_console;
+mod fwft;
+mod ipi;
+mod legacy;
+mod mpxy;
+mod nacl;
+mod pmu;
+mod reset;
+mod rfence;
+mod sse;
+mod sta;
+mod state;
+mod susp;
+mod time;
+
+pub use attestation::AttestationHandler;
+pub use base::BaseHandler;
+pub use cove_guest::CoveGuestHandler;
+pub use cove_host::CoveHostHandler;
+pub use cove_interrupt::CoveInterruptHandler;
+pub use cppc::CppcHandler;
+pub use dbtr::DbtrHandler;
+pub use debug_console::DebugConsoleHandler;
+pub use fwft::FwftHandler;
+pub use ipi::IpiHandler;
+pub use legacy::LegacyHandler;
+pub use mpxy::MpxyHandler;
+pub use nacl::NaclHandler;
+pub use pmu::PmuHandler;
+pub use reset::ResetHandler;
+pub use rfence::RfenceHandler;
+pub use sse::SseHandler;
+pub use sta::StaHandler;
+pub use state::HsmHandler;
+pub use susp::SuspendHandler;
+pub use time::TimeHandler;
+
+/// Handles calls to vendor extensions, which are passed on undecoded.
+pub trait VendorHandler {
+    /// Returns true if the vendor extension `eid` is implemented.
+    fn has_extension(&self, eid: u64) -> bool;
+
+    /// Handles a call to one of the implemented vendor extensions, given its a0-a7 register values.
+    fn handle(&mut self, regs: [u64; 8]) -> Result<u64>;
+}
+
+/// Handles calls to experimental extensions, with an EID in
+/// `EXT_EXPERIMENTAL_RANGE_START..=EXT_EXPERIMENTAL_RANGE_END`, which are passed on undecoded.
+pub trait ExperimentalHandler {
+    /// Returns true if the experimental extension `eid` is implemented.
+    fn has_extension(&self, eid: u64) -> bool;
+
+    /// Handles a call to one of the implemented experimental extensions.
+    fn handle(&mut self, call: ExperimentalCall) -> Result<u64>;
+}
+
+/// Handles calls to the firmware-specific extensions of this SBI implementation, with an EID in
+/// `EXT_FIRMWARE_RANGE_START..=EXT_FIRMWARE_RANGE_END`, which are passed on undecoded.
+pub trait FirmwareSpecificHandler {
+    /// Returns true if the firmware-specific extension `eid` is implemented.
+    fn has_extension(&self, eid: u64) -> bool;
+
+    /// Handles a call to one of the implemented firmware-specific extensions. The call's
+    /// `impl_id()` is always the `impl_id` of the server's `SbiServerConfig`.
+    fn handle(&mut self, call: FirmwareSpecificCall) -> Result<u64>;
+}
+
+/// The set of extension handlers behind an `SbiServer`.
+///
+/// Each method returns the handler for an extension, or `None` (the default) if the extension
+/// isn't implemented. A single type usually implements several handler traits and returns itself
+/// from the corresponding methods.
+pub trait SbiHandlers {
+    /// Returns the handler for the Base extension.
+    fn base(&mut self) -> Option<&mut dyn BaseHandler> {
+        None
+    }
+
+    /// Returns the handler for the Hart State Management extension.
+    fn hsm(&mut self) -> Option<&mut dyn HsmHandler> {
+        None
+    }
+
+    /// Returns the handler for the Timer extension.
+    fn time(&mut self) -> Option<&mut dyn TimeHandler> {
+        None
+    }
+
+    /// Returns the handler for the IPI extension.
+    fn ipi(&mut self) -> Option<&mut dyn IpiHandler> {
+        None
+    }
+
+    /// Returns the handler for the Remote Fence extension.
+    fn rfence(&mut self) -> Option<&mut dyn RfenceHandler> {
+        None
+    }
+
+    /// Returns the handler for the System Reset extension.
+    fn reset(&mut self) -> Option<&mut dyn ResetHandler> {
+        None
+    }
+
+    /// Returns the handler for the System Suspend extension.
+    fn suspend(&mut self) -> Option<&mut dyn SuspendHandler> {
+        None
+    }
+
+    /// Returns the handler for the Debug Console extension.
+    fn debug_console(&mut self) -> Option<&mut dyn DebugConsoleHandler> {
+        None
+    }
+
+    /// Returns the handler for the Nested Acceleration extension.
+    fn nacl(&mut self) -> Option<&mut dyn NaclHandler> {
+        None
+    }
+
+    /// Returns the handler for the COVE Host extension.
+    fn cove_host(&mut self) -> Option<&mut dyn CoveHostHandler> {
+        None
+    }
+
+    /// Returns the handler for the COVE Interrupt extension.
+    fn cove_interrupt(&mut self) -> Option<&mut dyn CoveInterruptHandler> {
+        None
+    }
+
+    /// Returns the handler for the COVE Guest extension.
+    fn cove_guest(&mut self) -> Option<&mut dyn CoveGuestHandler> {
+        None
+    }
+
+    /// Returns the handler for the Attestation extension.
+    fn attestation(&mut self) -> Option<&mut dyn AttestationHandler> {
+        None
+    }
+
+    /// Returns the handler for the Performance Monitoring Unit extension.
+    fn pmu(&mut self) -> Option<&mut dyn PmuHandler> {
+        None
+    }
+
+    /// Returns the handler for the CPPC extension.
+    fn cppc(&mut self) -> Option<&mut dyn CppcHandler> {
+        None
+    }
+
+    /// Returns the handler for the Steal-Time Accounting extension.
+    fn sta(&mut self) -> Option<&mut dyn StaHandler> {
+        None
+    }
+
+    /// Returns the handler for the Firmware Features extension.
+    fn fwft(&mut self) -> Option<&mut dyn FwftHandler> {
+        None
+    }
+
+    /// Returns the handler for the Debug Triggers extension.
+    fn dbtr(&mut self) -> Option<&mut dyn DbtrHandler> {
+        None
+    }
+
+    /// Returns the handler for the Supervisor Software Events extension.
+    fn sse(&mut self) -> Option<&mut dyn SseHandler> {
+        None
+    }
+
+    /// Returns the handler for the Message Proxy extension.
+    fn mpxy(&mut self) -> Option<&mut dyn MpxyHandler> {
+        None
+    }
+
+    /// Returns the handler for vendor extensions.
+    fn vendor(&mut self) -> Option<&mut dyn VendorHandler> {
+        None
+    }
+
+    /// Returns the handler for experimental extensions.
+    fn experimental(&mut self) -> Option<&mut dyn ExperimentalHandler> {
+        None
+    }
+
+    /// Returns the handler for the firmware-specific extensions of this SBI implementation.
+    fn firmware_specific(&mut self) -> Option<&mut dyn FirmwareSpecificHandler> {
+        None
+    }
+
+    /// Returns the handler for the legacy (v0.1) extensions.
+    fn legacy(&mut self) -> Option<&mut dyn LegacyHandler> {
+        None
+    }
+}
+
+/// The values an `SbiServer` answers the Base extension with when `SbiHandlers::base()` returns
+/// `None`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SbiServerConfig {
+    /// The implemented version of the SBI specification, with the major version in bits 30:24 and
+    /// the minor version in bits 23:0.
+    pub spec_version: u64,
+    /// The ID of the SBI implementation. Calls to firmware-specific extensions are decoded as those
+    /// of this implementation.
+    pub impl_id: u64,
+    /// The version of the SBI implementation.
+    pub impl_version: u64,
+    /// The value of `mvendorid`.
+    pub mvendorid: u64,
+    /// The value of `marchid`.
+    pub marchid: u64,
+    /// The value of `mimpid`.
+    pub mimpid: u64,
+}
+
+impl SbiServerConfig {
+    // Answers `function` from the configured values.
+    fn base(&self, function: BaseFunction) -> Result<u64> {
+        use BaseFunction::*;
+        match function {
+            GetSpecificationVersion => Ok(self.spec_version),
+            GetImplementationID => Ok(self.impl_id),
+            GetImplementationVersion => Ok(self.impl_version),
+            GetMachineVendorID => Ok(self.mvendorid),
+            GetMachineArchitectureID => Ok(self.marchid),
+            GetMachineImplementationID => Ok(self.mimpid),
+            _ => Err(Error::NotSupported),
+        }
+    }
+}
+
+/// Dispatches SBI calls to the extension handlers in `H`.
+///
+/// The Base extension is always implemented: `ProbeSbiExtension` is answered from the set of
+/// handlers `H` provides, and the other Base functions from the configuration if `H` has no Base
+/// handler. Calls to other extensions without a handler fail with `NotSupported`.
+pub struct SbiServer<H> {
+    handlers: H,
+    config: SbiServerConfig,
+}
+
+impl<H: SbiHandlers> SbiServer<H> {
+    /// Creates a server dispatching to `handlers`.
+    pub fn new(handlers: H, config: SbiServerConfig) -> Self {
+        Self { handlers, config }
+    }
+
+    /// Returns a reference to the handlers.
+    pub fn handlers(&self) -> &H {
+        &self.handlers
+    }
+
+    /// Returns a mutable reference to the handlers.
+    pub fn handlers_mut(&mut self) -> &mut H {
+        &mut self.handlers
+    }
+
+    /// Consumes the server, returning the handlers.
+    pub fn into_handlers(self) -> H {
+        self.handlers
+    }
+
+    /// Decodes and handles the SBI call in the saved a0-a7 registers `regs`, returning the values
+    /// to pass back using the return convention of the called extension. Calls to the legacy
+    /// extensions get a legacy return value even if they fail to decode.
+    pub fn handle(&mut self, regs: &[u64; 8]) -> SbiReturnType {
+        let msg = SbiMessage::from_regs_for_impl(regs, self.config.impl_id);
+        if (EXT_LEGACY_SET_TIMER..=EXT_LEGACY_SHUTDOWN).contains(&regs[7]) {
+            let ret = msg.map_or_else(|e| e.code(), |msg| self.handle_legacy(msg));
+            return SbiReturnType::Legacy(ret as u64);
+        }
+        let result = msg.and_then(|msg| self.handle_message(msg));
+        SbiReturnType::Standard(result.map(|value| value as i64).into())
+    }
+
+    /// Handles the already-decoded SBI call `msg`, returning the value to pass back in a1. Legacy
+    /// calls, which only return a value in a0, must be handled with `handle_legacy` instead.
+    pub fn handle_message(&mut self, msg: SbiMessage) -> Result<u64> {
+        use SbiMessage::*;
+        if let Base(BaseFunction::ProbeSbiExtension(eid)) = msg {
+            return Ok(self.probe(eid) as u64);
+        }
+        let h = &mut self.handlers;
+        match msg {
+            Base(f) => Some(match h.base() {
+                Some(h) => base::dispatch(h, f),
+                None => self.config.base(f),
+            }),
+            HartState(f) => h.hsm().map(|h| state::dispatch(h, f)),
+            Time(f) => h.time().map(|h| time::dispatch(h, f)),
+            Ipi(f) => h.ipi().map(|h| ipi::dispatch(h, f)),
+            Rfence(f) => h.rfence().map(|h| rfence::dispatch(h, f)),
+            Reset(f) => h.reset().map(|h| reset::dispatch(h, f)),
+            Suspend(f) => h.suspend().map(|h| susp::dispatch(h, f)),
+            DebugConsole(f) => h.debug_console().map(|h| debug_console::dispatch(h, f)),
+            Nacl(f) => h.nacl().map(|h| nacl::dispatch(h, f)),
+            CoveHost(f) => h.cove_host().map(|h| cove_host::dispatch(h, f)),
+            CoveInterrupt(f) => h.cove_interrupt().map(|h| cove_interrupt::dispatch(h, f)),
+            CoveGuest(f) => h.cove_guest().map(|h| cove_guest::dispatch(h, f)),
+            Attestation(f) => h.attestation().map(|h| attestation::dispatch(h, f)),
+            Pmu(f) => h.pmu().map(|h| pmu::dispatch(h, f)),
+            Cppc(f) => h.cppc().map(|h| cppc::dispatch(h, f)),
+            Sta(f) => h.sta().map(|h| sta::dispatch(h, f)),
+            Fwft(f) => h.fwft().map(|h| fwft::dispatch(h, f)),
+            Dbtr(f) => h.dbtr().map(|h| dbtr::dispatch(h, f)),
+            Sse(f) => h.sse().map(|h| sse::dispatch(h, f)),
+            Mpxy(f) => h.mpxy().map(|h| mpxy::dispatch(h, f)),
+            Vendor(regs) => h
+                .vendor()
+                .filter(|h| h.has_extension(regs[7]))
+                .map(|h| h.handle(regs)),
+            Experimental(call) => h
+                .experimental()
+                .filter(|h| h.has_extension(call.regs()[7]))
+                .map(|h| h.handle(call)),
+            // Calls made for another implementation aren't ours to handle.
+            FirmwareSpecific(call) if call.impl_id() == self.config.impl_id => h
+                .firmware_specific()
+                .filter(|h| h.has_extension(call.regs()[7]))
+                .map(|h| h.handle(call)),
+            PutChar(_) | Legacy(_) | FirmwareSpecific(_) => None,
+        }
+        .unwrap_or(Err(Error::NotSupported))
+    }
+
+    /// Handles the legacy SBI call `msg`, returning the value to pass back in a0. Fails with the
+    /// error code of `NotSupported` if `msg` isn't a legacy call or there is no legacy handler.
+    pub fn handle_legacy(&mut self, msg: SbiMessage) -> i64 {
+        let result = match (msg, self.handlers.legacy()) {
+            (SbiMessage::PutChar(ch), Some(h)) => h.console_putchar(ch as u8),
+            (SbiMessage::Legacy(f), Some(h)) => legacy::dispatch(h, f),
+            _ => Err(Error::NotSupported),
+        };
+        result.unwrap_or_else(|e| e.code())
+    }
+
+    /// Returns true if the extension `eid` is implemented, as reported by `ProbeSbiExtension`.
+    pub fn probe(&mut self, eid: u64) -> bool {
+        let h = &mut self.handlers;
+        match eid {
+            EXT_BASE => true,
+            EXT_LEGACY_SET_TIMER..=EXT_LEGACY_SHUTDOWN => h.legacy().is_some(),
+            EXT_HART_STATE => h.hsm().is_some(),
+            EXT_TIME => h.time().is_some(),
+            EXT_IPI => h.ipi().is_some(),
+            EXT_RFENCE => h.rfence().is_some(),
+            EXT_RESET => h.reset().is_some(),
+            EXT_SUSP => h.suspend().is_some(),
+            EXT_DBCN => h.debug_console().is_some(),
+            EXT_NACL => h.nacl().is_some(),
+            EXT_COVE_HOST => h.cove_host().is_some(),
+            EXT_COVE_INTERRUPT => h.cove_interrupt().is_some(),
+            EXT_COVE_GUEST => h.cove_guest().is_some(),
+            EXT_ATTESTATION => h.attestation().is_some(),
+            EXT_PMU => h.pmu().is_some(),
+            EXT_CPPC => h.cppc().is_some(),
+            EXT_STA => h.sta().is_some(),
+            EXT_FWFT => h.fwft().is_some(),
+            EXT_DBTR => h.dbtr().is_some(),
+            EXT_SSE => h.sse().is_some(),
+            EXT_MPXY => h.mpxy().is_some(),
+            EXT_VENDOR_RANGE_START..=EXT_VENDOR_RANGE_END => {
+                h.vendor().is_some_and(|h| h.has_extension(eid))
+            }
+            EXT_EXPERIMENTAL_RANGE_START..=EXT_EXPERIMENTAL_RANGE_END => {
+                h.experimental().is_some_and(|h| h.has_extension(eid))
+            }
+            EXT_FIRMWARE_RANGE_START..=EXT_FIRMWARE_RANGE_END => {
+                h.firmware_specific().is_some_and(|h| h.has_extension(eid))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HartState, LegacyFunction, SbiReturn, StateFunction, TimeFunction};
+    use std::vec::Vec;
+
+    pub(super) const TEST_CONFIG: SbiServerConfig = SbiServerConfig {
+        spec_version: 0x0200_0000,
+        impl_id: 7,
+        impl_version: 3,
+        mvendorid: 0x5f0,
+        marchid: 0x8000_0000_0000_0001,
+        mimpid: 0x10,
+    };
+
+    // Returns the messages decoded from the registers of a range of argument patterns for each of
+    // `functions` of the extension `eid`, checking that every function decodes at least once.
+    fn dispatched_messages(eid: u64, functions: &[(u64, &str)]) -> Vec<SbiMessage> {
+        let mut messages = Vec::new();
+        for &(fid, name) in functions {
+            let count = messages.len();
+            messages.extend(crate::tests::arg_patterns().filter_map(|args| {
+                SbiMessage::from_regs(&crate::tests::regs(args, fid, eid)).ok()
+            }));
+            assert!(messages.len() > count, "{name} never decoded");
+        }
+        messages
+    }
+
+    // Records the message each handler method is called for, rebuilt from the method's arguments.
+    // The handler traits are implemented for it in the tests of each extension's module.
+    #[derive(Default)]
+    pub(super) struct Recorder(pub(super) Option<SbiMessage>);
+
+    impl SbiHandlers for Recorder {
+        fn base(&mut self) -> Option<&mut dyn BaseHandler> {
+            Some(self)
+        }
+
+        fn hsm(&mut self) -> Option<&mut dyn HsmHandler> {
+            Some(self)
+        }
+
+        fn time(&mut self) -> Option<&mut dyn TimeHandler> {
+            Some(self)
+        }
+
+        fn ipi(&mut self) -> Option<&mut dyn IpiHandler> {
+            Some(self)
+        }
+
+        fn rfence(&mut self) -> Option<&mut dyn RfenceHandler> {
+            Some(self)
+        }
+
+        fn reset(&mut self) -> Option<&mut dyn ResetHandler> {
+            Some(self)
+        }
+
+        fn suspend(&mut self) -> Option<&mut dyn SuspendHandler> {
+            Some(self)
+        }
+
+        fn debug_console(&mut self) -> Option<&mut dyn DebugConsoleHandler> {
+            Some(self)
+        }
+
+        fn nacl(&mut self) -> Option<&mut dyn NaclHandler> {
+            Some(self)
+        }
+
+        fn cove_host(&mut self) -> Option<&mut dyn CoveHostHandler> {
+            Some(self)
+        }
+
+        fn cove_interrupt(&mut self) -> Option<&mut dyn CoveInterruptHandler> {
+            Some(self)
+        }
+
+        fn cove_guest(&mut self) -> Option<&mut dyn CoveGuestHandler> {
+            Some(self)
+        }
+
+        fn attestation(&mut self) -> Option<&mut dyn AttestationHandler> {
+            Some(self)
+        }
+
+        fn pmu(&mut self) -> Option<&mut dyn PmuHandler> {
+            Some(self)
+        }
+
+        fn cppc(&mut self) -> Option<&mut dyn CppcHandler> {
+            Some(self)
+        }
+
+        fn sta(&mut self) -> Option<&mut dyn StaHandler> {
+            Some(self)
+        }
+
+        fn fwft(&mut self) -> Option<&mut dyn FwftHandler> {
+            Some(self)
+        }
+
+        fn dbtr(&mut self) -> Option<&mut dyn DbtrHandler> {
+            Some(self)
+        }
+
+        fn sse(&mut self) -> Option<&mut dyn SseHandler> {
+            Some(self)
+        }
+
+        fn mpxy(&mut self) -> Option<&mut dyn MpxyHandler> {
+            Some(self)
+        }
+    }
+
+    // Handles the experimental extension `EXT_EXPERIMENTAL_RANGE_START` and the firmware-specific
+    // extension `EXT_FIRMWARE_RANGE_START`, recording the calls made to them.
+    #[derive(Default)]
+    struct RangeHandlers(Option<SbiMessage>);
+
+    impl ExperimentalHandler for RangeHandlers {
+        fn has_extension(&self, eid: u64) -> bool {
+            eid == EXT_EXPERIMENTAL_RANGE_START
+        }
+
+        fn handle(&mut self, call: ExperimentalCall) -> Result<u64> {
+            self.0 = Some(SbiMessage::Experimental(call));
+            Ok(1)
+        }
+    }
+
+    impl FirmwareSpecificHandler for RangeHandlers {
+        fn has_extension(&self, eid: u64) -> bool {
+            eid == EXT_FIRMWARE_RANGE_START
+        }
+
+        fn handle(&mut self, call: FirmwareSpecificCall) -> Result<u64> {
+            self.0 = Some(SbiMessage::FirmwareSpecific(call));
+            Ok(2)
+        }
+    }
+
+    impl SbiHandlers for RangeHandlers {
+        fn experimental(&mut self) -> Option<&mut dyn ExperimentalHandler> {
+            Some(self)
+        }
+
+        fn firmware_specific(&mut self) -> Option<&mut dyn FirmwareSpecificHandler> {
+            Some(self)
+        }
+    }
+
+    #[derive(Default)]
+    struct TestFirmware {
+        next_timer: Option<u64>,
+        console: Vec<u8>,
+    }
+
+    impl BaseHandler for TestFirmware {
+        fn get_implementation_id(&mut self) -> Result<u64> {
+            Ok(42)
+        }
+    }
+
+    impl TimeHandler for TestFirmware {
+        fn set_timer(&mut self, stime_value: u64) -> Result<u64> {
+            self.next_timer = Some(stime_value);
+            Ok(0)
+        }
+    }
+
+    impl HsmHandler for TestFirmware {
+        fn hart_status(&mut self, hart_id: u64) -> Result<u64> {
+            match hart_id {
+                0 => Ok(HartState::Started as u64),
+                _ => Err(Error::InvalidParam),
+            }
+        }
+    }
+
+    impl LegacyHandler for TestFirmware {
+        fn console_putchar(&mut self, ch: u8) -> Result<i64> {
+            self.console.push(ch);
+            Ok(0)
+        }
+
+        fn console_getchar(&mut self) -> Result<Option<u8>> {
+            Ok(self.console.pop())
+        }
+    }
+
+    impl SbiHandlers for TestFirmware {
+        fn base(&mut self) -> Option<&mut dyn BaseHandler> {
+            Some(self)
+        }
+
+        fn time(&mut self) -> Option<&mut dyn TimeHandler> {
+            Some(self)
+        }
+
+        fn hsm(&mut self) -> Option<&mut dyn HsmHandler> {
+            Some(self)
+        }
+
+        fn legacy(&mut self) -> Option<&mut dyn LegacyHandler> {
+            Some(self)
+        }
+    }
+
+    // Implements nothing, leaving the Base extension to the server.
+    struct NoHandlers;
+
+    impl SbiHandlers for NoHandlers {}
+
+    fn standard(ret: SbiReturn) -> SbiReturnType {
+        SbiReturnType::Standard(ret)
+    }
+
+    #[test]
+    fn dispatch() {
+        let mut server = SbiServer::new(TestFirmware::default(), TEST_CONFIG);
+        let ret = server
+            .handle(&SbiMessage::Time(TimeFunction::SetTimer { stime_value: 1234 }).to_regs());
+        assert_eq!(ret, standard(SbiReturn::success(0)));
+        assert_eq!(server.handlers().next_timer, Some(1234));
+
+        let ret = server.handle(&SbiMessage::Base(BaseFunction::GetImplementationID).to_regs());
+        assert_eq!(ret, standard(SbiReturn::success(42)));
+
+        let status = StateFunction::HartStatus { hart_id: 1 };
+        let ret = server.handle(&SbiMessage::HartState(status).to_regs());
+        assert_eq!(ret, standard(Error::InvalidParam.into()));
+    }
+
+    #[test]
+    fn dispatch_arguments() {
+        let mut server = SbiServer::new(Recorder::default(), TEST_CONFIG);
+        for (eid, functions) in crate::tests::EXTENSIONS {
+            for msg in dispatched_messages(*eid, functions) {
+                // ProbeSbiExtension is answered by the server itself.
+                if let SbiMessage::Base(BaseFunction::ProbeSbiExtension(_)) = msg {
+                    continue;
+                }
+                server.handle(&msg.to_regs());
+                assert_eq!(server.handlers_mut().0.take(), Some(msg));
+            }
+        }
+    }
+
+    #[test]
+    fn not_supported() {
+        let mut server = SbiServer::new(TestFirmware::default(), TEST_CONFIG);
+        // Unimplemented method of a registered extension.
+        let ret = server.handle(&SbiMessage::Base(BaseFunction::GetMachineVendorID).to_regs());
+        assert_eq!(ret, standard(Error::NotSupported.into()));
+        // Extension without a handler.
+        let ret = server.handle(&SbiMessage::Reset(crate::ResetFunction::shutdown()).to_regs());
+        assert_eq!(ret, standard(Error::NotSupported.into()));
+        // Unknown function.
+        let ret = server.handle(&[0, 0, 0, 0, 0, 0, 99, EXT_TIME]);
+        assert_eq!(ret, standard(Error::NotSupported.into()));
+    }
+
+    #[test]
+    fn probe() {
+        let mut server = SbiServer::new(TestFirmware::default(), TEST_CONFIG);
+        for (eid, present) in [
+            (EXT_BASE, 1),
+            (EXT_TIME, 1),
+            (EXT_HART_STATE, 1),
+            (EXT_PUT_CHAR, 1),
+            (EXT_IPI, 0),
+            (EXT_PMU, 0),
+            (EXT_VENDOR_RANGE_START, 0),
+            (0x1234_5678, 0),
+        ] {
+            let probe = SbiMessage::Base(BaseFunction::ProbeSbiExtension(eid));
+            let ret = server.handle(&probe.to_regs());
+            assert_eq!(ret, standard(SbiReturn::success(present)), "EID {eid:#x}");
+        }
+    }
+
+    #[test]
+    fn base_defaults() {
+        let mut server = SbiServer::new(NoHandlers, TEST_CONFIG);
+        for (function, value) in [
+            (
+                BaseFunction::GetSpecificationVersion,
+                TEST_CONFIG.spec_version,
+            ),
+            (BaseFunction::GetImplementationID, TEST_CONFIG.impl_id),
+            (
+                BaseFunction::GetImplementationVersion,
+                TEST_CONFIG.impl_version,
+            ),
+            (BaseFunction::GetMachineVendorID, TEST_CONFIG.mvendorid),
+            (BaseFunction::GetMachineArchitectureID, TEST_CONFIG.marchid),
+            (BaseFunction::GetMachineImplementationID, TEST_CONFIG.mimpid),
+            (BaseFunction::ProbeSbiExtension(EXT_BASE), 1),
+            (BaseFunction::ProbeSbiExtension(EXT_PUT_CHAR), 0),
+        ] {
+            let ret = server.handle(&SbiMessage::Base(function).to_regs());
+            assert_eq!(
+                ret,
+                standard(SbiReturn::success(value as i64)),
+                "{function:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn legacy() {
+        let mut server = SbiServer::new(TestFirmware::default(), TEST_CONFIG);
+        let getchar = SbiMessage::Legacy(LegacyFunction::GetChar).to_regs();
+        assert_eq!(server.handle(&getchar), SbiReturnType::Legacy(-1i64 as u64));
+        let ret = server.handle(&SbiMessage::PutChar(b'x' as u64).to_regs());
+        assert_eq!(ret, SbiReturnType::Legacy(0));
+        assert_eq!(server.handlers().console, b"x");
+        assert_eq!(server.handle(&getchar), SbiReturnType::Legacy(b'x' as u64));
+
+        let mut server = SbiServer::new(NoHandlers, TEST_CONFIG);
+        let ret = server.handle(&SbiMessage::PutChar(b'x' as u64).to_regs());
+        assert_eq!(
+            ret,
+            SbiReturnType::Legacy(Error::NotSupported.code() as u64)
+        );
+    }
+
+    #[test]
+    fn experimental_and_firmware_specific() {
+        let mut server = SbiServer::new(RangeHandlers::default(), TEST_CONFIG);
+        for (eid, present) in [
+            (EXT_EXPERIMENTAL_RANGE_START, 1),
+            (EXT_EXPERIMENTAL_RANGE_END, 0),
+            (EXT_FIRMWARE_RANGE_START, 1),
+            (EXT_FIRMWARE_RANGE_END, 0),
+        ] {
+            let probe = SbiMessage::Base(BaseFunction::ProbeSbiExtension(eid));
+            let ret = server.handle(&probe.to_regs());
+            assert_eq!(ret, standard(SbiReturn::success(present)), "EID {eid:#x}");
+        }
+
+        let msg = SbiMessage::experimental([1, 2, 3, 4, 5, 6, 7, EXT_EXPERIMENTAL_RANGE_START]);
+        let msg = msg.unwrap();
+        assert_eq!(
+            server.handle(&msg.to_regs()),
+            standard(SbiReturn::success(1))
+        );
+        assert_eq!(server.handlers_mut().0.take(), Some(msg));
+
+        // Firmware-specific calls are decoded as those of the configured implementation.
+        let regs = [1, 2, 3, 4, 5, 6, 7, EXT_FIRMWARE_RANGE_START];
+        let msg = SbiMessage::firmware_specific(TEST_CONFIG.impl_id, regs).unwrap();
+        assert_eq!(server.handle(&regs), standard(SbiReturn::success(2)));
+        assert_eq!(server.handlers_mut().0.take(), Some(msg));
+        // Calls for another implementation aren't handled.
+        let other = SbiMessage::firmware_specific(TEST_CONFIG.impl_id + 1, regs).unwrap();
+        assert_eq!(server.handle_message(other), Err(Error::NotSupported));
+
+        // Extensions the handlers don't implement.
+        for eid in [EXT_EXPERIMENTAL_RANGE_END, EXT_FIRMWARE_RANGE_END] {
+            let ret = server.handle(&[0, 0, 0, 0, 0, 0, 0, eid]);
+            assert_eq!(ret, standard(Error::NotSupported.into()), "EID {eid:#x}");
+        }
+        assert_eq!(server.handlers().0, None);
+    }
+}
@@ -0,0 +1,206 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, MpxyAttrId, MpxyFunction, Result};
+
+/// Handles calls to the Message Proxy extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait MpxyHandler {
+    /// Returns the size in bytes of the shared memory required by the calling hart. The size is
+    /// always a multiple of 4KiB.
+    fn get_shmem_size(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Registers the shared memory used by the calling hart for message data. Passing all-ones in
+    /// both `shmem_phys_lo` and `shmem_phys_hi` unregisters the shared memory.
+    fn set_shmem(&mut self, _shmem_phys_lo: u64, _shmem_phys_hi: u64, _flags: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Writes an `MpxyChannelIdsHeader` followed by channel IDs, starting at `start_index`, to the
+    /// shared memory.
+    fn get_channel_ids(&mut self, _start_index: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Reads `attr_count` u32 attributes of the channel, starting at `base_attr_id`, into the
+    /// shared memory.
+    fn read_attributes(
+        &mut self,
+        _channel_id: u64,
+        _base_attr_id: MpxyAttrId,
+        _attr_count: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Writes `attr_count` u32 attributes of the channel, starting at `base_attr_id`, from the
+    /// shared memory.
+    fn write_attributes(
+        &mut self,
+        _channel_id: u64,
+        _base_attr_id: MpxyAttrId,
+        _attr_count: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Sends the `msg_data_len` bytes of message data in the shared memory over the channel and
+    /// waits for the response. The response data is written to the shared memory and its length
+    /// returned.
+    fn send_message_with_response(
+        &mut self,
+        _channel_id: u64,
+        _msg_id: u64,
+        _msg_data_len: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Sends the `msg_data_len` bytes of message data in the shared memory over the channel
+    /// without waiting for a response.
+    fn send_message_without_response(
+        &mut self,
+        _channel_id: u64,
+        _msg_id: u64,
+        _msg_data_len: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Writes an `MpxyNotificationHeader` followed by the channel's pending notification event
+    /// data to the shared memory.
+    fn get_notification_events(&mut self, _channel_id: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn MpxyHandler, function: MpxyFunction) -> Result<u64> {
+    use MpxyFunction::*;
+    match function {
+        GetShmemSize => handler.get_shmem_size(),
+        SetShmem {
+            shmem_phys_lo,
+            shmem_phys_hi,
+            flags,
+        } => handler.set_shmem(shmem_phys_lo, shmem_phys_hi, flags),
+        GetChannelIds { start_index } => handler.get_channel_ids(start_index),
+        ReadAttributes {
+            channel_id,
+            base_attr_id,
+            attr_count,
+        } => handler.read_attributes(channel_id, base_attr_id, attr_count),
+        WriteAttributes {
+            channel_id,
+            base_attr_id,
+            attr_count,
+        } => handler.write_attributes(channel_id, base_attr_id, attr_count),
+        SendMessageWithResponse {
+            channel_id,
+            msg_id,
+            msg_data_len,
+        } => handler.send_message_with_response(channel_id, msg_id, msg_data_len),
+        SendMessageWithoutResponse {
+            channel_id,
+            msg_id,
+            msg_data_len,
+        } => handler.send_message_without_response(channel_id, msg_id, msg_data_len),
+        GetNotificationEvents { channel_id } => handler.get_notification_events(channel_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use MpxyFunction::*;
+
+    impl MpxyHandler for Recorder {
+        fn get_shmem_size(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Mpxy(GetShmemSize));
+            Ok(0)
+        }
+
+        fn set_shmem(&mut self, shmem_phys_lo: u64, shmem_phys_hi: u64, flags: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Mpxy(SetShmem {
+                shmem_phys_lo,
+                shmem_phys_hi,
+                flags,
+            }));
+            Ok(0)
+        }
+
+        fn get_channel_ids(&mut self, start_index: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Mpxy(GetChannelIds { start_index }));
+            Ok(0)
+        }
+
+        fn read_attributes(
+            &mut self,
+            channel_id: u64,
+            base_attr_id: MpxyAttrId,
+            attr_count: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Mpxy(ReadAttributes {
+                channel_id,
+                base_attr_id,
+                attr_count,
+            }));
+            Ok(0)
+        }
+
+        fn write_attributes(
+            &mut self,
+            channel_id: u64,
+            base_attr_id: MpxyAttrId,
+            attr_count: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Mpxy(WriteAttributes {
+                channel_id,
+                base_attr_id,
+                attr_count,
+            }));
+            Ok(0)
+        }
+
+        fn send_message_with_response(
+            &mut self,
+            channel_id: u64,
+            msg_id: u64,
+            msg_data_len: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Mpxy(SendMessageWithResponse {
+                channel_id,
+                msg_id,
+                msg_data_len,
+            }));
+            Ok(0)
+        }
+
+        fn send_message_without_response(
+            &mut self,
+            channel_id: u64,
+            msg_id: u64,
+            msg_data_len: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Mpxy(SendMessageWithoutResponse {
+                channel_id,
+                msg_id,
+                msg_data_len,
+            }));
+            Ok(0)
+        }
+
+        fn get_notification_events(&mut self, channel_id: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Mpxy(GetNotificationEvents { channel_id }));
+            Ok(0)
+        }
+    }
+}
@@ -0,0 +1,96 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, NaclFunction, Result};
+
+/// Handles calls to the Nested Acceleration extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait NaclHandler {
+    /// Allows the nested hypervisor to query Nacl features supported by the host hypervisor.
+    /// Features are given in `enum NaclFeature`. Salus doesn't support any of the features
+    /// and only allows the host to read CSRs. Any writes are ignored.
+    fn probe_feature(&mut self, _feature_id: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Registers the nested hypervisor <-> host hypervisor shared memory area for the calling CPU.
+    /// `shmem_pfn` is the base PFN of where the `NaclShmem` struct will be placed in the caller's
+    /// physical address space. The entire range of memory occupied by the `NaclShmem` struct must
+    /// remain accessible to the caller until the `NaclShmem` structure is unregistered by calling
+    /// this function with `shmem_pfn` set to -1. In particular this means that, in the presence of
+    /// the COVE-Host extension, the memory occupied by the `NaclShmem` structure is "pinned" in
+    /// the non-confidential state and cannot be converted.
+    fn set_shmem(&mut self, _shmem_addr: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Synchronizes the HS and VS CSRs marked in `dirty_bitmap` from the `csrs` array and clears
+    /// their dirty bits. Requires the `SyncCSR` feature.
+    fn sync_csr(&mut self, _csr_num: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Processes the pending entries in `hfence_queue` and clears their pending flags. Requires
+    /// the `SyncHfence` feature.
+    fn sync_hfence(&mut self, _entry_index: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Synchronizes all dirty CSRs and pending HFENCEs, then emulates an SRET to enter the guest
+    /// using the state in the scratch space. Doesn't return on success. Requires the `SyncSret`
+    /// feature.
+    fn sync_sret(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn NaclHandler, function: NaclFunction) -> Result<u64> {
+    use NaclFunction::*;
+    match function {
+        ProbeFeature { feature_id } => handler.probe_feature(feature_id),
+        SetShmem { shmem_addr } => handler.set_shmem(shmem_addr),
+        SyncCsr { csr_num } => handler.sync_csr(csr_num),
+        SyncHfence { entry_index } => handler.sync_hfence(entry_index),
+        SyncSret => handler.sync_sret(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use NaclFunction::*;
+
+    impl NaclHandler for Recorder {
+        fn probe_feature(&mut self, feature_id: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Nacl(ProbeFeature { feature_id }));
+            Ok(0)
+        }
+
+        fn set_shmem(&mut self, shmem_addr: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Nacl(SetShmem { shmem_addr }));
+            Ok(0)
+        }
+
+        fn sync_csr(&mut self, csr_num: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Nacl(SyncCsr { csr_num }));
+            Ok(0)
+        }
+
+        fn sync_hfence(&mut self, entry_index: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Nacl(SyncHfence { entry_index }));
+            Ok(0)
+        }
+
+        fn sync_sret(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Nacl(SyncSret));
+            Ok(0)
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    Error, PmuCounterConfigFlags, PmuCounterStartFlags, PmuCounterStopFlags, PmuEventType,
+    PmuFunction, Result,
+};
+
+/// Handles calls to the Performance Monitoring Unit extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait PmuHandler {
+    /// Returns the total number of performance counters (hardware and firmware).
+    fn get_num_counters(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Returns information about the hardware counter `counter_index`.
+    fn get_counter_info(&mut self, _counter_index: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Configures the counters selected by counter_index and counter_mask.
+    /// See the sbi_pmu_counter_config_matching documentation for details.
+    fn configure_matching_counters(
+        &mut self,
+        _counter_index: u64,
+        _counter_mask: u64,
+        _config_flags: PmuCounterConfigFlags,
+        _event_type: PmuEventType,
+        _event_data: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Starts the counters selected by counter_index and counter_mask.
+    /// See the sbi_pmu_counter_start documentation for details.
+    fn start_counters(
+        &mut self,
+        _counter_index: u64,
+        _counter_mask: u64,
+        _start_flags: PmuCounterStartFlags,
+        _initial_value: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Stops the counters selected by counter_index and counter_mask.
+    /// See the sbi_pmu_counter_stop documentation for details.
+    fn stop_counters(
+        &mut self,
+        _counter_index: u64,
+        _counter_mask: u64,
+        _stop_flags: PmuCounterStopFlags,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Returns the current value of the firmware counter `counter_index`.
+    fn read_firmware_counter(&mut self, _counter_index: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Returns the upper 32 bits of the firmware counter `counter_index` on RV32.
+    /// Always returns 0 on RV64.
+    fn read_firmware_counter_hi(&mut self, _counter_index: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Registers the `PmuSnapshotArea` of the calling hart. Passing all-ones in both
+    /// `shmem_phys_lo` and `shmem_phys_hi` disables the snapshot area.
+    fn snapshot_set_shmem(
+        &mut self,
+        _shmem_phys_lo: u64,
+        _shmem_phys_hi: u64,
+        _flags: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Queries whether each event in an array of `num_entries` `PmuEventInfo` entries is
+    /// supported, setting the output bit of each entry.
+    fn event_get_info(
+        &mut self,
+        _shmem_phys_lo: u64,
+        _shmem_phys_hi: u64,
+        _num_entries: u64,
+        _flags: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn PmuHandler, function: PmuFunction) -> Result<u64> {
+    use PmuFunction::*;
+    match function {
+        GetNumCounters => handler.get_num_counters(),
+        GetCounterInfo(counter_index) => handler.get_counter_info(counter_index),
+        ConfigureMatchingCounters {
+            counter_index,
+            counter_mask,
+            config_flags,
+            event_type,
+            event_data,
+        } => handler.configure_matching_counters(
+            counter_index,
+            counter_mask,
+            config_flags,
+            event_type,
+            event_data,
+        ),
+        StartCounters {
+            counter_index,
+            counter_mask,
+            start_flags,
+            initial_value,
+        } => handler.start_counters(counter_index, counter_mask, start_flags, initial_value),
+        StopCounters {
+            counter_index,
+            counter_mask,
+            stop_flags,
+        } => handler.stop_counters(counter_index, counter_mask, stop_flags),
+        ReadFirmwareCounter(counter_index) => handler.read_firmware_counter(counter_index),
+        ReadFirmwareCounterHi(counter_index) => handler.read_firmware_counter_hi(counter_index),
+        SnapshotSetShmem {
+            shmem_phys_lo,
+            shmem_phys_hi,
+            flags,
+        } => handler.snapshot_set_shmem(shmem_phys_lo, shmem_phys_hi, flags),
+        EventGetInfo {
+            shmem_phys_lo,
+            shmem_phys_hi,
+            num_entries,
+            flags,
+        } => handler.event_get_info(shmem_phys_lo, shmem_phys_hi, num_entries, flags),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use PmuFunction::*;
+
+    impl PmuHandler for Recorder {
+        fn get_num_counters(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Pmu(GetNumCounters));
+            Ok(0)
+        }
+
+        fn get_counter_info(&mut self, counter_index: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Pmu(GetCounterInfo(counter_index)));
+            Ok(0)
+        }
+
+        fn configure_matching_counters(
+            &mut self,
+            counter_index: u64,
+            counter_mask: u64,
+            config_flags: PmuCounterConfigFlags,
+            event_type: PmuEventType,
+            event_data: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Pmu(ConfigureMatchingCounters {
+                counter_index,
+                counter_mask,
+                config_flags,
+                event_type,
+                event_data,
+            }));
+            Ok(0)
+        }
+
+        fn start_counters(
+            &mut self,
+            counter_index: u64,
+            counter_mask: u64,
+            start_flags: PmuCounterStartFlags,
+            initial_value: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Pmu(StartCounters {
+                counter_index,
+                counter_mask,
+                start_flags,
+                initial_value,
+            }));
+            Ok(0)
+        }
+
+        fn stop_counters(
+            &mut self,
+            counter_index: u64,
+            counter_mask: u64,
+            stop_flags: PmuCounterStopFlags,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Pmu(StopCounters {
+                counter_index,
+                counter_mask,
+                stop_flags,
+            }));
+            Ok(0)
+        }
+
+        fn read_firmware_counter(&mut self, counter_index: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Pmu(ReadFirmwareCounter(counter_index)));
+            Ok(0)
+        }
+
+        fn read_firmware_counter_hi(&mut self, counter_index: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Pmu(ReadFirmwareCounterHi(counter_index)));
+            Ok(0)
+        }
+
+        fn snapshot_set_shmem(
+            &mut self,
+            shmem_phys_lo: u64,
+            shmem_phys_hi: u64,
+            flags: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Pmu(SnapshotSetShmem {
+                shmem_phys_lo,
+                shmem_phys_hi,
+                flags,
+            }));
+            Ok(0)
+        }
+
+        fn event_get_info(
+            &mut self,
+            shmem_phys_lo: u64,
+            shmem_phys_hi: u64,
+            num_entries: u64,
+            flags: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Pmu(EventGetInfo {
+                shmem_phys_lo,
+                shmem_phys_hi,
+                num_entries,
+                flags,
+            }));
+            Ok(0)
+        }
+    }
+}
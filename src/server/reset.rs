@@ -0,0 +1,40 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, ResetFunction, ResetReason, ResetType, Result};
+
+/// Handles calls to the System Reset extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait ResetHandler {
+    /// Performs a system reset.
+    fn reset(&mut self, _reset_type: ResetType, _reason: ResetReason) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn ResetHandler, function: ResetFunction) -> Result<u64> {
+    use ResetFunction::*;
+    match function {
+        Reset { reset_type, reason } => handler.reset(reset_type, reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use ResetFunction::*;
+
+    impl ResetHandler for Recorder {
+        fn reset(&mut self, reset_type: ResetType, reason: ResetReason) -> Result<u64> {
+            self.0 = Some(SbiMessage::Reset(Reset { reset_type, reason }));
+            Ok(0)
+        }
+    }
+}
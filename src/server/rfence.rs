@@ -0,0 +1,167 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, FenceRange, HartMask, Result, RfenceFunction};
+
+/// Handles calls to the Remote Fence extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait RfenceHandler {
+    /// Instructs the remote harts to execute a FENCE.I instruction.
+    fn remote_fence_i(&mut self, _hart_mask: HartMask) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Instructs the remote harts to execute one or more SFENCE.VMA instructions covering the
+    /// range of virtual addresses in `range`.
+    fn remote_sfence_vma(&mut self, _hart_mask: HartMask, _range: FenceRange) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Instructs the remote harts to execute one or more SFENCE.VMA instructions covering the
+    /// range of virtual addresses in `range`, for the given ASID only.
+    fn remote_sfence_vma_asid(
+        &mut self,
+        _hart_mask: HartMask,
+        _range: FenceRange,
+        _asid: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Instructs the remote harts to execute one or more HFENCE.GVMA instructions covering the
+    /// range of guest physical addresses in `range`, for the given VMID only.
+    fn remote_hfence_gvma_vmid(
+        &mut self,
+        _hart_mask: HartMask,
+        _range: FenceRange,
+        _vmid: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Instructs the remote harts to execute one or more HFENCE.GVMA instructions covering the
+    /// range of guest physical addresses in `range`, for all guests.
+    fn remote_hfence_gvma(&mut self, _hart_mask: HartMask, _range: FenceRange) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Instructs the remote harts to execute one or more HFENCE.VVMA instructions covering the
+    /// range of guest virtual addresses in `range`, for the given ASID and the VMID in the
+    /// current `hgatp`.
+    fn remote_hfence_vvma_asid(
+        &mut self,
+        _hart_mask: HartMask,
+        _range: FenceRange,
+        _asid: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Instructs the remote harts to execute one or more HFENCE.VVMA instructions covering the
+    /// range of guest virtual addresses in `range`, for the VMID in the current `hgatp`.
+    fn remote_hfence_vvma(&mut self, _hart_mask: HartMask, _range: FenceRange) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn RfenceHandler, function: RfenceFunction) -> Result<u64> {
+    use RfenceFunction::*;
+    match function {
+        RemoteFenceI { hart_mask } => handler.remote_fence_i(hart_mask),
+        RemoteSfenceVma { hart_mask, range } => handler.remote_sfence_vma(hart_mask, range),
+        RemoteSfenceVmaAsid {
+            hart_mask,
+            range,
+            asid,
+        } => handler.remote_sfence_vma_asid(hart_mask, range, asid),
+        RemoteHfenceGvmaVmid {
+            hart_mask,
+            range,
+            vmid,
+        } => handler.remote_hfence_gvma_vmid(hart_mask, range, vmid),
+        RemoteHfenceGvma { hart_mask, range } => handler.remote_hfence_gvma(hart_mask, range),
+        RemoteHfenceVvmaAsid {
+            hart_mask,
+            range,
+            asid,
+        } => handler.remote_hfence_vvma_asid(hart_mask, range, asid),
+        RemoteHfenceVvma { hart_mask, range } => handler.remote_hfence_vvma(hart_mask, range),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use RfenceFunction::*;
+
+    impl RfenceHandler for Recorder {
+        fn remote_fence_i(&mut self, hart_mask: HartMask) -> Result<u64> {
+            self.0 = Some(SbiMessage::Rfence(RemoteFenceI { hart_mask }));
+            Ok(0)
+        }
+
+        fn remote_sfence_vma(&mut self, hart_mask: HartMask, range: FenceRange) -> Result<u64> {
+            self.0 = Some(SbiMessage::Rfence(RemoteSfenceVma { hart_mask, range }));
+            Ok(0)
+        }
+
+        fn remote_sfence_vma_asid(
+            &mut self,
+            hart_mask: HartMask,
+            range: FenceRange,
+            asid: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Rfence(RemoteSfenceVmaAsid {
+                hart_mask,
+                range,
+                asid,
+            }));
+            Ok(0)
+        }
+
+        fn remote_hfence_gvma_vmid(
+            &mut self,
+            hart_mask: HartMask,
+            range: FenceRange,
+            vmid: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Rfence(RemoteHfenceGvmaVmid {
+                hart_mask,
+                range,
+                vmid,
+            }));
+            Ok(0)
+        }
+
+        fn remote_hfence_gvma(&mut self, hart_mask: HartMask, range: FenceRange) -> Result<u64> {
+            self.0 = Some(SbiMessage::Rfence(RemoteHfenceGvma { hart_mask, range }));
+            Ok(0)
+        }
+
+        fn remote_hfence_vvma_asid(
+            &mut self,
+            hart_mask: HartMask,
+            range: FenceRange,
+            asid: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Rfence(RemoteHfenceVvmaAsid {
+                hart_mask,
+                range,
+                asid,
+            }));
+            Ok(0)
+        }
+
+        fn remote_hfence_vvma(&mut self, hart_mask: HartMask, range: FenceRange) -> Result<u64> {
+            self.0 = Some(SbiMessage::Rfence(RemoteHfenceVvma { hart_mask, range }));
+            Ok(0)
+        }
+    }
+}
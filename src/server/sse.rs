@@ -0,0 +1,225 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, Result, SseAttrId, SseEventId, SseFunction};
+
+/// Handles calls to the Supervisor Software Events extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait SseHandler {
+    /// Reads `attr_count` attributes of the event, starting at `base_attr_id`, into the array of
+    /// XLEN-wide `unsigned long`s at `output_phys`.
+    fn read_attrs(
+        &mut self,
+        _event_id: SseEventId,
+        _base_attr_id: SseAttrId,
+        _attr_count: u64,
+        _output_phys_lo: u64,
+        _output_phys_hi: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Writes `attr_count` attributes of the event, starting at `base_attr_id`, from the array of
+    /// XLEN-wide `unsigned long`s at `input_phys`.
+    fn write_attrs(
+        &mut self,
+        _event_id: SseEventId,
+        _base_attr_id: SseAttrId,
+        _attr_count: u64,
+        _input_phys_lo: u64,
+        _input_phys_hi: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Registers a handler for the event. The handler is entered at `handler_entry_pc` with a6 set
+    /// to the hart ID and a7 set to `handler_entry_arg`, and must finish with `Complete`.
+    fn register(
+        &mut self,
+        _event_id: SseEventId,
+        _handler_entry_pc: u64,
+        _handler_entry_arg: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Unregisters the handler for the event.
+    fn unregister(&mut self, _event_id: SseEventId) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Enables delivery of the event.
+    fn enable(&mut self, _event_id: SseEventId) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Disables delivery of the event.
+    fn disable(&mut self, _event_id: SseEventId) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Completes handling of the current event and resumes the interrupted context. Doesn't return
+    /// on success.
+    fn complete(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Injects the event on the given hart.
+    fn inject(&mut self, _event_id: SseEventId, _hart_id: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Unmasks delivery of software events on the calling hart.
+    fn hart_unmask(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Masks delivery of software events on the calling hart.
+    fn hart_mask(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn SseHandler, function: SseFunction) -> Result<u64> {
+    use SseFunction::*;
+    match function {
+        ReadAttrs {
+            event_id,
+            base_attr_id,
+            attr_count,
+            output_phys_lo,
+            output_phys_hi,
+        } => handler.read_attrs(
+            event_id,
+            base_attr_id,
+            attr_count,
+            output_phys_lo,
+            output_phys_hi,
+        ),
+        WriteAttrs {
+            event_id,
+            base_attr_id,
+            attr_count,
+            input_phys_lo,
+            input_phys_hi,
+        } => handler.write_attrs(
+            event_id,
+            base_attr_id,
+            attr_count,
+            input_phys_lo,
+            input_phys_hi,
+        ),
+        Register {
+            event_id,
+            handler_entry_pc,
+            handler_entry_arg,
+        } => handler.register(event_id, handler_entry_pc, handler_entry_arg),
+        Unregister { event_id } => handler.unregister(event_id),
+        Enable { event_id } => handler.enable(event_id),
+        Disable { event_id } => handler.disable(event_id),
+        Complete => handler.complete(),
+        Inject { event_id, hart_id } => handler.inject(event_id, hart_id),
+        HartUnmask => handler.hart_unmask(),
+        HartMask => handler.hart_mask(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use SseFunction::*;
+
+    impl SseHandler for Recorder {
+        fn read_attrs(
+            &mut self,
+            event_id: SseEventId,
+            base_attr_id: SseAttrId,
+            attr_count: u64,
+            output_phys_lo: u64,
+            output_phys_hi: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Sse(ReadAttrs {
+                event_id,
+                base_attr_id,
+                attr_count,
+                output_phys_lo,
+                output_phys_hi,
+            }));
+            Ok(0)
+        }
+
+        fn write_attrs(
+            &mut self,
+            event_id: SseEventId,
+            base_attr_id: SseAttrId,
+            attr_count: u64,
+            input_phys_lo: u64,
+            input_phys_hi: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Sse(WriteAttrs {
+                event_id,
+                base_attr_id,
+                attr_count,
+                input_phys_lo,
+                input_phys_hi,
+            }));
+            Ok(0)
+        }
+
+        fn register(
+            &mut self,
+            event_id: SseEventId,
+            handler_entry_pc: u64,
+            handler_entry_arg: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Sse(Register {
+                event_id,
+                handler_entry_pc,
+                handler_entry_arg,
+            }));
+            Ok(0)
+        }
+
+        fn unregister(&mut self, event_id: SseEventId) -> Result<u64> {
+            self.0 = Some(SbiMessage::Sse(Unregister { event_id }));
+            Ok(0)
+        }
+
+        fn enable(&mut self, event_id: SseEventId) -> Result<u64> {
+            self.0 = Some(SbiMessage::Sse(Enable { event_id }));
+            Ok(0)
+        }
+
+        fn disable(&mut self, event_id: SseEventId) -> Result<u64> {
+            self.0 = Some(SbiMessage::Sse(Disable { event_id }));
+            Ok(0)
+        }
+
+        fn complete(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Sse(Complete));
+            Ok(0)
+        }
+
+        fn inject(&mut self, event_id: SseEventId, hart_id: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Sse(Inject { event_id, hart_id }));
+            Ok(0)
+        }
+
+        fn hart_unmask(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Sse(HartUnmask));
+            Ok(0)
+        }
+
+        fn hart_mask(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::Sse(HartMask));
+            Ok(0)
+        }
+    }
+}
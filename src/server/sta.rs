@@ -0,0 +1,49 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, Result, StaFunction};
+
+/// Handles calls to the Steal-Time Accounting extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait StaHandler {
+    /// Registers the `StaShmem` area for the calling virtual hart. The area must be 64-byte
+    /// aligned. Passing all-ones in both `shmem_lo` and `shmem_hi` disables steal-time reporting.
+    fn set_shmem(&mut self, _shmem_lo: u64, _shmem_hi: u64, _flags: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn StaHandler, function: StaFunction) -> Result<u64> {
+    use StaFunction::*;
+    match function {
+        SetShmem {
+            shmem_lo,
+            shmem_hi,
+            flags,
+        } => handler.set_shmem(shmem_lo, shmem_hi, flags),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use StaFunction::*;
+
+    impl StaHandler for Recorder {
+        fn set_shmem(&mut self, shmem_lo: u64, shmem_hi: u64, flags: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Sta(SetShmem {
+                shmem_lo,
+                shmem_hi,
+                flags,
+            }));
+            Ok(0)
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, Result, StateFunction, SuspendType};
+
+/// Handles calls to the Hart State Management extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait HsmHandler {
+    /// Starts the given hart.
+    fn hart_start(&mut self, _hart_id: u64, _start_addr: u64, _opaque: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Stops the current hart.
+    fn hart_stop(&mut self) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Returns the status of the given hart.
+    fn hart_status(&mut self, _hart_id: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+
+    /// Requests that the calling hart be suspended.
+    fn hart_suspend(
+        &mut self,
+        _suspend_type: SuspendType,
+        _resume_addr: u64,
+        _opaque: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn HsmHandler, function: StateFunction) -> Result<u64> {
+    use StateFunction::*;
+    match function {
+        HartStart {
+            hart_id,
+            start_addr,
+            opaque,
+        } => handler.hart_start(hart_id, start_addr, opaque),
+        HartStop => handler.hart_stop(),
+        HartStatus { hart_id } => handler.hart_status(hart_id),
+        HartSuspend {
+            suspend_type,
+            resume_addr,
+            opaque,
+        } => handler.hart_suspend(suspend_type, resume_addr, opaque),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use StateFunction::*;
+
+    impl HsmHandler for Recorder {
+        fn hart_start(&mut self, hart_id: u64, start_addr: u64, opaque: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::HartState(HartStart {
+                hart_id,
+                start_addr,
+                opaque,
+            }));
+            Ok(0)
+        }
+
+        fn hart_stop(&mut self) -> Result<u64> {
+            self.0 = Some(SbiMessage::HartState(HartStop));
+            Ok(0)
+        }
+
+        fn hart_status(&mut self, hart_id: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::HartState(HartStatus { hart_id }));
+            Ok(0)
+        }
+
+        fn hart_suspend(
+            &mut self,
+            suspend_type: SuspendType,
+            resume_addr: u64,
+            opaque: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::HartState(HartSuspend {
+                suspend_type,
+                resume_addr,
+                opaque,
+            }));
+            Ok(0)
+        }
+    }
+}
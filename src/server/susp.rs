@@ -0,0 +1,59 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, Result, SleepType, SuspendFunction};
+
+/// Handles calls to the System Suspend extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait SuspendHandler {
+    /// Requests that the system enter the sleep state given by `sleep_type`. All harts other than
+    /// the calling hart must be stopped or suspended before making this call.
+    fn system_suspend(
+        &mut self,
+        _sleep_type: SleepType,
+        _resume_addr: u64,
+        _opaque: u64,
+    ) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn SuspendHandler, function: SuspendFunction) -> Result<u64> {
+    use SuspendFunction::*;
+    match function {
+        SystemSuspend {
+            sleep_type,
+            resume_addr,
+            opaque,
+        } => handler.system_suspend(sleep_type, resume_addr, opaque),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use SuspendFunction::*;
+
+    impl SuspendHandler for Recorder {
+        fn system_suspend(
+            &mut self,
+            sleep_type: SleepType,
+            resume_addr: u64,
+            opaque: u64,
+        ) -> Result<u64> {
+            self.0 = Some(SbiMessage::Suspend(SystemSuspend {
+                sleep_type,
+                resume_addr,
+                opaque,
+            }));
+            Ok(0)
+        }
+    }
+}
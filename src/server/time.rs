@@ -0,0 +1,41 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, Result, TimeFunction};
+
+/// Handles calls to the Timer extension.
+///
+/// Each method handles the function of the same name and returns the value to pass back in a1.
+/// Methods that aren't implemented return `NotSupported`.
+pub trait TimeHandler {
+    /// Programs the clock for the next event after `stime_value` time. `stime_value` is in absolute
+    /// time. This also clears the pending timer interrupt bit.
+    fn set_timer(&mut self, _stime_value: u64) -> Result<u64> {
+        Err(Error::NotSupported)
+    }
+}
+
+// Calls the method of `handler` that handles `function`.
+pub(super) fn dispatch(handler: &mut dyn TimeHandler, function: TimeFunction) -> Result<u64> {
+    use TimeFunction::*;
+    match function {
+        SetTimer { stime_value } => handler.set_timer(stime_value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::Recorder;
+    use crate::SbiMessage;
+
+    use TimeFunction::*;
+
+    impl TimeHandler for Recorder {
+        fn set_timer(&mut self, stime_value: u64) -> Result<u64> {
+            self.0 = Some(SbiMessage::Time(SetTimer { stime_value }));
+            Ok(0)
+        }
+    }
+}
@@ -0,0 +1,468 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::*;
+use crate::function::*;
+
+/// Software event IDs. Bit 15 of the raw value marks an event as global, meaning it can be routed
+/// to any hart, rather than local to each hart.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SseEventId {
+    /// High priority RAS event, local to each hart.
+    LocalHighPrioRas,
+    /// Double trap event, local to each hart.
+    LocalDoubleTrap,
+    /// High priority RAS event, global.
+    GlobalHighPrioRas,
+    /// PMU counter overflow event, local to each hart.
+    LocalPmuOverflow,
+    /// Low priority RAS event, local to each hart.
+    LocalLowPrioRas,
+    /// Low priority RAS event, global.
+    GlobalLowPrioRas,
+    /// Event injected by software, local to each hart.
+    LocalSoftwareInjected,
+    /// Event injected by software, global.
+    GlobalSoftwareInjected,
+    /// An event ID that isn't defined by the spec and isn't platform-specific.
+    Reserved(ReservedSseEventId),
+    /// A platform-specific event ID. These have bit 14 set within one of the defined 64K blocks of
+    /// event IDs, e.g. `0x00004000..=0x00007FFF` (local) and `0x0000C000..=0x0000FFFF` (global).
+    PlatformSpecific(PlatformSseEventId),
+}
+
+/// A software event ID that isn't defined by the spec and isn't platform-specific. Created with
+/// `SseEventId::reserved`, which checks that the ID isn't one of the others.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ReservedSseEventId(u32);
+
+impl ReservedSseEventId {
+    /// Returns the raw event ID.
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
+/// A platform-specific software event ID. Created with `SseEventId::platform_specific`, which checks
+/// that the ID is in one of the platform-specific ranges.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PlatformSseEventId(u32);
+
+impl PlatformSseEventId {
+    /// Returns the raw event ID.
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
+impl SseEventId {
+    // The upper 16 bits of the blocks of event IDs defined by the spec.
+    const BLOCKS: [u32; 4] = [0x0000, 0x0001, 0x0010, 0xffff];
+    const PLATFORM_BIT: u32 = 1 << 14;
+    const GLOBAL_BIT: u32 = 1 << 15;
+
+    // Returns the event defined by the spec with the given ID, if any.
+    fn defined(id: u32) -> Option<Self> {
+        use SseEventId::*;
+        Some(match id {
+            0x0000_0000 => LocalHighPrioRas,
+            0x0000_0001 => LocalDoubleTrap,
+            0x0000_8000 => GlobalHighPrioRas,
+            0x0001_0000 => LocalPmuOverflow,
+            0x0010_0000 => LocalLowPrioRas,
+            0x0010_8000 => GlobalLowPrioRas,
+            0xffff_0000 => LocalSoftwareInjected,
+            0xffff_8000 => GlobalSoftwareInjected,
+            _ => return None,
+        })
+    }
+
+    // Returns true if `id` is in one of the platform-specific ranges.
+    fn is_platform_id(id: u32) -> bool {
+        Self::BLOCKS.contains(&(id >> 16)) && id & Self::PLATFORM_BIT != 0
+    }
+
+    /// Creates a reserved event ID. Returns `InvalidParam` if `raw` is defined by the spec or is
+    /// platform-specific.
+    pub fn reserved(raw: u32) -> Result<Self> {
+        if Self::defined(raw).is_some() || Self::is_platform_id(raw) {
+            return Err(Error::InvalidParam);
+        }
+        Ok(SseEventId::Reserved(ReservedSseEventId(raw)))
+    }
+
+    /// Creates a platform-specific event ID. Returns `InvalidParam` unless `raw` has bit 14 set
+    /// within one of the defined blocks of event IDs.
+    pub fn platform_specific(raw: u32) -> Result<Self> {
+        if !Self::is_platform_id(raw) {
+            return Err(Error::InvalidParam);
+        }
+        Ok(SseEventId::PlatformSpecific(PlatformSseEventId(raw)))
+    }
+
+    /// Creates an event ID from a register value or returns an error if the value doesn't fit in 32
+    /// bits.
+    pub fn from_reg(reg: u64) -> Result<Self> {
+        let id = u32::try_from(reg).map_err(|_| Error::InvalidParam)?;
+        if let Some(event_id) = Self::defined(id) {
+            Ok(event_id)
+        } else if Self::is_platform_id(id) {
+            Self::platform_specific(id)
+        } else {
+            Self::reserved(id)
+        }
+    }
+
+    /// Returns the raw value of this event ID.
+    pub fn raw(&self) -> u32 {
+        use SseEventId::*;
+        match self {
+            LocalHighPrioRas => 0x0000_0000,
+            LocalDoubleTrap => 0x0000_0001,
+            GlobalHighPrioRas => 0x0000_8000,
+            LocalPmuOverflow => 0x0001_0000,
+            LocalLowPrioRas => 0x0010_0000,
+            GlobalLowPrioRas => 0x0010_8000,
+            LocalSoftwareInjected => 0xffff_0000,
+            GlobalSoftwareInjected => 0xffff_8000,
+            Reserved(id) => id.raw(),
+            PlatformSpecific(id) => id.raw(),
+        }
+    }
+
+    /// Returns true if this event is global rather than local to each hart.
+    pub fn is_global(&self) -> bool {
+        self.raw() & Self::GLOBAL_BIT != 0
+    }
+}
+
+impl SbiReg for SseEventId {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_reg(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.raw() as u64
+    }
+}
+
+/// Software event attribute IDs, used with `ReadAttrs` and `WriteAttrs`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SseAttrId {
+    /// The event's state, pending and injection status. Read-only.
+    Status,
+    /// The event's priority. Writable while the event is registered but not enabled.
+    Priority,
+    /// The event's configuration flags. Writable while the event is registered but not enabled.
+    Config,
+    /// The hart a global event is routed to. Ignored for local events.
+    PreferredHart,
+    /// The handler entry point passed to `Register`. Read-only.
+    EntryPc,
+    /// The handler argument passed to `Register`. Read-only.
+    EntryArg,
+    /// The `sepc` of the interrupted context. Writable only from the event handler.
+    InterruptedSepc,
+    /// The `sstatus`/`hstatus` flags of the interrupted context. Writable only from the event
+    /// handler.
+    InterruptedFlags,
+    /// The `a6` value of the interrupted context. Writable only from the event handler.
+    InterruptedA6,
+    /// The `a7` value of the interrupted context. Writable only from the event handler.
+    InterruptedA7,
+    /// An attribute ID that isn't defined by the spec.
+    Reserved(ReservedSseAttrId),
+}
+
+/// A software event attribute ID that isn't defined by the spec. Created with
+/// `SseAttrId::reserved`, which checks that the ID isn't one of the defined attributes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ReservedSseAttrId(u32);
+
+impl ReservedSseAttrId {
+    /// Returns the raw attribute ID.
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
+impl SseAttrId {
+    // The number of attributes defined by the spec.
+    const DEFINED_COUNT: u32 = 10;
+
+    /// Creates a reserved attribute ID. Returns `InvalidParam` if `raw` is one of the defined
+    /// attributes.
+    pub fn reserved(raw: u32) -> Result<Self> {
+        if raw < Self::DEFINED_COUNT {
+            return Err(Error::InvalidParam);
+        }
+        Ok(SseAttrId::Reserved(ReservedSseAttrId(raw)))
+    }
+
+    /// Creates an attribute ID from a register value or returns an error if the value doesn't fit
+    /// in 32 bits.
+    pub fn from_reg(reg: u64) -> Result<Self> {
+        use SseAttrId::*;
+        let id = u32::try_from(reg).map_err(|_| Error::InvalidParam)?;
+        Ok(match id {
+            0 => Status,
+            1 => Priority,
+            2 => Config,
+            3 => PreferredHart,
+            4 => EntryPc,
+            5 => EntryArg,
+            6 => InterruptedSepc,
+            7 => InterruptedFlags,
+            8 => InterruptedA6,
+            9 => InterruptedA7,
+            _ => return Self::reserved(id),
+        })
+    }
+
+    /// Returns the raw value of this attribute ID.
+    pub fn raw(&self) -> u32 {
+        use SseAttrId::*;
+        match self {
+            Status => 0,
+            Priority => 1,
+            Config => 2,
+            PreferredHart => 3,
+            EntryPc => 4,
+            EntryArg => 5,
+            InterruptedSepc => 6,
+            InterruptedFlags => 7,
+            InterruptedA6 => 8,
+            InterruptedA7 => 9,
+            Reserved(id) => id.raw(),
+        }
+    }
+}
+
+impl SbiReg for SseAttrId {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_reg(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.raw() as u64
+    }
+}
+
+/// Functions for the Supervisor Software Events (SSE) extension
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
+pub enum SseFunction {
+    /// Reads `attr_count` attributes of the event, starting at `base_attr_id`, into the array of
+    /// XLEN-wide `unsigned long`s at `output_phys`.
+    ///
+    /// a6 = 0
+    #[sbi(fid = 0)]
+    ReadAttrs {
+        /// a0 = event ID
+        #[sbi(reg = a0)]
+        event_id: SseEventId,
+        /// a1 = ID of the first attribute to read
+        #[sbi(reg = a1)]
+        base_attr_id: SseAttrId,
+        /// a2 = number of attributes to read
+        #[sbi(reg = a2)]
+        attr_count: u64,
+        /// a3 = lower XLEN bits of the physical address of the output array
+        #[sbi(reg = a3)]
+        output_phys_lo: u64,
+        /// a4 = upper bits of the physical address of the output array (RV32 only)
+        #[sbi(reg = a4)]
+        output_phys_hi: u64,
+    },
+    /// Writes `attr_count` attributes of the event, starting at `base_attr_id`, from the array of
+    /// XLEN-wide `unsigned long`s at `input_phys`.
+    ///
+    /// a6 = 1
+    #[sbi(fid = 1)]
+    WriteAttrs {
+        /// a0 = event ID
+        #[sbi(reg = a0)]
+        event_id: SseEventId,
+        /// a1 = ID of the first attribute to write
+        #[sbi(reg = a1)]
+        base_attr_id: SseAttrId,
+        /// a2 = number of attributes to write
+        #[sbi(reg = a2)]
+        attr_count: u64,
+        /// a3 = lower XLEN bits of the physical address of the input array
+        #[sbi(reg = a3)]
+        input_phys_lo: u64,
+        /// a4 = upper bits of the physical address of the input array (RV32 only)
+        #[sbi(reg = a4)]
+        input_phys_hi: u64,
+    },
+    /// Registers a handler for the event. The handler is entered at `handler_entry_pc` with a6 set
+    /// to the hart ID and a7 set to `handler_entry_arg`, and must finish with `Complete`.
+    ///
+    /// a6 = 2
+    #[sbi(fid = 2)]
+    Register {
+        /// a0 = event ID
+        #[sbi(reg = a0)]
+        event_id: SseEventId,
+        /// a1 = virtual address of the handler entry point
+        #[sbi(reg = a1)]
+        handler_entry_pc: u64,
+        /// a2 = argument passed to the handler in a7
+        #[sbi(reg = a2)]
+        handler_entry_arg: u64,
+    },
+    /// Unregisters the handler for the event.
+    ///
+    /// a6 = 3
+    #[sbi(fid = 3)]
+    Unregister {
+        /// a0 = event ID
+        #[sbi(reg = a0)]
+        event_id: SseEventId,
+    },
+    /// Enables delivery of the event.
+    ///
+    /// a6 = 4
+    #[sbi(fid = 4)]
+    Enable {
+        /// a0 = event ID
+        #[sbi(reg = a0)]
+        event_id: SseEventId,
+    },
+    /// Disables delivery of the event.
+    ///
+    /// a6 = 5
+    #[sbi(fid = 5)]
+    Disable {
+        /// a0 = event ID
+        #[sbi(reg = a0)]
+        event_id: SseEventId,
+    },
+    /// Completes handling of the current event and resumes the interrupted context. Doesn't return
+    /// on success.
+    ///
+    /// a6 = 6
+    #[sbi(fid = 6)]
+    Complete,
+    /// Injects the event on the given hart.
+    ///
+    /// a6 = 7
+    #[sbi(fid = 7)]
+    Inject {
+        /// a0 = event ID
+        #[sbi(reg = a0)]
+        event_id: SseEventId,
+        /// a1 = hart ID to inject the event on (local events only)
+        #[sbi(reg = a1)]
+        hart_id: u64,
+    },
+    /// Unmasks delivery of software events on the calling hart.
+    ///
+    /// a6 = 8
+    #[sbi(fid = 8)]
+    HartUnmask,
+    /// Masks delivery of software events on the calling hart.
+    ///
+    /// a6 = 9
+    #[sbi(fid = 9)]
+    HartMask,
+}
+
+impl SseFunction {
+    /// Returns the event ID this function operates on, if any.
+    pub fn event_id(&self) -> Option<SseEventId> {
+        use SseFunction::*;
+        match self {
+            ReadAttrs { event_id, .. }
+            | WriteAttrs { event_id, .. }
+            | Register { event_id, .. }
+            | Unregister { event_id }
+            | Enable { event_id }
+            | Disable { event_id }
+            | Inject { event_id, .. } => Some(*event_id),
+            Complete | HartUnmask | HartMask => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_id_ranges() {
+        use SseEventId::*;
+        let reserved = |raw| SseEventId::reserved(raw).unwrap();
+        let platform = |raw| SseEventId::platform_specific(raw).unwrap();
+        for (reg, expected, global) in [
+            (0x0000_0000, LocalHighPrioRas, false),
+            (0x0000_0001, LocalDoubleTrap, false),
+            (0x0000_0002, reserved(0x0000_0002), false),
+            (0x0000_3fff, reserved(0x0000_3fff), false),
+            (0x0000_4000, platform(0x0000_4000), false),
+            (0x0000_7fff, platform(0x0000_7fff), false),
+            (0x0000_8000, GlobalHighPrioRas, true),
+            (0x0000_bfff, reserved(0x0000_bfff), true),
+            (0x0000_c000, platform(0x0000_c000), true),
+            (0x0001_0000, LocalPmuOverflow, false),
+            (0x0001_4000, platform(0x0001_4000), false),
+            (0x0010_0000, LocalLowPrioRas, false),
+            (0x0010_8000, GlobalLowPrioRas, true),
+            (0x0010_ffff, platform(0x0010_ffff), true),
+            (0xffff_0000, LocalSoftwareInjected, false),
+            (0xffff_8000, GlobalSoftwareInjected, true),
+            (0xffff_c000, platform(0xffff_c000), true),
+            // Bit 14 is only platform-specific within the defined blocks.
+            (0x0002_4000, reserved(0x0002_4000), false),
+            (0xfffe_c000, reserved(0xfffe_c000), true),
+        ] {
+            assert_eq!(SseEventId::from_reg(reg), Ok(expected), "{reg:#x}");
+            assert_eq!(expected.raw() as u64, reg);
+            assert_eq!(expected.is_global(), global, "{reg:#x}");
+        }
+        assert_eq!(
+            SseEventId::from_reg(0x1_0000_0000),
+            Err(Error::InvalidParam)
+        );
+    }
+
+    #[test]
+    fn constructed_ids_are_checked() {
+        // IDs that would decode as a defined or platform-specific event can't be wrapped as
+        // reserved ones, and vice versa.
+        for raw in [
+            0x0000_0000,
+            0x0000_0001,
+            0x0000_4000,
+            0xffff_8000,
+            0xffff_c000,
+        ] {
+            assert_eq!(
+                SseEventId::reserved(raw),
+                Err(Error::InvalidParam),
+                "{raw:#x}"
+            );
+        }
+        for raw in [0x0000_0001, 0x0000_8000, 0x0000_3fff, 0x0002_4000] {
+            assert_eq!(
+                SseEventId::platform_specific(raw),
+                Err(Error::InvalidParam),
+                "{raw:#x}"
+            );
+        }
+        for raw in [0, 9] {
+            assert_eq!(SseAttrId::reserved(raw), Err(Error::InvalidParam));
+        }
+
+        // Constructed IDs decode back to themselves.
+        for event_id in [
+            SseEventId::reserved(0x0000_0002).unwrap(),
+            SseEventId::platform_specific(0x0001_4000).unwrap(),
+        ] {
+            assert_eq!(SseEventId::from_reg(event_id.raw() as u64), Ok(event_id));
+        }
+        let attr_id = SseAttrId::reserved(10).unwrap();
+        assert_eq!(SseAttrId::from_reg(attr_id.raw() as u64), Ok(attr_id));
+    }
+}
@@ -0,0 +1,69 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use static_assertions::const_assert;
+
+use crate::function::*;
+
+/// Size in bytes of the `StaShmem` structure.
+pub const STA_SHMEM_SIZE: usize = 64;
+
+/// Layout of the steal-time accounting shared memory area registered with `SetShmem`.
+///
+/// The SBI implementation increments `sequence` before and after updating the structure, so an
+/// odd value means an update is in progress. Readers should use `api::sta::StaShmemRef` to get a
+/// consistent view.
+#[repr(C, align(64))]
+pub struct StaShmem {
+    /// Sequence counter, odd while the SBI implementation is updating the structure.
+    pub sequence: u32,
+    /// Always zero. Reserved for future use.
+    pub flags: u32,
+    /// Amount of time in which this virtual hart was not idle and scheduled out, in units of the
+    /// `time` CSR.
+    pub steal: u64,
+    /// Non-zero if this virtual hart is currently not idle and scheduled out.
+    pub preempted: u8,
+    _pad: [u8; 47],
+}
+
+impl Default for StaShmem {
+    fn default() -> Self {
+        Self {
+            sequence: 0,
+            flags: 0,
+            steal: 0,
+            preempted: 0,
+            _pad: [0; 47],
+        }
+    }
+}
+
+fn _assert_sta_shmem_layout() {
+    const_assert!(core::mem::size_of::<StaShmem>() == STA_SHMEM_SIZE);
+    const_assert!(core::mem::offset_of!(StaShmem, flags) == 4);
+    const_assert!(core::mem::offset_of!(StaShmem, steal) == 8);
+    const_assert!(core::mem::offset_of!(StaShmem, preempted) == 16);
+}
+
+/// Functions for the Steal-Time Accounting (STA) extension
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
+pub enum StaFunction {
+    /// Registers the `StaShmem` area for the calling virtual hart. The area must be 64-byte
+    /// aligned. Passing all-ones in both `shmem_lo` and `shmem_hi` disables steal-time reporting.
+    ///
+    /// a6 = 0
+    #[sbi(fid = 0)]
+    SetShmem {
+        /// a0 = lower XLEN bits of the physical address of the shared memory
+        #[sbi(reg = a0)]
+        shmem_lo: u64,
+        /// a1 = upper bits of the physical address of the shared memory (RV32 only)
+        #[sbi(reg = a1)]
+        shmem_hi: u64,
+        /// a2 = flags, must be 0
+        #[sbi(reg = a2)]
+        flags: u64,
+    },
+}
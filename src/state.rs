@@ -6,37 +6,49 @@ use crate::error::*;
 use crate::function::*;
 
 /// Functions defined for the State extension
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, SbiFunction)]
 pub enum StateFunction {
     /// Starts the given hart.
+    #[sbi(fid = 0)]
     HartStart {
         /// a0 - hart id to start.
+        #[sbi(reg = a0)]
         hart_id: u64,
         /// a1 - address to start the hart.
+        #[sbi(reg = a1)]
         start_addr: u64,
         /// a2 - value to be set in a1 when starting the hart.
+        #[sbi(reg = a2)]
         opaque: u64,
     },
     /// Stops the current hart.
+    #[sbi(fid = 1)]
     HartStop,
     /// Returns the status of the given hart.
+    #[sbi(fid = 2)]
     HartStatus {
         /// a0 - ID of the hart to check.
+        #[sbi(reg = a0)]
         hart_id: u64,
     },
     /// Requests that the calling hart be suspended.
+    #[sbi(fid = 3)]
     HartSuspend {
         /// a0 - Specifies the type of suspend to initiate.
-        suspend_type: u32,
+        #[sbi(reg = a0)]
+        suspend_type: SuspendType,
         /// a1 - The address to jump to on resume.
+        #[sbi(reg = a1)]
         resume_addr: u64,
         /// a2 - An opaque value to load in a1 when resuming the hart.
+        #[sbi(reg = a2)]
         opaque: u64,
     },
 }
 
 /// Return value for the HartStatus SBI call.
 #[repr(u64)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum HartState {
     /// The hart is physically powered-up and executing normally.
     Started = 0,
@@ -55,88 +67,138 @@ pub enum HartState {
     ResumePending = 6,
 }
 
-impl StateFunction {
-    /// Attempts to parse `Self` from the passed in `a0-a7`.
-    pub(crate) fn from_regs(args: &[u64]) -> Result<Self> {
-        use StateFunction::*;
-        match args[6] {
-            0 => Ok(HartStart {
-                hart_id: args[0],
-                start_addr: args[1],
-                opaque: args[2],
-            }),
-            1 => Ok(HartStop),
-            2 => Ok(HartStatus { hart_id: args[0] }),
-            3 => Ok(HartSuspend {
-                suspend_type: args[0] as u32,
-                resume_addr: args[1],
-                opaque: args[2],
-            }),
-            _ => Err(Error::NotSupported),
-        }
+impl HartState {
+    /// Creates a hart state from the value returned by `HartStatus` or returns an error if no
+    /// mapping is known for the given value.
+    pub fn from_reg(reg: u64) -> Result<Self> {
+        use HartState::*;
+        Ok(match reg {
+            0 => Started,
+            1 => Stopped,
+            2 => StartPending,
+            3 => StopPending,
+            4 => Suspended,
+            5 => SuspendPending,
+            6 => ResumePending,
+            _ => return Err(Error::InvalidParam),
+        })
     }
 }
 
-impl SbiFunction for StateFunction {
-    fn a6(&self) -> u64 {
-        use StateFunction::*;
-        match self {
-            HartStart { .. } => 0,
-            HartStop => 1,
-            HartStatus { .. } => 2,
-            HartSuspend { .. } => 3,
+/// The types of suspend a hart can request with `HartSuspend`.
+///
+/// Retentive suspends preserve the hart's register and CSR state and return from `HartSuspend`
+/// on resume. Non-retentive suspends lose that state and resume at the `resume_addr` passed to
+/// `HartSuspend`.
+///
+/// The value is checked when the suspend type is created, so a `SuspendType` is always in one of
+/// the ranges defined by the SBI spec.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SuspendType(u32);
+
+impl SuspendType {
+    /// The default retentive suspend (0x00000000).
+    pub const DEFAULT_RETENTIVE: Self = Self(0);
+
+    /// The default non-retentive suspend (0x80000000).
+    pub const DEFAULT_NON_RETENTIVE: Self = Self(0x8000_0000);
+
+    /// Creates a platform-specific retentive suspend type. Returns `InvalidParam` unless `raw` is
+    /// in the range `0x10000000..=0x7FFFFFFF`.
+    pub fn platform_retentive(raw: u32) -> Result<Self> {
+        match raw {
+            0x1000_0000..=0x7fff_ffff => Ok(Self(raw)),
+            _ => Err(Error::InvalidParam),
         }
     }
 
-    fn a0(&self) -> u64 {
-        use StateFunction::*;
-        match self {
-            HartStart {
-                hart_id,
-                start_addr: _,
-                opaque: _,
-            } => *hart_id,
-            HartStatus { hart_id } => *hart_id,
-            HartSuspend {
-                suspend_type,
-                resume_addr: _,
-                opaque: _,
-            } => *suspend_type as u64,
-            _ => 0,
+    /// Creates a platform-specific non-retentive suspend type. Returns `InvalidParam` unless `raw`
+    /// is in the range `0x90000000..=0xFFFFFFFF`.
+    pub fn platform_non_retentive(raw: u32) -> Result<Self> {
+        match raw {
+            0x9000_0000..=0xffff_ffff => Ok(Self(raw)),
+            _ => Err(Error::InvalidParam),
         }
     }
 
-    fn a1(&self) -> u64 {
-        use StateFunction::*;
-        match self {
-            HartStart {
-                hart_id: _,
-                start_addr,
-                opaque: _,
-            } => *start_addr,
-            HartSuspend {
-                suspend_type: _,
-                resume_addr,
-                opaque: _,
-            } => *resume_addr,
-            _ => 0,
+    /// Creates a suspend type from the a0 register value or returns an error if the value is in one
+    /// of the reserved ranges.
+    pub fn from_reg(reg: u64) -> Result<Self> {
+        let raw = u32::try_from(reg).map_err(|_| Error::InvalidParam)?;
+        match raw {
+            0 => Ok(Self::DEFAULT_RETENTIVE),
+            0x8000_0000 => Ok(Self::DEFAULT_NON_RETENTIVE),
+            1..=0x7fff_ffff => Self::platform_retentive(raw),
+            _ => Self::platform_non_retentive(raw),
         }
     }
 
-    fn a2(&self) -> u64 {
-        use StateFunction::*;
-        match self {
-            HartStart {
-                hart_id: _,
-                start_addr: _,
-                opaque,
-            } => *opaque,
-            HartSuspend {
-                suspend_type: _,
-                resume_addr: _,
-                opaque,
-            } => *opaque,
-            _ => 0,
+    /// Returns the raw value of this suspend type.
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns true if the hart's state is preserved across this type of suspend.
+    pub fn is_retentive(&self) -> bool {
+        self.0 & 0x8000_0000 == 0
+    }
+
+    /// Returns true if this is a platform-specific suspend type rather than one of the defaults.
+    pub fn is_platform_specific(&self) -> bool {
+        self.0 & 0x7fff_ffff != 0
+    }
+}
+
+impl SbiReg for SuspendType {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_reg(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.raw() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suspend_type_ranges() {
+        for (reg, retentive) in [
+            (0x0000_0000, true),
+            (0x1000_0000, true),
+            (0x7fff_ffff, true),
+            (0x8000_0000, false),
+            (0x9000_0000, false),
+            (0xffff_ffff, false),
+        ] {
+            let suspend_type = SuspendType::from_reg(reg).unwrap();
+            assert_eq!(suspend_type.raw() as u64, reg);
+            assert_eq!(suspend_type.is_retentive(), retentive, "{reg:#x}");
         }
+        for reg in [0x1, 0x0fff_ffff, 0x8000_0001, 0x8fff_ffff, 0x1_0000_0000] {
+            assert_eq!(
+                SuspendType::from_reg(reg),
+                Err(Error::InvalidParam),
+                "{reg:#x}"
+            );
+        }
+
+        assert!(SuspendType::platform_retentive(0x1000_0000).is_ok());
+        // Non-retentive values can't be passed off as retentive, or vice versa.
+        assert_eq!(
+            SuspendType::platform_retentive(0x9000_0000),
+            Err(Error::InvalidParam)
+        );
+        assert_eq!(
+            SuspendType::platform_non_retentive(0x1000_0000),
+            Err(Error::InvalidParam)
+        );
+        assert_eq!(SuspendType::platform_retentive(0), Err(Error::InvalidParam));
+        assert!(!SuspendType::DEFAULT_NON_RETENTIVE.is_platform_specific());
+        assert!(SuspendType::platform_non_retentive(0x9000_0000)
+            .unwrap()
+            .is_platform_specific());
     }
 }
@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::*;
+use crate::function::*;
+
+/// Functions for the System Suspend extension
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
+pub enum SuspendFunction {
+    /// Requests that the system enter the sleep state given by `sleep_type`. All harts other than
+    /// the calling hart must be stopped or suspended before making this call.
+    ///
+    /// On success, the calling hart resumes execution at `resume_addr` in supervisor mode with
+    /// the MMU off, the hart ID in a0 and `opaque` in a1, once the system wakes up. This call only
+    /// returns on failure.
+    ///
+    /// a6 = 0
+    #[sbi(fid = 0)]
+    SystemSuspend {
+        /// a0 = the type of sleep to enter
+        #[sbi(reg = a0)]
+        sleep_type: SleepType,
+        /// a1 = the physical address to resume at
+        #[sbi(reg = a1)]
+        resume_addr: u64,
+        /// a2 = an opaque value to load in a1 on resume
+        #[sbi(reg = a2)]
+        opaque: u64,
+    },
+}
+
+/// The types of sleep a supervisor can request with `SystemSuspend`.
+///
+/// The value is checked when the sleep type is created, so a `SleepType` is always in the range
+/// it was created for and encodes to the request the caller named.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SleepType(u32);
+
+impl SleepType {
+    /// Suspend-to-RAM (0x00000000).
+    pub const SUSPEND_TO_RAM: Self = Self(0);
+
+    /// Creates a sleep type in the reserved range `0x00000001..=0x7FFFFFFF`. Implementations must
+    /// reject these with `InvalidParam`. Returns `InvalidParam` if `raw` isn't in that range.
+    pub fn reserved(raw: u32) -> Result<Self> {
+        match raw {
+            0x1..=0x7fff_ffff => Ok(Self(raw)),
+            _ => Err(Error::InvalidParam),
+        }
+    }
+
+    /// Creates a platform-specific sleep type. Returns `InvalidParam` unless `raw` is in the range
+    /// `0x80000000..=0xFFFFFFFF`.
+    pub fn platform_specific(raw: u32) -> Result<Self> {
+        match raw {
+            0x8000_0000..=0xffff_ffff => Ok(Self(raw)),
+            _ => Err(Error::InvalidParam),
+        }
+    }
+
+    /// Creates a sleep type from the a0 register value or returns an error if the value doesn't fit
+    /// in 32 bits.
+    pub fn from_reg(reg: u64) -> Result<Self> {
+        let raw = u32::try_from(reg).map_err(|_| Error::InvalidParam)?;
+        Ok(Self(raw))
+    }
+
+    /// Returns the raw value of this sleep type.
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns true if this sleep type is in the reserved range.
+    pub fn is_reserved(&self) -> bool {
+        (0x1..=0x7fff_ffff).contains(&self.0)
+    }
+
+    /// Returns true if this is a platform-specific sleep type.
+    pub fn is_platform_specific(&self) -> bool {
+        self.0 & 0x8000_0000 != 0
+    }
+}
+
+impl SbiReg for SleepType {
+    fn from_reg(reg: u64) -> Result<Self> {
+        Self::from_reg(reg)
+    }
+
+    fn to_reg(&self) -> u64 {
+        self.raw() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleep_type_ranges() {
+        for (reg, reserved, platform_specific) in [
+            (0x0000_0000, false, false),
+            (0x0000_0001, true, false),
+            (0x7fff_ffff, true, false),
+            (0x8000_0000, false, true),
+            (0xffff_ffff, false, true),
+        ] {
+            let sleep_type = SleepType::from_reg(reg).unwrap();
+            assert_eq!(sleep_type.raw() as u64, reg);
+            assert_eq!(sleep_type.is_reserved(), reserved, "{reg:#x}");
+            assert_eq!(
+                sleep_type.is_platform_specific(),
+                platform_specific,
+                "{reg:#x}"
+            );
+        }
+        assert_eq!(SleepType::from_reg(0), Ok(SleepType::SUSPEND_TO_RAM));
+        assert_eq!(SleepType::from_reg(0x1_0000_0000), Err(Error::InvalidParam));
+
+        // A reserved or platform-specific sleep type can't be created with a value that would
+        // encode to a different request.
+        assert_eq!(SleepType::reserved(0), Err(Error::InvalidParam));
+        assert_eq!(SleepType::reserved(0x8000_0000), Err(Error::InvalidParam));
+        assert_eq!(SleepType::platform_specific(0), Err(Error::InvalidParam));
+        assert_eq!(SleepType::platform_specific(1), Err(Error::InvalidParam));
+        assert_eq!(SleepType::reserved(1).map(|t| t.raw()), Ok(1));
+        assert_eq!(
+            SleepType::platform_specific(0x8000_0000).map(|t| t.raw()),
+            Ok(0x8000_0000)
+        );
+    }
+}
@@ -0,0 +1,25 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::function::*;
+
+/// Functions for the Timer extension
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SbiFunction)]
+pub enum TimeFunction {
+    /// Programs the clock for the next event after `stime_value` time. `stime_value` is in absolute
+    /// time. This also clears the pending timer interrupt bit.
+    ///
+    /// To clear the timer interrupt without scheduling the next event, either request a timer
+    /// interrupt infinitely far into the future (`u64::MAX`) or mask the timer interrupt by
+    /// clearing `sie.STIE`.
+    ///
+    /// a6 = 0
+    #[sbi(fid = 0)]
+    SetTimer {
+        /// a0 = absolute time of the next timer event. On RV32, a0 holds the lower 32 bits and a1
+        /// holds the upper 32 bits.
+        #[sbi(regs(a0, a1))]
+        stime_value: u64,
+    },
+}
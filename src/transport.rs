@@ -0,0 +1,153 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Result, SbiMessage, SbiReturn};
+
+#[cfg(all(target_arch = "riscv64", target_os = "none"))]
+use core::arch::asm;
+
+/// A way of delivering SBI calls to the firmware or hypervisor. `ecall_send_with` makes a call
+/// through a given transport; `ecall_send` and the functions in `api` use `Ecall` on bare-metal
+/// RISC-V targets.
+///
+/// Other transports let the `api` layer run somewhere other than in supervisor mode, for example
+/// against a fake firmware in host tests (see `override_transport`).
+pub trait SbiTransport {
+    /// Makes the SBI call for `msg` and returns the raw a0 and a1 values, as `error_code` and
+    /// `return_value` respectively.
+    ///
+    /// # Safety
+    ///
+    /// Same as `ecall_send`: any memory referenced by `msg` must obey Rust's memory safety rules
+    /// for the accesses the call makes.
+    unsafe fn ecall(&self, msg: &SbiMessage) -> SbiReturn;
+}
+
+/// Makes SBI calls with the `ecall` instruction. This is the transport `ecall_send` uses on
+/// bare-metal RISC-V targets.
+#[cfg(all(target_arch = "riscv64", target_os = "none"))]
+pub struct Ecall;
+
+#[cfg(all(target_arch = "riscv64", target_os = "none"))]
+impl SbiTransport for Ecall {
+    #[inline(always)]
+    unsafe fn ecall(&self, msg: &SbiMessage) -> SbiReturn {
+        // normally error code
+        let mut a0;
+        // normally return value
+        let mut a1;
+        asm!("ecall", inlateout("a0") msg.a0()=>a0, inlateout("a1")msg.a1()=>a1,
+                    in("a2")msg.a2(), in("a3") msg.a3(),
+                    in("a4")msg.a4(), in("a5") msg.a5(),
+                    in("a6")msg.a6(), in("a7") msg.a7(), options(nostack));
+        SbiReturn {
+            error_code: a0,
+            return_value: a1,
+        }
+    }
+}
+
+/// Sends `msg` through `transport` and interprets the result as `ecall_send` does.
+///
+/// # Safety
+///
+/// Same as `ecall_send`.
+#[inline(always)]
+pub unsafe fn ecall_send_with<T>(transport: &impl SbiTransport, msg: &SbiMessage) -> Result<T>
+where
+    Result<T>: From<SbiReturn>,
+{
+    let ret = transport.ecall(msg);
+    msg.result(ret.error_code, ret.return_value)
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+mod thread_override {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::SbiTransport;
+
+    std::thread_local! {
+        static OVERRIDE: RefCell<Option<Rc<dyn SbiTransport>>> = const { RefCell::new(None) };
+    }
+
+    /// Routes the SBI calls made with `ecall_send` by the calling thread to `transport` until the
+    /// returned guard is dropped, at which point the previous override, if any, is restored.
+    /// Overrides must be dropped in the reverse order they were made.
+    pub fn override_transport(transport: Rc<dyn SbiTransport>) -> TransportOverride {
+        let previous = OVERRIDE.with(|current| current.borrow_mut().replace(transport));
+        TransportOverride { previous }
+    }
+
+    /// Restores the previous transport of the thread when dropped. See `override_transport`.
+    pub struct TransportOverride {
+        previous: Option<Rc<dyn SbiTransport>>,
+    }
+
+    impl Drop for TransportOverride {
+        fn drop(&mut self) {
+            OVERRIDE.with(|current| *current.borrow_mut() = self.previous.take());
+        }
+    }
+
+    // Returns the transport overriding `ecall_send` on the calling thread, if any.
+    pub(crate) fn current() -> Option<Rc<dyn SbiTransport>> {
+        OVERRIDE.with(|current| current.borrow().clone())
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+pub use thread_override::{override_transport, TransportOverride};
+
+// Makes the SBI call for `msg` on targets that can't make ecalls, through the override of the
+// calling thread.
+#[cfg(not(all(target_arch = "riscv64", target_os = "none")))]
+pub(crate) unsafe fn host_ecall(msg: &SbiMessage) -> SbiReturn {
+    #[cfg(any(test, feature = "test-utils"))]
+    if let Some(transport) = thread_override::current() {
+        return transport.ecall(msg);
+    }
+    panic!("ecall_send called without an SBI transport: {msg:?}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecall_send;
+    use std::rc::Rc;
+
+    struct FixedReply(i64);
+
+    impl SbiTransport for FixedReply {
+        unsafe fn ecall(&self, _msg: &SbiMessage) -> SbiReturn {
+            SbiReturn::success(self.0)
+        }
+    }
+
+    const MSG: SbiMessage = SbiMessage::Time(crate::TimeFunction::SetTimer { stime_value: 0 });
+
+    #[test]
+    fn send_with() {
+        // Safety: SetTimer doesn't touch memory.
+        assert_eq!(
+            unsafe { ecall_send_with::<i64>(&FixedReply(3), &MSG) },
+            Ok(3)
+        );
+    }
+
+    #[test]
+    fn scoped_override() {
+        let outer = override_transport(Rc::new(FixedReply(1)));
+        // Safety: SetTimer doesn't touch memory.
+        assert_eq!(unsafe { ecall_send::<i64>(&MSG) }, Ok(1));
+        {
+            let _inner = override_transport(Rc::new(FixedReply(2)));
+            assert_eq!(unsafe { ecall_send::<i64>(&MSG) }, Ok(2));
+        }
+        assert_eq!(unsafe { ecall_send::<i64>(&MSG) }, Ok(1));
+        drop(outer);
+        assert!(thread_override::current().is_none());
+    }
+}
@@ -0,0 +1,87 @@
+// SPDX-FileCopyrightText: 2023 Rivos Inc.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::consts::*;
+use crate::error::*;
+use crate::function::*;
+
+/// A vendor-specific SBI extension, carried in `SbiMessage::Vendor`.
+///
+/// Implementers describe how to encode their functions with `SbiFunction` and how to decode them
+/// with `from_regs`, and are then converted with `SbiMessage::from_vendor` and
+/// `SbiMessage::decode_vendor`. Enums deriving `SbiFunction` get this implementation by adding
+/// `#[sbi(vendor_eid = ...)]`. `EID` must lie in the vendor extension range, which is checked at
+/// compile time when either of those is used:
+///
+/// ```compile_fail
+/// use sbi_rs::{SbiFunction, SbiMessage, Result, VendorExtension};
+///
+/// struct NotVendor;
+///
+/// impl SbiFunction for NotVendor {}
+///
+/// impl VendorExtension for NotVendor {
+///     const EID: u64 = 0x10;
+///
+///     fn from_regs(_args: &[u64]) -> Result<Self> {
+///         Ok(NotVendor)
+///     }
+/// }
+///
+/// let _ = SbiMessage::from_vendor(&NotVendor);
+/// ```
+pub trait VendorExtension: SbiFunction + Sized {
+    /// The extension ID, in `EXT_VENDOR_RANGE_START..=EXT_VENDOR_RANGE_END`.
+    const EID: u64;
+
+    /// Attempts to parse `Self` from the passed in `a0-a7`. `a7` is guaranteed to be `EID`.
+    fn from_regs(args: &[u64]) -> Result<Self>;
+}
+
+// Fails to compile if `E::EID` is outside the vendor extension range.
+pub(crate) const fn assert_vendor_eid<E: VendorExtension>() {
+    const {
+        assert!(
+            E::EID >= EXT_VENDOR_RANGE_START && E::EID <= EXT_VENDOR_RANGE_END,
+            "vendor extension ID is outside the vendor extension range"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::salus::{MemCopyArgs, SalusTestFunction};
+    use crate::*;
+
+    #[test]
+    fn vendor_round_trip() {
+        let func = SalusTestFunction::MemCopy(MemCopyArgs {
+            to: 0x1000,
+            from: 0x2000,
+            len: 0x30,
+        });
+        let msg = SbiMessage::from_vendor(&func);
+        let regs = [
+            msg.a0(),
+            msg.a1(),
+            msg.a2(),
+            msg.a3(),
+            msg.a4(),
+            msg.a5(),
+            msg.a6(),
+            msg.a7(),
+        ];
+        let decoded = SbiMessage::from_regs(&regs)
+            .and_then(|m| m.decode_vendor::<SalusTestFunction>())
+            .unwrap();
+        let SalusTestFunction::MemCopy(args) = decoded;
+        assert_eq!((args.to, args.from, args.len), (0x1000, 0x2000, 0x30));
+    }
+
+    #[test]
+    fn decode_other_extension() {
+        let msg = SbiMessage::Vendor([0, 0, 0, 0, 0, 0, 0, EXT_VENDOR_RANGE_START]);
+        assert!(msg.decode_vendor::<SalusTestFunction>().is_err());
+    }
+}